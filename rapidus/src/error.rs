@@ -0,0 +1,75 @@
+use crate::parser::{self, Parser};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompileErrorKind {
+  /// Errors reported by the parser, and early errors such as redeclarations.
+  SyntaxError,
+  /// References to undeclared variables and functions, or to variables in their TDZ.
+  ReferenceError,
+  /// E.g. an assignment to a constant.
+  TypeError,
+  /// Valid JavaScript that the compiler does not support yet.
+  Unsupported,
+}
+
+/// An error in a script, found before it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileError {
+  pub kind: CompileErrorKind,
+  pub message: String,
+  /// Position in the source text.
+  pub pos: usize,
+}
+
+impl CompileError {
+  pub fn new(kind: CompileErrorKind, message: impl Into<String>, pos: usize) -> Self {
+    CompileError {
+      kind,
+      message: message.into(),
+      pos,
+    }
+  }
+
+  pub fn syntax_error(message: impl Into<String>, pos: usize) -> Self {
+    CompileError::new(CompileErrorKind::SyntaxError, message, pos)
+  }
+
+  pub fn reference_error(message: impl Into<String>, pos: usize) -> Self {
+    CompileError::new(CompileErrorKind::ReferenceError, message, pos)
+  }
+
+  pub fn type_error(message: impl Into<String>, pos: usize) -> Self {
+    CompileError::new(CompileErrorKind::TypeError, message, pos)
+  }
+
+  pub fn unsupported(message: impl Into<String>, pos: usize) -> Self {
+    CompileError::new(CompileErrorKind::Unsupported, message, pos)
+  }
+
+  /// An error of the parser, which is at `end` (the end of the script) if it is an unexpected EOF.
+  /// A script which could not be loaded has no end, so `end` is then 0.
+  pub fn from_parse_error(err: &parser::Error, end: usize) -> Self {
+    let (pos, message) = match err {
+      parser::Error::Expect(pos, msg)
+      | parser::Error::General(pos, msg)
+      | parser::Error::UnexpectedToken(pos, msg) => (*pos, msg.clone()),
+      parser::Error::UnexpectedEOF(msg) => (end, format!("unexpected EOF. {}", msg)),
+      parser::Error::InvalidToken(pos) => (*pos, "Invalid token.".to_string()),
+      parser::Error::UnsupportedFeature(pos) => (*pos, "Unsupported feature.".to_string()),
+      parser::Error::NormalEOF => unreachable!(),
+    };
+    CompileError::syntax_error(message, pos)
+  }
+
+  /// Prints the error with the source code around it.
+  pub fn show(&self, parser: &Parser) {
+    parser.show_error_of_kind_at(&format!("{:?}", self.kind), self.pos, self.message.clone());
+  }
+}
+
+impl fmt::Display for CompileError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?}: {}", self.kind, self.message)
+  }
+}
@@ -1,6 +1,16 @@
-use crate::node::{BinOp, FormalParameter, Node, NodeBase};
+use crate::error::CompileError;
+use crate::node::{
+  BinOp, CaseClause, FormalParameter, MethodDefinitionKind, Node, NodeBase, PatternElement,
+  PropertyDefinition, UnaryOp, VarKind,
+};
 use crate::parser;
+use crate::runtime::ic::{self, InlineCache, ObjectLayout};
+use crate::runtime::object::ObjectKind;
+use crate::runtime::{self, value, Constants};
+use crate::scope::{self, ScopeInfo};
+use crate::typing::{Ty, TypeInference, TypeInfo};
 use cilk::codegen::x64::exec::jit::{GenericValue, JITCompiler};
+use cilk::ir::basic_block::BasicBlockId;
 use cilk::ir::builder::Builder;
 pub use cilk::ir::function::FunctionId;
 pub use cilk::module::Module;
@@ -13,7 +23,10 @@ pub use cilk::{
     interpreter::interp::{ConcreteValue, Interpreter},
     jit::x64::regalloc::RegisterAllocator,
   },
-  ir::{opcode::ICmpKind, value::*},
+  ir::{
+    opcode::{FCmpKind, ICmpKind},
+    value::*,
+  },
 };
 
 use std::collections::HashMap;
@@ -21,39 +34,95 @@ use std::time::Instant;
 extern crate clap;
 extern crate libc;
 
-pub fn compile_file(file_name: impl Into<String>) -> Result<Module, String> {
+/// A compiled script: the cilk module, and the values its code refers to by address,
+/// which are freed with it.
+pub struct CompiledModule {
+  pub module: Module,
+  constants: Constants,
+}
+
+impl CompiledModule {
+  /// The inline caches of the code, whose hits and misses `ic::stats` counts.
+  pub fn inline_caches(&self) -> &[*mut InlineCache] {
+    self.constants.inline_caches()
+  }
+}
+
+pub fn compile_file(file_name: impl Into<String>) -> Result<CompiledModule, CompileError> {
   let mut parser = match parser::Parser::load_module(file_name.into()) {
     Ok(ok) => ok,
-    Err(err) => return Err(format!("{:?}", err)),
+    Err(err) => return Err(CompileError::from_parse_error(&err, 0)),
   };
+  compile(&mut parser)
+}
 
-  let node = match parser.parse_all() {
+/// Compiles the script loaded in `parser`. Errors are not printed but returned,
+/// so that the caller can render them with `CompileError::show`.
+pub fn compile(parser: &mut parser::Parser) -> Result<CompiledModule, CompileError> {
+  let mut node = match parser.parse_all() {
     Ok(ok) => ok,
-    Err(err) => {
-      parser.handle_error(&err);
-      return Err(format!("{:?}", err));
-    }
+    Err(err) => return Err(CompileError::from_parse_error(&err, parser.lexer.pos)),
   };
   //println!("{:?}", node);
 
+  check_labels(&node, &mut vec![])?;
+  let scope_info = scope::resolve_scopes("main", &mut node)?;
+
+  let type_info = TypeInference::new(&scope_info).run("main", &node);
+
   let mut module = module::Module::new("cilk");
   module.add_function(function::Function::new(
     "cilk.println.i32",
     types::Type::Void,
     vec![types::Type::Int32],
   ));
+  module.add_function(function::Function::new(
+    "cilk.println.f64",
+    types::Type::Void,
+    vec![types::Type::F64],
+  ));
+  runtime::declare_runtime_functions(&mut module);
   let mut func_queue: Vec<(FunctionId, Vec<FormalParameter>, Node)> = vec![];
-  let main = module.add_function(function::Function::new("main", types::Type::Int32, vec![]));
+  let main = module.add_function(function::Function::new("main", types::Type::F64, vec![]));
+  declare_functions(&mut module, &type_info, &scope_info);
   func_queue.push((main, vec![], node));
 
+  let pos_line_list = &parser.lexer.pos_line_list;
+  let mut constants = Constants::default();
   while let Some((function_id, params, node)) = func_queue.pop() {
-    let fc = FuncCompiler::new(&mut module, function_id);
-    let func_map = fc.compile(&params, &node);
+    let fc = FuncCompiler::new(
+      &mut module,
+      &mut constants,
+      &type_info,
+      &scope_info,
+      pos_line_list,
+      function_id,
+    );
+    let func_map = fc.compile(&params, &node)?;
     for func in &func_map {
       func_queue.push(func.clone());
     }
   }
 
+  // The call sites have declared the dispatchers they need.
+  let dispatchers: Vec<(FunctionId, usize)> = module
+    .functions
+    .iter()
+    .filter(|(_, func)| func.name.starts_with(DISPATCHER_PREFIX))
+    .map(|(id, func)| (id, func.name[DISPATCHER_PREFIX.len()..].parse().unwrap()))
+    .collect();
+  for (function_id, argc) in dispatchers {
+    let fc = FuncCompiler::new(
+      &mut module,
+      &mut constants,
+      &type_info,
+      &scope_info,
+      pos_line_list,
+      function_id,
+    );
+    fc.compile_dispatcher(argc)?;
+  }
+
   #[cfg(debug_assertions)]
   {
     for (f_id, func) in &module.functions {
@@ -62,11 +131,11 @@ pub fn compile_file(file_name: impl Into<String>) -> Result<Module, String> {
   };
 
   RegisterAllocator::new(&module).analyze();
-  Ok(module)
+  Ok(CompiledModule { module, constants })
 }
 
-pub fn execute_jit(m: &mut Module) -> Result<GenericValue, String> {
-  let mut dag_module = dag::convert::ConvertToDAG::new(&m).convert_module();
+pub fn execute_jit(m: &mut CompiledModule) -> Result<GenericValue, String> {
+  let mut dag_module = dag::convert::ConvertToDAG::new(&m.module).convert_module();
   dag::combine::Combine::new().combine_module(&mut dag_module);
   /*
   for (_, dag_func) in &dag_module.functions {
@@ -101,42 +170,399 @@ pub fn execute_jit(m: &mut Module) -> Result<GenericValue, String> {
   */
 
   let mut jit = JITCompiler::new(&machine_module);
+  runtime::register_to_jit(&mut jit);
   jit.compile_module();
   let func = machine_module.find_function_by_name("main").unwrap();
   let now = Instant::now();
   let ret = jit.run(func, vec![GenericValue::Int32(0)]);
   println!("duration: {:?}", Instant::now().duration_since(now));
+  check_uncaught_exception()?;
   Ok(ret)
 }
 
-pub fn execute_interpreter(module: &mut Module) -> Result<ConcreteValue, String> {
-  let main = module.find_function_by_name("main").unwrap();
-  let mut interp = Interpreter::new(&module);
+pub fn execute_interpreter(m: &mut CompiledModule) -> Result<ConcreteValue, String> {
+  let main = m.module.find_function_by_name("main").unwrap();
+  let mut interp = Interpreter::new(&m.module);
+  runtime::register_to_interpreter(&mut interp);
   let ret = interp.run_function(main, vec![ConcreteValue::Int32(9)]);
+  check_uncaught_exception()?;
   Ok(ret)
 }
 
+/// Reports an exception that the script threw and never caught, which stopped it.
+fn check_uncaught_exception() -> Result<(), String> {
+  match runtime::exception::take() {
+    Some(e) => Err(format!(
+      "Uncaught exception thrown at line {}: {}",
+      e.line,
+      e.value.inspect()
+    )),
+    None => Ok(()),
+  }
+}
+
+/// The name of the dispatcher for indirect calls with a number of arguments, without the number
+/// (see `FuncCompiler::compile_dispatcher`).
+const DISPATCHER_PREFIX: &str = "rapidus.dispatch.";
+
+/// Declares every function but `main`, so that any function can refer to any other.
+/// Nested functions take the environment record of the enclosing function and `this` first,
+/// and the `arguments` object last if they use it.
+fn declare_functions(module: &mut Module, type_info: &TypeInfo, scope_info: &ScopeInfo) {
+  let mut names: Vec<&String> = type_info
+    .functions
+    .keys()
+    .filter(|name| name.contains('.'))
+    .collect();
+  names.sort();
+  for name in names {
+    let types = type_info.function(name);
+    let mut param_types = vec![types::Type::Int64, types::Type::Int64];
+    param_types.extend(types.param_types());
+    if scope_info.function(name).uses_arguments {
+      param_types.push(types::Type::Int64);
+    }
+    module.add_function(function::Function::new(
+      name.as_str(),
+      types.ret.to_cilk_type(),
+      param_types,
+    ));
+  }
+}
+
+/// Returns the value of a `case` test that is an integer literal fitting in 32 bits.
+fn case_int(test: &Node) -> Option<i32> {
+  let n = match &test.base {
+    NodeBase::Number(n) => *n,
+    NodeBase::UnaryOp(expr, UnaryOp::Minus) => match expr.base {
+      NodeBase::Number(n) => -n,
+      _ => return None,
+    },
+    _ => return None,
+  };
+  if Ty::of_number(n) == Ty::Int32 {
+    Some(n as i32)
+  } else {
+    None
+  }
+}
+
+/// Whether `node` is a loop statement. The parser wraps a `for` statement in a block.
+fn is_loop(node: &Node) -> bool {
+  match &node.base {
+    NodeBase::While(_, _) | NodeBase::DoWhile(_, _) | NodeBase::For(_, _, _, _) => true,
+    NodeBase::ForIn(_, _, _) | NodeBase::ForOf(_, _, _) => true,
+    NodeBase::Block(nodes) if nodes.len() == 1 => is_loop(&nodes[0]),
+    NodeBase::Label(_, body) => is_loop(body),
+    _ => false,
+  }
+}
+
+/// Reports `break` and `continue` statements which have no valid target.
+/// https://tc39.github.io/ecma262/#sec-labelled-statements-static-semantics-early-errors
+/// `labels` holds the enclosing labels and whether each of them is attached to a loop.
+/// `None` entries stand for unlabeled loops and `switch` statements.
+fn check_labels(node: &Node, labels: &mut Vec<(Option<String>, bool)>) -> Result<(), CompileError> {
+  match &node.base {
+    NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => {
+      for node in nodes {
+        check_labels(node, labels)?;
+      }
+    }
+    // Labels are not visible in nested functions.
+    NodeBase::FunctionDecl(_, params, body)
+    | NodeBase::FunctionExpr(_, params, body)
+    | NodeBase::ArrowFunction(params, body) => {
+      for param in params {
+        if let Some(init) = &param.init {
+          check_labels(init, &mut vec![])?;
+        }
+      }
+      check_labels(body, &mut vec![])?;
+    }
+    NodeBase::If(cond, then_, else_) => {
+      check_labels(cond, labels)?;
+      check_labels(then_, labels)?;
+      check_labels(else_, labels)?;
+    }
+    NodeBase::While(cond, body) | NodeBase::DoWhile(body, cond) => {
+      check_labels(cond, labels)?;
+      labels.push((None, true));
+      check_labels(body, labels)?;
+      labels.pop();
+    }
+    NodeBase::For(init, cond, step, body) => {
+      check_labels(init, labels)?;
+      check_labels(cond, labels)?;
+      check_labels(step, labels)?;
+      labels.push((None, true));
+      check_labels(body, labels)?;
+      labels.pop();
+    }
+    NodeBase::ForIn(binding, target, body) | NodeBase::ForOf(binding, target, body) => {
+      check_labels(binding, labels)?;
+      check_labels(target, labels)?;
+      labels.push((None, true));
+      check_labels(body, labels)?;
+      labels.pop();
+    }
+    NodeBase::Switch(discriminant, clauses) => {
+      check_labels(discriminant, labels)?;
+      labels.push((None, false));
+      for clause in clauses {
+        if let Some(test) = &clause.test {
+          check_labels(test, labels)?;
+        }
+        for node in &clause.body {
+          check_labels(node, labels)?;
+        }
+      }
+      labels.pop();
+    }
+    NodeBase::Try(try_, catch, _, finally) => {
+      check_labels(try_, labels)?;
+      check_labels(catch, labels)?;
+      check_labels(finally, labels)?;
+    }
+    NodeBase::Label(name, body) => {
+      if labels.iter().any(|(label, _)| label.as_ref() == Some(name)) {
+        return Err(CompileError::syntax_error(
+          format!("Label '{}' has already been declared", name),
+          node.pos,
+        ));
+      }
+      labels.push((Some(name.clone()), is_loop(body)));
+      check_labels(body, labels)?;
+      labels.pop();
+    }
+    NodeBase::Break(None) => {
+      if !labels.iter().any(|(label, is_loop)| *is_loop || label.is_none()) {
+        return Err(CompileError::syntax_error("Illegal break statement", node.pos));
+      }
+    }
+    NodeBase::Continue(None) => {
+      if !labels.iter().any(|(_, is_loop)| *is_loop) {
+        return Err(CompileError::syntax_error(
+          "Illegal continue statement: no surrounding iteration statement",
+          node.pos,
+        ));
+      }
+    }
+    NodeBase::Break(Some(name)) | NodeBase::Continue(Some(name)) => {
+      let is_continue = match node.base {
+        NodeBase::Continue(_) => true,
+        _ => false,
+      };
+      match labels.iter().rev().find(|(label, _)| label.as_ref() == Some(name)) {
+        None => {
+          return Err(CompileError::syntax_error(
+            format!("Undefined label '{}'", name),
+            node.pos,
+          ));
+        }
+        Some((_, false)) if is_continue => {
+          return Err(CompileError::syntax_error(
+            format!(
+              "Illegal continue statement: '{}' does not denote an iteration statement",
+              name
+            ),
+            node.pos,
+          ));
+        }
+        Some(_) => {}
+      }
+    }
+    // Expressions are only searched for function expressions and arrow functions.
+    NodeBase::VarDecl(_, Some(expr), _)
+    | NodeBase::ForBinding(expr, _)
+    | NodeBase::Member(expr, _)
+    | NodeBase::New(expr)
+    | NodeBase::Throw(expr)
+    | NodeBase::Return(Some(expr))
+    | NodeBase::UnaryOp(expr, _) => check_labels(expr, labels)?,
+    NodeBase::Index(lhs, rhs) | NodeBase::Assign(lhs, rhs) | NodeBase::BinaryOp(lhs, rhs, _) => {
+      check_labels(lhs, labels)?;
+      check_labels(rhs, labels)?;
+    }
+    NodeBase::TernaryOp(cond, then_, else_) => {
+      check_labels(cond, labels)?;
+      check_labels(then_, labels)?;
+      check_labels(else_, labels)?;
+    }
+    NodeBase::Call(callee, args) => {
+      check_labels(callee, labels)?;
+      for arg in args {
+        check_labels(arg, labels)?;
+      }
+    }
+    NodeBase::Array(elems) | NodeBase::TemplateLiteral(_, elems) => {
+      for elem in elems {
+        check_labels(elem, labels)?;
+      }
+    }
+    NodeBase::Object(properties) => {
+      for property in properties {
+        match property {
+          PropertyDefinition::IdentifierReference(_) => {}
+          PropertyDefinition::Property(_, value)
+          | PropertyDefinition::MethodDefinition(_, _, value) => check_labels(value, labels)?,
+        }
+      }
+    }
+    NodeBase::ArrayPattern(elements) => {
+      for element in elements.iter().flatten() {
+        check_pattern_element_labels(element, labels)?;
+      }
+    }
+    NodeBase::ObjectPattern(properties) => {
+      for (_, element) in properties {
+        check_pattern_element_labels(element, labels)?;
+      }
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+fn check_pattern_element_labels(
+  element: &PatternElement,
+  labels: &mut Vec<(Option<String>, bool)>,
+) -> Result<(), CompileError> {
+  check_labels(&element.target, labels)?;
+  match &element.init {
+    Some(init) => check_labels(init, labels),
+    None => Ok(()),
+  }
+}
+
+fn is_typeof_comparison(lhs: &Node, rhs: &Node, op: &BinOp) -> bool {
+  let is_typeof = |node: &Node| match node.base {
+    NodeBase::UnaryOp(_, UnaryOp::Typeof) => true,
+    _ => false,
+  };
+  let is_string = |node: &Node| match node.base {
+    NodeBase::String(_) => true,
+    _ => false,
+  };
+  match op {
+    BinOp::Eq | BinOp::SEq | BinOp::Ne | BinOp::SNe => {
+      (is_typeof(lhs) && is_string(rhs)) || (is_string(lhs) && is_typeof(rhs))
+    }
+    _ => false,
+  }
+}
+
+fn not_defined(name: &String, pos: usize) -> CompileError {
+  CompileError::reference_error(format!("{} is not defined", name), pos)
+}
+
+fn boxed_immediate(v: value::BoxedValue) -> Value {
+  Value::Immediate(ImmediateValue::Int64(v.0 as i64))
+}
+
 #[derive(Debug)]
 pub struct FuncCompiler<'a> {
-  function_id: FunctionId,
   function_name: String,
   builder: Builder<'a>,
+  /// String literals and inline caches, owned by the module.
+  constants: &'a mut Constants,
+  type_info: &'a TypeInfo,
+  scope_info: &'a ScopeInfo,
   variable_map: HashMap<String, Value>,
   arguments_map: HashMap<String, usize>,
   function_map: HashMap<String, (FunctionId, Vec<FormalParameter>, Node)>,
+  break_targets: Vec<BreakTarget>,
+  /// Labels to be attached to the loop compiled next.
+  pending_labels: Vec<String>,
+  /// The environment record of this function call, created if the function has nested functions.
+  env: Option<Value>,
+  /// The environment record of the enclosing function, passed as the first parameter.
+  parent_env: Option<Value>,
+  /// The height of the GC shadow stack when this call was entered (see `runtime::gc`).
+  frame_height: Value,
+  /// The `try` statements whose block or `catch` clause is being compiled, from the outermost one.
+  try_contexts: Vec<TryContext>,
+  /// The block returning from this call when an exception is not caught in it,
+  /// created on first use.
+  unwind_bb: Option<BasicBlockId>,
+  /// Variables holding the value and the line of an exception while a `finally` clause runs,
+  /// for each `try` statement with a `finally` clause (keyed by its position).
+  exception_slots: HashMap<usize, (Value, Value)>,
+  /// Variables holding the iterator of each for-in/of statement (keyed by its position).
+  iterator_slots: HashMap<usize, Value>,
+  /// Variables holding the environment record of each block with captured bindings
+  /// (keyed by its position, see `FunctionScope::blocks`).
+  env_slots: HashMap<usize, Value>,
+  /// The blocks with an environment record enclosing the code being compiled,
+  /// from the outermost one.
+  block_envs: Vec<usize>,
+  /// Correspondence between positions and line numbers in the script, for thrown exceptions.
+  pos_line_list: &'a [(usize, usize)],
+}
+
+/// A statement that `break` (and `continue`, for loops) can jump out of.
+#[derive(Debug)]
+struct BreakTarget {
+  labels: Vec<String>,
+  break_bb: BasicBlockId,
+  /// `None` if the statement is not a loop but a labeled statement or a `switch` statement.
+  continue_bb: Option<BasicBlockId>,
+  /// Whether an unlabeled `break` jumps out of the statement, i.e. it is a loop or a `switch`.
+  accepts_unlabeled_break: bool,
+}
+
+/// The block or `catch` clause of a `try` statement, which catches exceptions thrown in it.
+#[derive(Debug)]
+struct TryContext {
+  /// Where exceptions go: the `catch` clause, or code running the `finally` clause and rethrowing.
+  handler_bb: BasicBlockId,
+  /// The `finally` clause, which runs when `return`, `break` or `continue` leaves the statement.
+  finally: Option<Node>,
+  /// The number of break targets enclosing the statement.
+  break_depth: usize,
+  /// The number of blocks with an environment record enclosing the statement.
+  block_env_depth: usize,
+}
+
+/// The operand of `typeof`, whose type name is known statically unless it is boxed.
+enum TypeofOperand {
+  /// The index of the type name in `value::TYPEOF_NAMES`.
+  Known(usize),
+  Dynamic(Value),
 }
 
 impl<'a> FuncCompiler<'a> {
-  pub fn new(module: &'a mut module::Module, function_id: FunctionId) -> Self {
+  pub fn new(
+    module: &'a mut module::Module,
+    constants: &'a mut Constants,
+    type_info: &'a TypeInfo,
+    scope_info: &'a ScopeInfo,
+    pos_line_list: &'a [(usize, usize)],
+    function_id: FunctionId,
+  ) -> Self {
     let function_name = module.function_ref(function_id).name.clone();
     let builder = Builder::new(module, function_id);
     FuncCompiler {
-      function_id,
       function_name,
       builder,
+      constants,
+      type_info,
+      scope_info,
       variable_map: HashMap::default(),
       arguments_map: HashMap::default(),
       function_map: HashMap::default(),
+      break_targets: vec![],
+      pending_labels: vec![],
+      env: None,
+      parent_env: None,
+      frame_height: Value::None,
+      try_contexts: vec![],
+      unwind_bb: None,
+      exception_slots: HashMap::default(),
+      iterator_slots: HashMap::default(),
+      env_slots: HashMap::default(),
+      block_envs: vec![],
+      pos_line_list,
     }
   }
 
@@ -146,68 +572,192 @@ impl<'a> FuncCompiler<'a> {
 
   pub fn compile(
     mut self,
-    params: &Vec<FormalParameter>,
+    params: &[FormalParameter],
     node: &'a Node,
-  ) -> Vec<(FunctionId, Vec<FormalParameter>, Node)> {
+  ) -> Result<Vec<(FunctionId, Vec<FormalParameter>, Node)>, CompileError> {
     self.set_arguments(params);
     let entry = self.builder.append_basic_block();
     self.builder.set_insert_point(entry);
-    self.collect_var_decl(node);
-    let _v = self.visit(node);
-    self
-      .builder
-      .build_ret(Value::Immediate(ImmediateValue::Int32(0)));
-    self.function_map.into_iter().map(|x| x.1).collect()
+    self.frame_height = self.call_runtime("rapidus.rt.frame_enter", vec![]);
+    self.build_environment();
+    self.build_parameters(params)?;
+    self.collect_var_decl(node)?;
+    self.visit(node)?;
+    // Falling off the end returns `undefined`. The block is unreachable if the function ends
+    // with `return` on every path, in which case the return type may be other than `Dynamic`.
+    self.build_return(boxed_immediate(value::UNDEFINED), node.pos)?;
+    if let Some(unwind_bb) = self.unwind_bb {
+      // The caller sees the pending exception, and ignores the return value.
+      self.builder.set_insert_point(unwind_bb);
+      let ret_ty = self.type_info.function(&self.function_name).ret.to_cilk_type();
+      let ret_v = self.uninitialized_value(ret_ty);
+      self.build_return(ret_v, node.pos)?;
+    }
+    Ok(self.function_map.into_iter().map(|x| x.1).collect())
   }
 
-  pub fn set_arguments(&mut self, params: &Vec<FormalParameter>) {
+  pub fn set_arguments(&mut self, params: &[FormalParameter]) {
+    // Nested functions take the environment record of the enclosing function and `this` first.
+    let scope = self.scope_info.function(&self.function_name);
+    if scope.parent.is_none() {
+      return;
+    }
+    self.arguments_map.insert("this".to_string(), 1);
     for (i, param) in params.iter().enumerate() {
-      self.arguments_map.insert(param.name.clone(), i);
+      self.arguments_map.insert(param.name.clone(), i + 2);
+    }
+    if scope.uses_arguments {
+      self
+        .arguments_map
+        .insert("arguments".to_string(), params.len() + 2);
+    }
+  }
+
+  /// Creates the environment record of this call, and moves captured parameters into it.
+  fn build_environment(&mut self) {
+    let scope_info = self.scope_info;
+    let scope = scope_info.function(&self.function_name);
+    if scope.parent.is_some() {
+      self.parent_env = Some(self.builder.get_param(0).unwrap());
+    }
+    if !scope.has_children {
+      return;
+    }
+    let parent_env = self
+      .parent_env
+      .unwrap_or(Value::Immediate(ImmediateValue::Int64(0)));
+    let len = Value::Immediate(ImmediateValue::Int32(scope.captured.len() as i32));
+    let env = self.call_runtime("rapidus.rt.env_new", vec![parent_env, len]);
+    self.env = Some(env);
+    self.build_tdz_slots(env, &scope.captured);
+    let mut params: Vec<(String, usize)> = self
+      .arguments_map
+      .iter()
+      .filter(|(name, _)| scope.captured.contains(*name))
+      .map(|(name, i)| (name.clone(), *i))
+      .collect();
+    params.sort_by_key(|(_, i)| *i);
+    for (name, i) in params {
+      let v = self.builder.get_param(i).unwrap();
+      self.store_variable(&name, v, 0).unwrap();
+    }
+  }
+
+  /// Moves the parameters which are not captured into variables, so that they can be assigned,
+  /// and replaces `undefined` arguments with the default values of their parameters.
+  fn build_parameters(&mut self, params: &[FormalParameter]) -> Result<(), CompileError> {
+    for param in params {
+      let arg_v = self.builder.get_param(self.arguments_map[&param.name]).unwrap();
+      let ty = self.var_type(&param.name);
+      if !self.is_captured(&param.name) {
+        let var_v = self.builder.build_alloca(ty.clone());
+        self.variable_map.insert(param.name.clone(), var_v);
+        let v = self.coerce(arg_v, ty.clone(), 0)?;
+        self.store_variable(&param.name, v, 0)?;
+      }
+      let init = match &param.init {
+        // Only a dynamically typed argument may be `undefined`.
+        Some(init) if self.get_type(arg_v) == types::Type::Int64 => init,
+        _ => continue,
+      };
+      let default_bb = self.builder.append_basic_block();
+      let cont_bb = self.builder.append_basic_block();
+      let undefined = boxed_immediate(value::UNDEFINED);
+      let is_undefined = self.builder.build_icmp(ICmpKind::Eq, arg_v, undefined);
+      self.builder.build_cond_br(is_undefined, default_bb, cont_bb);
+      self.builder.set_insert_point(default_bb);
+      let init_v = self.visit(init)?;
+      let init_v = self.coerce(init_v, ty, init.pos)?;
+      self.store_variable(&param.name, init_v, init.pos)?;
+      self.builder.build_br(cont_bb);
+      self.builder.set_insert_point(cont_bb);
     }
+    Ok(())
   }
 
-  pub fn collect_var_decl(&mut self, node: &'a crate::node::Node) {
+  pub fn collect_var_decl(&mut self, node: &'a crate::node::Node) -> Result<(), CompileError> {
     match &node.base {
       NodeBase::StatementList(nodes) => {
         for node in nodes {
-          self.collect_var_decl(&node);
+          self.collect_var_decl(&node)?;
         }
       }
       NodeBase::Block(nodes) => {
+        self.declare_block_env(node.pos);
         for node in nodes {
-          self.collect_var_decl(&node);
+          self.collect_var_decl(&node)?;
         }
       }
-      NodeBase::VarDecl(name, _init, _kind) => {
-        if self.variable_map.contains_key(name) {
-          panic!("duplicated declaration of variable: named {:?}", name);
-        } else {
-          let v = self.builder.build_alloca(types::Type::Int32);
-          self.variable_map.insert(name.clone(), v);
+      NodeBase::VarDecl(name, _init, kind) => self.declare_variable(name, kind),
+      NodeBase::ForBinding(target, kind) => {
+        for name in target.bound_names() {
+          self.declare_variable(name, kind);
         }
       }
       NodeBase::If(_, then_, else_) => {
-        self.collect_var_decl(&then_);
-        self.collect_var_decl(&else_);
+        self.collect_var_decl(&then_)?;
+        self.collect_var_decl(&else_)?;
       }
       NodeBase::While(_, body) => {
-        self.collect_var_decl(&body);
+        self.collect_var_decl(&body)?;
+      }
+      NodeBase::DoWhile(body, _) => {
+        self.collect_var_decl(&body)?;
+      }
+      NodeBase::Label(_, body) => {
+        self.collect_var_decl(&body)?;
+      }
+      NodeBase::Try(try_, catch, param, finally) => {
+        self.collect_var_decl(&try_)?;
+        self.declare_block_env(node.pos);
+        if let NodeBase::Identifier(name) = &param.base {
+          if !self.is_captured(name) {
+            let ty = self.var_type(name);
+            let v = self.builder.build_alloca(ty);
+            self.builder.build_store(boxed_immediate(value::UNDEFINED), v);
+            self.variable_map.insert(name.clone(), v);
+          }
+        }
+        self.collect_var_decl(&catch)?;
+        self.collect_var_decl(&finally)?;
+        if finally.base != NodeBase::Nope {
+          let value_v = self.builder.build_alloca(types::Type::Int64);
+          self.builder.build_store(boxed_immediate(value::UNDEFINED), value_v);
+          let line_v = self.builder.build_alloca(types::Type::Int32);
+          self.exception_slots.insert(node.pos, (value_v, line_v));
+        }
       }
       NodeBase::For(init, _, step, body) => {
-        self.collect_var_decl(&init);
-        self.collect_var_decl(&step);
-        self.collect_var_decl(&body);
+        self.collect_var_decl(&init)?;
+        self.collect_var_decl(&step)?;
+        self.collect_var_decl(&body)?;
+      }
+      NodeBase::ForIn(binding, _, body) | NodeBase::ForOf(binding, _, body) => {
+        let iter_v = self.builder.build_alloca(types::Type::Int64);
+        self
+          .builder
+          .build_store(boxed_immediate(value::UNDEFINED), iter_v);
+        self.iterator_slots.insert(node.pos, iter_v);
+        self.collect_var_decl(&binding)?;
+        self.collect_var_decl(&body)?;
+      }
+      NodeBase::Switch(_, clauses) => {
+        self.declare_block_env(node.pos);
+        for clause in clauses {
+          for node in &clause.body {
+            self.collect_var_decl(&node)?;
+          }
+        }
       }
       NodeBase::FunctionDecl(name, params, body) => {
         if self.function_map.contains_key(name) {
-          panic!("duplicated declaration of function: named {:?}", name);
+          return Err(CompileError::unsupported(
+            format!("Redeclaration of function '{}' is not supported yet", name),
+            node.pos,
+          ));
         } else {
           let decl_function_name = format!("{}.{}", self.function_name, name);
-          let func_id = self.builder.module.add_function(function::Function::new(
-            decl_function_name.as_str(),
-            types::Type::Int32,
-            vec![types::Type::Int32; params.len()],
-          ));
+          let func_id = self.find_function(&decl_function_name);
           self
             .function_map
             .insert(name.clone(), (func_id, params.clone(), *body.clone()));
@@ -215,80 +765,330 @@ impl<'a> FuncCompiler<'a> {
       }
       _ => {}
     }
+    Ok(())
   }
 
-  pub fn visit(&mut self, node: &Node) -> Value {
-    match &node.base {
+  fn declare_variable(&mut self, name: &String, kind: &VarKind) {
+    // `let` and `const` declarations have unique names after `scope::resolve_scopes`,
+    // while `var` may be declared twice.
+    // Captured variables live in the environment record instead.
+    if !self.variable_map.contains_key(name) && !self.is_captured(name) {
+      let ty = self.var_type(name);
+      let v = self.builder.build_alloca(ty.clone());
+      // A hoisted `var` is `undefined` until its declaration is evaluated.
+      // Boxed variables are always initialized, since the GC reads them at loop headers.
+      if *kind == VarKind::Var || ty == types::Type::Int64 {
+        let init_v = self.uninitialized_value(ty);
+        self.builder.build_store(init_v, v);
+      }
+      self.variable_map.insert(name.clone(), v);
+    }
+  }
+
+  /// Allocates the variable holding the environment record of the block at `pos`,
+  /// if it holds captured bindings.
+  fn declare_block_env(&mut self, pos: usize) {
+    let scope = self.scope_info.function(&self.function_name);
+    if scope.blocks.contains_key(&pos) {
+      let v = self.builder.build_alloca(types::Type::Int64);
+      self.env_slots.insert(pos, v);
+    }
+  }
+
+  pub fn visit(&mut self, node: &Node) -> Result<Value, CompileError> {
+    let v = match &node.base {
       NodeBase::StatementList(nodes) => {
         let mut v = Value::None;
         for node in nodes {
-          v = self.visit(node);
+          v = self.visit(node)?;
         }
         v
       }
       NodeBase::Block(nodes) => {
+        let has_env = self.enter_block_env(node.pos);
         let mut v = Value::None;
         for node in nodes {
-          v = self.visit(node);
+          v = self.visit(node)?;
+        }
+        if has_env {
+          self.block_envs.pop();
         }
         v
       }
+      NodeBase::BinaryOp(lhs, rhs, op) if is_typeof_comparison(lhs, rhs, op) => {
+        self.build_typeof_comparison(lhs, rhs, op)?
+      }
       NodeBase::BinaryOp(lhs, rhs, op) => match op {
         BinOp::Add => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          if self.is_dynamic(lhs_v) || self.is_dynamic(rhs_v) {
+            let lhs_v = self.coerce(lhs_v, types::Type::Int64, node.pos)?;
+            let rhs_v = self.coerce(rhs_v, types::Type::Int64, node.pos)?;
+            return Ok(self.call_runtime("rapidus.rt.add", vec![lhs_v, rhs_v]));
+          }
+          let ty = self.arithmetic_type(node);
+          let lhs_v = self.coerce(lhs_v, ty.clone(), node.pos)?;
+          let rhs_v = self.coerce(rhs_v, ty, node.pos)?;
           self.builder.build_add(lhs_v, rhs_v)
         }
         BinOp::Sub => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let ty = self.arithmetic_type(node);
+          let lhs_v = self.coerce(lhs_v, ty.clone(), node.pos)?;
+          let rhs_v = self.coerce(rhs_v, ty, node.pos)?;
           self.builder.build_sub(lhs_v, rhs_v)
         }
         BinOp::Mul => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let ty = self.arithmetic_type(node);
+          let lhs_v = self.coerce(lhs_v, ty.clone(), node.pos)?;
+          let rhs_v = self.coerce(rhs_v, ty, node.pos)?;
           self.builder.build_mul(lhs_v, rhs_v)
         }
         BinOp::Rem => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let ty = self.arithmetic_type(node);
+          let lhs_v = self.coerce(lhs_v, ty.clone(), node.pos)?;
+          let rhs_v = self.coerce(rhs_v, ty, node.pos)?;
           self.builder.build_rem(lhs_v, rhs_v)
         }
+        BinOp::Div => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.coerce(lhs_v, types::Type::F64, node.pos)?;
+          let rhs_v = self.coerce(rhs_v, types::Type::F64, node.pos)?;
+          self.builder.build_div(lhs_v, rhs_v)
+        }
+        BinOp::Exp => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.coerce(lhs_v, types::Type::F64, node.pos)?;
+          let rhs_v = self.coerce(rhs_v, types::Type::F64, node.pos)?;
+          self.call_runtime("rapidus.rt.pow", vec![lhs_v, rhs_v])
+        }
         BinOp::Eq => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
-          self.builder.build_icmp(ICmpKind::Eq, lhs_v, rhs_v)
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Eq, FCmpKind::OEq, "rapidus.rt.eq", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::Ne => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Ne, FCmpKind::UNe, "rapidus.rt.ne", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::SEq => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let (lhs_v, rhs_v) = self.box_if_types_differ(lhs_v, rhs_v, node.pos)?;
+          self.build_compare(ICmpKind::Eq, FCmpKind::OEq, "rapidus.rt.seq", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::SNe => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let (lhs_v, rhs_v) = self.box_if_types_differ(lhs_v, rhs_v, node.pos)?;
+          self.build_compare(ICmpKind::Ne, FCmpKind::UNe, "rapidus.rt.sne", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::Lt => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Lt, FCmpKind::OLt, "rapidus.rt.lt", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::Gt => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Gt, FCmpKind::OGt, "rapidus.rt.gt", lhs_v, rhs_v, node.pos)?
         }
         BinOp::Le => {
-          let lhs_v = self.visit(lhs);
-          let rhs_v = self.visit(rhs);
-          self.builder.build_icmp(ICmpKind::Le, lhs_v, rhs_v)
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Le, FCmpKind::OLe, "rapidus.rt.le", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::Ge => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_compare(ICmpKind::Ge, FCmpKind::OGe, "rapidus.rt.ge", lhs_v, rhs_v, node.pos)?
+        }
+        BinOp::And => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_int32(rhs_v, node.pos)?;
+          self.builder.build_and(lhs_v, rhs_v)
+        }
+        BinOp::Or => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_int32(rhs_v, node.pos)?;
+          self.builder.build_or(lhs_v, rhs_v)
+        }
+        BinOp::Xor => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_int32(rhs_v, node.pos)?;
+          self.builder.build_xor(lhs_v, rhs_v)
+        }
+        BinOp::Shl => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_shift_count(rhs_v, node.pos)?;
+          self.builder.build_shl(lhs_v, rhs_v)
+        }
+        BinOp::Shr => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_shift_count(rhs_v, node.pos)?;
+          self.builder.build_ashr(lhs_v, rhs_v)
+        }
+        BinOp::ZFShr => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.to_int32(lhs_v, node.pos)?;
+          let rhs_v = self.to_shift_count(rhs_v, node.pos)?;
+          let v = self.builder.build_lshr(lhs_v, rhs_v);
+          // The result is an unsigned 32-bit integer, which may not fit in Int32.
+          let v = self.builder.build_zext(v, types::Type::Int64);
+          self.builder.build_sitofp(v, types::Type::F64)
+        }
+        BinOp::InstanceOf => {
+          let lhs_v = self.visit(lhs)?;
+          let rhs_v = self.visit(rhs)?;
+          let lhs_v = self.coerce(lhs_v, types::Type::Int64, node.pos)?;
+          let rhs_v = self.coerce(rhs_v, types::Type::Int64, node.pos)?;
+          let v = self.call_runtime("rapidus.rt.instance_of", vec![lhs_v, rhs_v]);
+          self.build_exception_check(node.pos);
+          let zero = Value::Immediate(ImmediateValue::Int32(0));
+          self.builder.build_icmp(ICmpKind::Ne, v, zero)
+        }
+        BinOp::LAnd | BinOp::LOr => self.build_logical(lhs, rhs, op)?,
+        BinOp::Comma => {
+          self.visit(lhs)?;
+          self.visit(rhs)?
+        }
+        _ => {
+          return Err(CompileError::unsupported(
+            format!("Operator {:?} is not supported yet", op),
+            node.pos,
+          ));
+        }
+      },
+      NodeBase::UnaryOp(expr, op) => match op {
+        UnaryOp::PrInc | UnaryOp::PrDec | UnaryOp::PoInc | UnaryOp::PoDec => {
+          let is_inc = *op == UnaryOp::PrInc || *op == UnaryOp::PoInc;
+          let is_prefix = *op == UnaryOp::PrInc || *op == UnaryOp::PrDec;
+          let is_int32 = self.type_info.int32_unary_ops.contains(&node.pos);
+          self.build_update(expr, is_inc, is_prefix, is_int32)?
+        }
+        UnaryOp::Plus => {
+          let v = self.visit(expr)?;
+          self.to_number(v, node.pos)?
+        }
+        UnaryOp::Minus if self.type_info.int32_unary_ops.contains(&node.pos) => match expr.base {
+          NodeBase::Number(x) => Value::Immediate(ImmediateValue::Int32(-x as i32)),
+          _ => {
+            let v = self.visit(expr)?;
+            let v = self.coerce(v, types::Type::Int32, node.pos)?;
+            let zero = Value::Immediate(ImmediateValue::Int32(0));
+            self.builder.build_sub(zero, v)
+          }
+        },
+        UnaryOp::Minus => match expr.base {
+          NodeBase::Number(x) => Value::Immediate(ImmediateValue::F64(-x)),
+          _ => {
+            let v = self.visit(expr)?;
+            let v = self.coerce(v, types::Type::F64, node.pos)?;
+            // Multiplying keeps the sign of zero: -(0) is -0.
+            let minus_one = Value::Immediate(ImmediateValue::F64(-1.0));
+            self.builder.build_mul(v, minus_one)
+          }
+        },
+        UnaryOp::Not => {
+          let v = self.visit(expr)?;
+          let v = self.coerce(v, types::Type::Int1, node.pos)?;
+          let false_ = Value::Immediate(ImmediateValue::Int1(false));
+          self.builder.build_icmp(ICmpKind::Eq, v, false_)
+        }
+        UnaryOp::BitwiseNot => {
+          let v = self.visit(expr)?;
+          let v = self.to_int32(v, node.pos)?;
+          let minus_one = Value::Immediate(ImmediateValue::Int32(-1));
+          self.builder.build_xor(v, minus_one)
         }
-        _ => unimplemented!("{:?}", op),
+        UnaryOp::Void => {
+          self.visit(expr)?;
+          boxed_immediate(value::UNDEFINED)
+        }
+        UnaryOp::Typeof => match self.visit_typeof_operand(expr)? {
+          TypeofOperand::Known(code) => self.string_literal(&value::TYPEOF_NAMES[code].to_string()),
+          TypeofOperand::Dynamic(v) => self.call_runtime("rapidus.rt.typeof_string", vec![v]),
+        },
+        UnaryOp::Delete => match &expr.base {
+          NodeBase::Member(obj, name) => {
+            let obj_v = self.visit(obj)?;
+            let name_v = self.string_literal(name);
+            self.build_delete(obj_v, name_v, node.pos)?
+          }
+          NodeBase::Index(obj, key) => {
+            let obj_v = self.visit(obj)?;
+            let key_v = self.visit(key)?;
+            self.build_delete(obj_v, key_v, node.pos)?
+          }
+          _ => {
+            return Err(CompileError::unsupported(
+              "Only properties can be deleted yet",
+              node.pos,
+            ));
+          }
+        },
       },
+      NodeBase::TernaryOp(cond, then_, else_) => self.build_conditional(cond, then_, else_)?,
       NodeBase::Assign(lhs, rhs) => match &lhs.base {
         NodeBase::Identifier(name) => {
-          let rhs_v = self.visit(rhs);
-          let val_v = self.get_variable(name);
-          self.builder.build_store(rhs_v, val_v);
+          let rhs_v = self.visit(rhs)?;
+          let ty = self.var_type(name);
+          let rhs_v = self.coerce(rhs_v, ty, node.pos)?;
+          self.store_variable(name, rhs_v, lhs.pos)?;
+          rhs_v
+        }
+        NodeBase::Member(obj, name) => {
+          let obj_v = self.visit(obj)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_set_member(obj_v, name, rhs_v, node.pos)?;
           rhs_v
         }
-        _ => unimplemented!(
-          "Left hand side of assignment statement should be an identifier. {:?}",
-          lhs
-        ),
+        NodeBase::Index(obj, key) => {
+          let obj_v = self.visit(obj)?;
+          let key_v = self.visit(key)?;
+          let rhs_v = self.visit(rhs)?;
+          self.build_set_index(obj_v, key_v, rhs_v, node.pos)?;
+          rhs_v
+        }
+        _ => {
+          return Err(CompileError::unsupported(
+            "Only identifiers and properties can be assigned to yet",
+            lhs.pos,
+          ));
+        }
       },
       NodeBase::If(cond, then_, else_) => {
-        let cond_v = self.visit(cond);
+        let cond_v = self.visit(cond)?;
+        let cond_v = self.coerce(cond_v, types::Type::Int1, node.pos)?;
         let then_bb = self.builder.append_basic_block();
         let else_bb = self.builder.append_basic_block();
         let cont_bb = self.builder.append_basic_block();
         self.builder.build_cond_br(cond_v, then_bb, else_bb);
         self.builder.set_insert_point(then_bb);
-        self.visit(then_);
+        self.visit(then_)?;
         self.builder.build_br(cont_bb);
         self.builder.set_insert_point(else_bb);
-        self.visit(else_);
+        self.visit(else_)?;
         self.builder.build_br(cont_bb);
         self.builder.set_insert_point(cont_bb);
 
@@ -300,11 +1100,35 @@ impl<'a> FuncCompiler<'a> {
         let cont_bb = self.builder.append_basic_block();
         self.builder.build_br(cond_bb);
         self.builder.set_insert_point(cond_bb);
-        let cond_v = self.visit(cond);
+        self.build_gc_safepoint();
+        let cond_v = self.visit(cond)?;
+        let cond_v = self.coerce(cond_v, types::Type::Int1, node.pos)?;
         self.builder.build_cond_br(cond_v, body_bb, cont_bb);
         self.builder.set_insert_point(body_bb);
-        self.visit(body);
+        self.push_loop(cont_bb, cond_bb);
+        self.visit(body)?;
+        self.break_targets.pop();
+        self.builder.build_br(cond_bb);
+        self.builder.set_insert_point(cont_bb);
+
+        Value::None
+      }
+      // The body runs before the condition is first checked, so it is the loop header.
+      NodeBase::DoWhile(body, cond) => {
+        let body_bb = self.builder.append_basic_block();
+        let cond_bb = self.builder.append_basic_block();
+        let cont_bb = self.builder.append_basic_block();
+        self.builder.build_br(body_bb);
+        self.builder.set_insert_point(body_bb);
+        self.build_gc_safepoint();
+        self.push_loop(cont_bb, cond_bb);
+        self.visit(body)?;
+        self.break_targets.pop();
         self.builder.build_br(cond_bb);
+        self.builder.set_insert_point(cond_bb);
+        let cond_v = self.visit(cond)?;
+        let cond_v = self.coerce(cond_v, types::Type::Int1, node.pos)?;
+        self.builder.build_cond_br(cond_v, body_bb, cont_bb);
         self.builder.set_insert_point(cont_bb);
 
         Value::None
@@ -313,104 +1137,1981 @@ impl<'a> FuncCompiler<'a> {
         let init_bb = self.builder.append_basic_block();
         let cond_bb = self.builder.append_basic_block();
         let body_bb = self.builder.append_basic_block();
+        let step_bb = self.builder.append_basic_block();
         let cont_bb = self.builder.append_basic_block();
+        // The parser wraps the loop in a block at the same position, which declares the
+        // bindings of its head. Each iteration gets a copy of them, initialized to the values
+        // at the end of the previous one.
+        // https://tc39.github.io/ecma262/#sec-createperiterationenvironment
+        let copies_env = self.block_envs.last() == Some(&node.pos);
         self.builder.build_br(init_bb);
         self.builder.set_insert_point(init_bb);
-        self.visit(init);
+        self.visit(init)?;
+        if copies_env {
+          self.build_env_copy(node.pos);
+        }
         self.builder.build_br(cond_bb);
         self.builder.set_insert_point(cond_bb);
-        let cond_v = self.visit(cond);
+        self.build_gc_safepoint();
+        let cond_v = self.visit(cond)?;
+        let cond_v = self.coerce(cond_v, types::Type::Int1, node.pos)?;
         self.builder.build_cond_br(cond_v, body_bb, cont_bb);
         self.builder.set_insert_point(body_bb);
-        self.visit(body);
-        self.visit(step);
+        self.push_loop(cont_bb, step_bb);
+        self.visit(body)?;
+        self.break_targets.pop();
+        self.builder.build_br(step_bb);
+        self.builder.set_insert_point(step_bb);
+        if copies_env {
+          self.build_env_copy(node.pos);
+        }
+        self.visit(step)?;
         self.builder.build_br(cond_bb);
         self.builder.set_insert_point(cont_bb);
 
         Value::None
       }
+      NodeBase::ForIn(binding, obj, body) => {
+        let obj_v = self.visit(obj)?;
+        let obj_v = self.coerce(obj_v, types::Type::Int64, node.pos)?;
+        let iter_slot = self.iterator_slots[&node.pos];
+        let iter_v = self.call_runtime("rapidus.rt.for_in_iterator", vec![obj_v]);
+        self.builder.build_store(iter_v, iter_slot);
+        let loop_bb = self.builder.append_basic_block();
+        let body_bb = self.builder.append_basic_block();
+        let cont_bb = self.builder.append_basic_block();
+        self.builder.build_br(loop_bb);
+        self.builder.set_insert_point(loop_bb);
+        self.build_gc_safepoint();
+        let iter_v = self.builder.build_load(iter_slot);
+        let key_v = self.call_runtime("rapidus.rt.iterator_next", vec![iter_v]);
+        let is_done = self
+          .builder
+          .build_icmp(ICmpKind::Eq, key_v, boxed_immediate(value::EMPTY));
+        self.builder.build_cond_br(is_done, cont_bb, body_bb);
+        self.builder.set_insert_point(body_bb);
+        self.build_for_in_of_body(binding, key_v, body, loop_bb, cont_bb, node.pos)?;
+        self.builder.set_insert_point(cont_bb);
+
+        Value::None
+      }
+      NodeBase::ForOf(binding, iterable, body) => {
+        let iterable_v = self.visit(iterable)?;
+        self.build_for_of(binding, iterable_v, body, node.pos)?;
+        Value::None
+      }
+      NodeBase::Label(name, body) => {
+        if is_loop(body) {
+          self.pending_labels.push(name.clone());
+          return self.visit(body);
+        }
+        let cont_bb = self.builder.append_basic_block();
+        self.break_targets.push(BreakTarget {
+          labels: vec![name.clone()],
+          break_bb: cont_bb,
+          continue_bb: None,
+          accepts_unlabeled_break: false,
+        });
+        self.visit(body)?;
+        self.break_targets.pop();
+        self.builder.build_br(cont_bb);
+        self.builder.set_insert_point(cont_bb);
+
+        Value::None
+      }
+      NodeBase::Break(label) => {
+        let index = self.find_break_target(label, false, node.pos)?;
+        self.build_finally_clauses(index + 1)?;
+        let break_bb = self.break_targets[index].break_bb;
+        self.build_jump(break_bb)
+      }
+      NodeBase::Continue(label) => {
+        let index = self.find_break_target(label, true, node.pos)?;
+        self.build_finally_clauses(index + 1)?;
+        let continue_bb = self.break_targets[index].continue_bb.unwrap();
+        self.build_jump(continue_bb)
+      }
+      NodeBase::Switch(discriminant, clauses) => {
+        self.build_switch(discriminant, clauses, node.pos)?;
+        Value::None
+      }
+      NodeBase::Try(try_, catch, param, finally) => {
+        self.build_try(try_, catch, param, finally, node.pos)?;
+        Value::None
+      }
+      NodeBase::Throw(expr) => {
+        let v = self.visit(expr)?;
+        let v = self.coerce(v, types::Type::Int64, node.pos)?;
+        let line = self.line_of(node.pos);
+        self.call_runtime("rapidus.rt.throw", vec![v, line]);
+        let handler_bb = self.exception_handler();
+        self.build_jump(handler_bb)
+      }
+      // Redeclaring a `var` without an initializer does not change its value.
+      NodeBase::VarDecl(_, None, VarKind::Var) => Value::None,
       NodeBase::VarDecl(name, init, _kind) => {
         let init_v = match init {
-          Some(init) => self.visit(init),
-          None => Value::Immediate(ImmediateValue::Int32(0)),
+          Some(init) => self.visit(init)?,
+          None => self.uninitialized_value(self.var_type(name)),
         };
-        let v = self.get_variable(name);
-        self.builder.build_store(init_v, v) // returns Value::None
+        let ty = self.var_type(name);
+        let init_v = self.coerce(init_v, ty, node.pos)?;
+        self.store_variable(name, init_v, node.pos)?;
+        Value::None
       }
       NodeBase::FunctionDecl(_name, _params, _body) => Value::None,
+      NodeBase::FunctionExpr(name, params, body) => {
+        let full_name = scope::function_expr_name(&self.function_name, name, node.pos);
+        let name = name.clone().unwrap_or_default();
+        self.build_function_value(&full_name, &name, true, params, body)
+      }
+      // Arrow functions are not constructors.
+      NodeBase::ArrowFunction(params, body) => {
+        let full_name = scope::function_expr_name(&self.function_name, &None, node.pos);
+        self.build_function_value(&full_name, &"".to_string(), false, params, body)
+      }
+      NodeBase::New(expr) => {
+        let (callee, args) = match &expr.base {
+          NodeBase::Call(callee, args) => (callee, args.as_slice()),
+          _ => (expr, &[][..]),
+        };
+        let callee_v = self.visit(callee)?;
+        let callee_v = self.coerce(callee_v, types::Type::Int64, node.pos)?;
+        let mut args_v = vec![];
+        for arg in args {
+          args_v.push(self.visit(arg)?);
+        }
+        let this_v = self.call_runtime("rapidus.rt.construct", vec![callee_v]);
+        self.build_exception_check(node.pos);
+        let ret_v = self.build_indirect_call(callee_v, this_v, args_v, node.pos)?;
+        self.call_runtime("rapidus.rt.construct_result", vec![ret_v, this_v])
+      }
       NodeBase::Call(callee, args) => {
         let callee_id = match &callee.base {
-          NodeBase::Identifier(name) => self.find_func_name(name),
+          NodeBase::Identifier(name) if name == "gc" && self.is_builtin(name) => {
+            self.call_runtime("rapidus.rt.gc", vec![]);
+            return Ok(Value::None);
+          }
+          NodeBase::Identifier(name) if !self.is_declared(name) => {
+            self.find_func_name(name, callee.pos)?
+          }
           NodeBase::Member(parent, member) => {
             if parent.base == NodeBase::Identifier("console".to_string())
               && *member == "log".to_string()
             {
-              self
-                .builder
-                .module
-                .find_function_by_name("cilk.println.i32")
-                .unwrap()
-            } else {
-              panic!("Member expression is not implemented yet.");
+              if args.len() == 1 {
+                let v = self.visit(&args[0])?;
+                self.build_println(v, node.pos)?;
+                return Ok(Value::None);
+              }
+              // Arguments are separated by a space.
+              for (i, arg) in args.iter().enumerate() {
+                let v = self.visit(arg)?;
+                let v = self.coerce(v, types::Type::Int64, node.pos)?;
+                let end = if i + 1 == args.len() { '\n' } else { ' ' };
+                let end = Value::Immediate(ImmediateValue::Int32(end as i32));
+                self.call_runtime("rapidus.rt.print", vec![v, end]);
+              }
+              return Ok(Value::None);
+            }
+            let obj_v = self.visit(parent)?;
+            let mut args_v = vec![];
+            for arg in args {
+              args_v.push(self.visit(arg)?);
             }
+            return self.build_method_call(obj_v, member, args_v, node.pos);
+          }
+          _ => {
+            let callee_v = self.visit(callee)?;
+            let mut args_v = vec![];
+            for arg in args {
+              args_v.push(self.visit(arg)?);
+            }
+            // Functions called without a receiver get the global object as `this`.
+            let this_v = boxed_immediate(runtime::global_object());
+            return self.build_indirect_call(callee_v, this_v, args_v, node.pos);
           }
-          _ => unimplemented!("callee should be Identifier."),
         };
+        let callee_name = self.builder.module.function_ref(callee_id).name.clone();
         let mut args_v = vec![];
         for arg in args {
-          let v = self.visit(arg);
-          args_v.push(v);
+          args_v.push(self.visit(arg)?);
         }
-        self.builder.build_call(Value::Function(callee_id), args_v)
+        // The callee is declared in this function or in one of its ancestors.
+        let parent_name = scope::ancestor(&callee_name, 1);
+        let distance = self.function_name.matches('.').count() - parent_name.matches('.').count();
+        let this_v = boxed_immediate(runtime::global_object());
+        let mut call_args = vec![self.environment_of(distance), this_v];
+        call_args.extend(self.build_arguments(&callee_name, &args_v, node.pos)?);
+        let ret_v = self.builder.build_call(Value::Function(callee_id), call_args);
+        self.build_exception_check(node.pos);
+        ret_v
       }
       NodeBase::Return(ret) => {
         let ret_v = match ret {
-          Some(node) => self.visit(node),
-          None => Value::None,
+          Some(node) => self.visit(node)?,
+          None => boxed_immediate(value::UNDEFINED),
         };
-        self.builder.build_ret(ret_v)
+        self.build_finally_clauses(0)?;
+        self.build_return(ret_v, node.pos)?
       }
-      NodeBase::Identifier(name) => {
-        match self.arguments_map.get(name) {
-          Some(v) => return self.builder.get_param(*v).unwrap(),
-          None => {}
-        };
-        let v = self.get_variable(name);
-        self.builder.build_load(v)
+      NodeBase::Identifier(name) if name == "undefined" && !self.is_declared(name) => {
+        boxed_immediate(value::UNDEFINED)
       }
-      NodeBase::Number(x) => {
-        let x_i32 = *x as i32;
-        Value::Immediate(ImmediateValue::Int32(x_i32))
+      NodeBase::Identifier(name) if !self.is_declared(name) => {
+        match self.type_info.resolve_function(&self.function_name, name) {
+          Some(full_name) => self.build_declared_function_value(&full_name, name),
+          None => return Err(not_defined(name, node.pos)),
+        }
+      }
+      NodeBase::Identifier(name) => self.load_variable(name, node.pos)?,
+      NodeBase::Member(obj, name) => {
+        let obj_v = self.visit(obj)?;
+        self.build_get_member(obj_v, name, node.pos)?
+      }
+      NodeBase::Index(obj, key) => {
+        let obj_v = self.visit(obj)?;
+        let key_v = self.visit(key)?;
+        self.build_get_index(obj_v, key_v, node.pos)?
+      }
+      NodeBase::Object(properties) => self.build_object(properties, node.pos)?,
+      NodeBase::Array(elems) => {
+        let mut elems_v = vec![];
+        for elem in elems {
+          elems_v.push(self.visit(elem)?);
+        }
+        self.build_array(elems_v, node.pos)?
+      }
+      NodeBase::TemplateLiteral(strings, exprs) => {
+        // Adding to a string converts the substitutions to strings.
+        let mut str_v = self.string_literal(&strings[0]);
+        for (expr, s) in exprs.iter().zip(&strings[1..]) {
+          let v = self.visit(expr)?;
+          let v = self.coerce(v, types::Type::Int64, node.pos)?;
+          str_v = self.call_runtime("rapidus.rt.add", vec![str_v, v]);
+          if !s.is_empty() {
+            let s_v = self.string_literal(s);
+            str_v = self.call_runtime("rapidus.rt.add", vec![str_v, s_v]);
+          }
+        }
+        str_v
       }
+      NodeBase::TemplateStrings(strings, raw) => {
+        boxed_immediate(self.constants.template(strings, raw))
+      }
+      NodeBase::RegExp(pattern, flags) => {
+        // Invalid patterns are early errors, so the runtime only creates the object.
+        if let Err(msg) = runtime::regexp::Regex::new(pattern, flags) {
+          return Err(CompileError::syntax_error(
+            format!("Invalid regular expression: /{}/: {}", pattern, msg),
+            node.pos,
+          ));
+        }
+        let args = vec![self.string_literal(pattern), self.string_literal(flags)];
+        self.call_runtime("rapidus.rt.regexp_new", args)
+      }
+      NodeBase::String(s) => self.string_literal(s),
+      NodeBase::Number(x) => match Ty::of_number(*x) {
+        Ty::Int32 => Value::Immediate(ImmediateValue::Int32(*x as i32)),
+        _ => Value::Immediate(ImmediateValue::F64(*x)),
+      },
+      NodeBase::Boolean(b) => Value::Immediate(ImmediateValue::Int1(*b)),
+      NodeBase::Null => boxed_immediate(value::NULL),
       NodeBase::Nope => Value::None,
-      _ => unimplemented!("{:?}", node.base),
-    }
+      _ => {
+        return Err(CompileError::unsupported(
+          "Such expressions are not supported yet",
+          node.pos,
+        ));
+      }
+    };
+    Ok(v)
   }
 
-  fn find_func_name(&mut self, name: &String) -> FunctionId {
+  fn find_func_name(&mut self, name: &String, pos: usize) -> Result<FunctionId, CompileError> {
     match self.function_map.get(name) {
-      Some(v) => return v.0,
+      Some(v) => return Ok(v.0),
       None => {}
     };
-    let function_name = self.function_name.clone();
-    let mut func_name_vec: Vec<&str> = function_name.split('.').collect();
-    while let Some(fname) = func_name_vec.pop() {
-      if *name == *fname {
-        let fullname = format!("{}.{}", func_name_vec.join("."), fname);
-        return self
-          .builder
-          .module
-          .find_function_by_name(fullname.as_str())
-          .unwrap();
-      }
+    match self.type_info.resolve_function(&self.function_name, name) {
+      Some(full_name) => Ok(self.find_function(&full_name)),
+      None => Err(not_defined(name, pos)),
     }
-    panic!("function not found: {}", name);
   }
 
-  fn get_variable(&mut self, name: &String) -> Value {
-    match self.variable_map.get(name) {
-      Some(v) => *v,
-      None => panic!("Undefined var: {:?}", name),
-    }
+  /// Creates a value of the function expression or arrow function `full_name`,
+  /// whose body is compiled later like declared functions.
+  fn build_function_value(
+    &mut self,
+    full_name: &String,
+    name: &String,
+    is_constructor: bool,
+    params: &Vec<FormalParameter>,
+    body: &Node,
+  ) -> Value {
+    let func_id = self.find_function(full_name);
+    self
+      .function_map
+      .insert(full_name.clone(), (func_id, params.clone(), body.clone()));
+    let (id, env) = self.closure_operands(full_name);
+    let is_constructor = Value::Immediate(ImmediateValue::Int32(is_constructor as i32));
+    let name_v = self.string_literal(name);
+    self.call_runtime("rapidus.rt.closure_new", vec![id, env, name_v, is_constructor])
+  }
+
+  /// The value of the declared function `full_name`, which is the same object
+  /// every time it is referred to in a call of the enclosing function.
+  fn build_declared_function_value(&mut self, full_name: &String, name: &String) -> Value {
+    let (id, env) = self.closure_operands(full_name);
+    let name_v = self.string_literal(name);
+    self.call_runtime("rapidus.rt.function_object", vec![id, env, name_v])
+  }
+
+  /// The id of the function value `full_name`, and the environment record the value captures:
+  /// the record of the innermost block it is created in, or of the enclosing function.
+  fn closure_operands(&mut self, full_name: &String) -> (Value, Value) {
+    let id = self
+      .type_info
+      .function_values()
+      .iter()
+      .position(|name| *name == full_name)
+      .unwrap();
+    let env = match self.scope_info.function(full_name).env_blocks.first() {
+      Some(&block) => self.block_env(block),
+      None => {
+        let parent_name = scope::ancestor(full_name, 1);
+        let distance = self.function_name.matches('.').count() - parent_name.matches('.').count();
+        self.environment_of(distance)
+      }
+    };
+    (Value::Immediate(ImmediateValue::Int32(id as i32)), env)
+  }
+
+  /// Calls the method `name_v` of `obj_v`. Builtin methods such as `Array.prototype.push`
+  /// are run by the runtime, and the others are called as function values.
+  fn build_method_call(
+    &mut self,
+    obj_v: Value,
+    name: &String,
+    args: Vec<Value>,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let name_v = self.string_literal(name);
+    let id_v = self.call_runtime("rapidus.rt.builtin_method", vec![obj_v, name_v]);
+    let is_builtin = self
+      .builder
+      .build_icmp(ICmpKind::Ge, id_v, Value::Immediate(ImmediateValue::Int32(0)));
+    let builtin_bb = self.builder.append_basic_block();
+    let method_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_builtin, builtin_bb, method_bb);
+
+    self.builder.set_insert_point(builtin_bb);
+    let args_v = self.build_array(args.clone(), pos)?;
+    let builtin_ret_v = self.call_runtime("rapidus.rt.call_builtin", vec![id_v, obj_v, args_v]);
+    self.build_exception_check(pos);
+    let builtin_end_bb = self.build_br_from_new_block(cont_bb);
+
+    self.builder.set_insert_point(method_bb);
+    let callee_v = self.build_get_member(obj_v, name, pos)?;
+    let method_ret_v = self.build_indirect_call(callee_v, obj_v, args, pos)?;
+    let method_ret_v = self.coerce(method_ret_v, types::Type::Int64, pos)?;
+    let method_end_bb = self.build_br_from_new_block(cont_bb);
+
+    self.builder.set_insert_point(cont_bb);
+    Ok(self
+      .builder
+      .build_phi(vec![(builtin_ret_v, builtin_end_bb), (method_ret_v, method_end_bb)]))
+  }
+
+  /// Calls the function value `callee_v`.
+  /// Since cilk has no indirect calls, the function id stored in the value, which indexes
+  /// `TypeInfo::function_values`, is passed to the dispatcher for the number of arguments,
+  /// which selects a direct call through a jump table.
+  fn build_indirect_call(
+    &mut self,
+    callee_v: Value,
+    this_v: Value,
+    args: Vec<Value>,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let callee_v = self.coerce(callee_v, types::Type::Int64, pos)?;
+    let mut args_v = vec![];
+    for arg in args {
+      args_v.push(self.coerce(arg, types::Type::Int64, pos)?);
+    }
+    let id_v = self.call_runtime("rapidus.rt.closure_func", vec![callee_v]);
+    self.build_exception_check(pos);
+    let env_v = self.call_runtime("rapidus.rt.closure_env", vec![callee_v]);
+    let this_v = self.coerce(this_v, types::Type::Int64, pos)?;
+    let dispatcher = self.dispatcher(args_v.len());
+    let mut call_args = vec![id_v, env_v, this_v];
+    call_args.extend(args_v);
+    let ret_v = self.builder.build_call(Value::Function(dispatcher), call_args);
+    self.build_exception_check(pos);
+    Ok(ret_v)
+  }
+
+  /// The dispatcher for calls with `argc` arguments (see `compile_dispatcher`),
+  /// declared by the first call site needing it.
+  fn dispatcher(&mut self, argc: usize) -> FunctionId {
+    let name = format!("{}{}", DISPATCHER_PREFIX, argc);
+    if let Some(id) = self.builder.module.find_function_by_name(&name) {
+      return id;
+    }
+    let mut param_types = vec![types::Type::Int32, types::Type::Int64, types::Type::Int64];
+    param_types.extend(vec![types::Type::Int64; argc]);
+    self.builder.module.add_function(function::Function::new(
+      name.as_str(),
+      types::Type::Int64,
+      param_types,
+    ))
+  }
+
+  /// Compiles a dispatcher, which calls the function value whose id it takes first with the
+  /// environment record, `this` and the `argc` boxed arguments that follow.
+  /// Every indirect call with `argc` arguments calls it, so that the jump table over the
+  /// function values is emitted once per argument count rather than at every call site.
+  pub fn compile_dispatcher(mut self, argc: usize) -> Result<(), CompileError> {
+    let entry = self.builder.append_basic_block();
+    self.builder.set_insert_point(entry);
+    let id_v = self.builder.get_param(0).unwrap();
+    let env_v = self.builder.get_param(1).unwrap();
+    let this_v = self.builder.get_param(2).unwrap();
+    let args_v: Vec<Value> = (0..argc)
+      .map(|i| self.builder.get_param(i + 3).unwrap())
+      .collect();
+    let type_info = self.type_info;
+    let names = type_info.function_values();
+    let call_bbs: Vec<BasicBlockId> =
+      names.iter().map(|_| self.builder.append_basic_block()).collect();
+    // Unreachable, since `rapidus.rt.closure_func` has checked that the callee is a function.
+    let default_bb = self.builder.append_basic_block();
+    let mut runs = vec![(::std::i32::MIN, None)];
+    if !call_bbs.is_empty() {
+      runs.extend(call_bbs.iter().enumerate().map(|(id, bb)| (id as i32, Some(*bb))));
+      runs.push((call_bbs.len() as i32, None));
+    }
+    self.build_jump_table(id_v, runs, default_bb);
+    for (name, call_bb) in names.into_iter().zip(call_bbs) {
+      self.builder.set_insert_point(call_bb);
+      let mut call_args = vec![env_v, this_v];
+      call_args.extend(self.build_arguments(name, &args_v, 0)?);
+      let func_id = self.find_function(name);
+      let ret_v = self.builder.build_call(Value::Function(func_id), call_args);
+      let ret_v = self.coerce(ret_v, types::Type::Int64, 0)?;
+      self.builder.build_ret(ret_v);
+    }
+    self.builder.set_insert_point(default_bb);
+    self.builder.build_ret(boxed_immediate(value::UNDEFINED));
+    Ok(())
+  }
+
+  /// The values passed to the parameters of the function `name` for the arguments `args`.
+  /// Missing arguments are `undefined`, and extra ones are dropped unless the function has
+  /// a rest parameter or uses `arguments`, which get arrays of them.
+  fn build_arguments(
+    &mut self,
+    name: &str,
+    args: &[Value],
+    pos: usize,
+  ) -> Result<Vec<Value>, CompileError> {
+    let type_info = self.type_info;
+    let types = type_info.function(name);
+    let mut params_v = vec![];
+    for (i, ty) in types.param_types().into_iter().enumerate() {
+      if types.has_rest_param && i + 1 == types.params.len() {
+        let rest = args.get(i..).unwrap_or(&[]).to_vec();
+        params_v.push(self.build_array(rest, pos)?);
+        break;
+      }
+      let arg = args
+        .get(i)
+        .cloned()
+        .unwrap_or_else(|| boxed_immediate(value::UNDEFINED));
+      params_v.push(self.coerce(arg, ty, pos)?);
+    }
+    if self.scope_info.function(name).uses_arguments {
+      params_v.push(self.build_array(args.to_vec(), pos)?);
+    }
+    Ok(params_v)
+  }
+
+  /// Allocates an array holding `elems`.
+  fn build_array(&mut self, elems: Vec<Value>, pos: usize) -> Result<Value, CompileError> {
+    let len = Value::Immediate(ImmediateValue::Int32(elems.len() as i32));
+    let arr_v = self.call_runtime("rapidus.rt.array_new", vec![len]);
+    for elem in elems {
+      let elem = self.coerce(elem, types::Type::Int64, pos)?;
+      self.call_runtime("rapidus.rt.array_push", vec![arr_v, elem]);
+    }
+    Ok(arr_v)
+  }
+
+  /// Creates an object from the properties of an object literal.
+  fn build_object(
+    &mut self,
+    properties: &Vec<PropertyDefinition>,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let obj_v = self.call_runtime("rapidus.rt.object_new", vec![]);
+    let undefined = boxed_immediate(value::UNDEFINED);
+    for property in properties {
+      match property {
+        PropertyDefinition::IdentifierReference(name) => {
+          let v = self.visit(&Node::new(NodeBase::Identifier(name.clone()), pos))?;
+          let v = self.coerce(v, types::Type::Int64, pos)?;
+          let name_v = self.string_literal(name);
+          self.call_runtime("rapidus.rt.define_property", vec![obj_v, name_v, v]);
+        }
+        PropertyDefinition::Property(name, value)
+        | PropertyDefinition::MethodDefinition(MethodDefinitionKind::Ordinary, name, value) => {
+          let v = self.visit(value)?;
+          let v = self.coerce(v, types::Type::Int64, pos)?;
+          let name_v = self.string_literal(name);
+          self.call_runtime("rapidus.rt.define_property", vec![obj_v, name_v, v]);
+        }
+        PropertyDefinition::MethodDefinition(MethodDefinitionKind::Get, name, func) => {
+          let get = self.visit(func)?;
+          let name_v = self.string_literal(name);
+          let args = vec![obj_v, name_v, get, undefined];
+          self.call_runtime("rapidus.rt.define_accessor", args);
+        }
+        PropertyDefinition::MethodDefinition(MethodDefinitionKind::Set, name, func) => {
+          let set = self.visit(func)?;
+          let name_v = self.string_literal(name);
+          let args = vec![obj_v, name_v, undefined, set];
+          self.call_runtime("rapidus.rt.define_accessor", args);
+        }
+      }
+    }
+    Ok(obj_v)
+  }
+
+  /// Reads `obj.name` through a new inline cache, calling the runtime only on a miss.
+  fn build_get_member(
+    &mut self,
+    obj_v: Value,
+    name: &String,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let cache = self.constants.inline_cache();
+    let miss_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    let slot_v = self.build_inline_cache(obj_v, cache, miss_bb);
+    let hit_v = self.builder.build_load(slot_v);
+    // The runtime roots the values it returns, so a hit does too.
+    let is_pointer = self.build_is_pointer(hit_v);
+    let root_bb = self.builder.append_basic_block();
+    let hit_end_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_pointer, root_bb, hit_end_bb);
+    self.builder.set_insert_point(root_bb);
+    self.call_runtime("rapidus.rt.root", vec![hit_v]);
+    self.builder.build_br(hit_end_bb);
+    self.builder.set_insert_point(hit_end_bb);
+    self.builder.build_br(cont_bb);
+
+    self.builder.set_insert_point(miss_bb);
+    let name_v = self.string_literal(name);
+    let cache_v = Value::Immediate(ImmediateValue::Int64(cache as i64));
+    let miss_v =
+      self.build_get_property("rapidus.rt.get_member", obj_v, name_v, Some(cache_v), pos)?;
+    let miss_end_bb = self.build_br_from_new_block(cont_bb);
+
+    self.builder.set_insert_point(cont_bb);
+    Ok(
+      self
+        .builder
+        .build_phi(vec![(hit_v, hit_end_bb), (miss_v, miss_end_bb)]),
+    )
+  }
+
+  fn build_get_index(
+    &mut self,
+    obj_v: Value,
+    key_v: Value,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    self.build_get_property("rapidus.rt.get_index", obj_v, key_v, None, pos)
+  }
+
+  /// Stores to `obj.name` through a new inline cache, calling the runtime only on a miss.
+  fn build_set_member(
+    &mut self,
+    obj_v: Value,
+    name: &String,
+    v: Value,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let v = self.coerce(v, types::Type::Int64, pos)?;
+    let cache = self.constants.inline_cache();
+    let miss_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    let slot_v = self.build_inline_cache(obj_v, cache, miss_bb);
+    self.builder.build_store(v, slot_v);
+    self.builder.build_br(cont_bb);
+
+    self.builder.set_insert_point(miss_bb);
+    let name_v = self.string_literal(name);
+    let cache_v = Value::Immediate(ImmediateValue::Int64(cache as i64));
+    self.build_set_property("rapidus.rt.set_member", obj_v, name_v, v, Some(cache_v), pos)?;
+    self.builder.build_br(cont_bb);
+
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  fn build_set_index(
+    &mut self,
+    obj_v: Value,
+    key_v: Value,
+    v: Value,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    self.build_set_property("rapidus.rt.set_index", obj_v, key_v, v, None, pos)
+  }
+
+  /// Checks `cache` against the shape of `obj_v`, branching to `miss_bb` unless `obj_v` is an
+  /// object of a cached shape with a data property in the cached slot.
+  /// On a hit, counts it and returns a pointer to the value of the property.
+  fn build_inline_cache(
+    &mut self,
+    obj_v: Value,
+    cache: *mut InlineCache,
+    miss_bb: BasicBlockId,
+  ) -> Value {
+    let layout = ObjectLayout::get();
+    let int64 = |n: usize| Value::Immediate(ImmediateValue::Int64(n as i64));
+
+    let is_pointer = self.build_is_pointer(obj_v);
+    let pointer_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_pointer, pointer_bb, miss_bb);
+    self.builder.set_insert_point(pointer_bb);
+    let object_v = self.builder.build_and(obj_v, int64(value::PAYLOAD_MASK as usize));
+    let kind_v = self.build_load_at(object_v, layout.kind, types::Type::Int32);
+    let is_object = self.builder.build_icmp(
+      ICmpKind::Lt,
+      kind_v,
+      Value::Immediate(ImmediateValue::Int32(ObjectKind::String as i32)),
+    );
+    let object_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_object, object_bb, miss_bb);
+    self.builder.set_insert_point(object_bb);
+    let shape_v = self.build_load_at(object_v, layout.shape, types::Type::Int64);
+
+    // The entries are compared in order, and the first match gives the offset of the slot.
+    let found_bb = self.builder.append_basic_block();
+    let mut offsets = vec![];
+    for i in 0..ic::MAX_ENTRIES {
+      let entry_v = int64(cache as usize + InlineCache::shape_offset(i));
+      let cached_v = self.build_load_at(entry_v, 0, types::Type::Int64);
+      let matches = self.builder.build_icmp(ICmpKind::Eq, shape_v, cached_v);
+      let match_bb = self.builder.append_basic_block();
+      let next_bb = if i + 1 == ic::MAX_ENTRIES {
+        miss_bb
+      } else {
+        self.builder.append_basic_block()
+      };
+      self.builder.build_cond_br(matches, match_bb, next_bb);
+      self.builder.set_insert_point(match_bb);
+      let offset_addr = int64(cache as usize + InlineCache::slot_offset(i));
+      let offset_v = self.build_load_at(offset_addr, 0, types::Type::Int64);
+      self.builder.build_br(found_bb);
+      offsets.push((offset_v, match_bb));
+      if next_bb != miss_bb {
+        self.builder.set_insert_point(next_bb);
+      }
+    }
+
+    self.builder.set_insert_point(found_bb);
+    let offset_v = self.builder.build_phi(offsets);
+    let slots_v = self.build_load_at(object_v, layout.slots, types::Type::Int64);
+    let slot_v = self.builder.build_add(slots_v, offset_v);
+    let tag_v = self.build_load_at(slot_v, 0, types::Type::Int64);
+    let is_data = self.builder.build_icmp(ICmpKind::Eq, tag_v, int64(0));
+    let hit_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_data, hit_bb, miss_bb);
+    self.builder.set_insert_point(hit_bb);
+
+    let hits_v = int64(cache as usize + InlineCache::hits_offset());
+    let count_v = self.build_load_at(hits_v, 0, types::Type::Int64);
+    let count_v = self.builder.build_add(count_v, int64(1));
+    let hits_ptr = self.build_pointer_at(hits_v, 0, types::Type::Int64);
+    self.builder.build_store(count_v, hits_ptr);
+    self.build_pointer_at(slot_v, layout.data, types::Type::Int64)
+  }
+
+  /// Whether the `BoxedValue` `v` points to a heap-allocated value.
+  fn build_is_pointer(&mut self, v: Value) -> Value {
+    let tag_v = self.builder.build_and(
+      v,
+      Value::Immediate(ImmediateValue::Int64(value::TAG_MASK as i64)),
+    );
+    self.builder.build_icmp(
+      ICmpKind::Eq,
+      tag_v,
+      Value::Immediate(ImmediateValue::Int64(value::TAG_POINTER as i64)),
+    )
+  }
+
+  /// A pointer to a `ty` at `offset` bytes past the address `addr`, an `Int64`.
+  fn build_pointer_at(&mut self, addr: Value, offset: usize, ty: types::Type) -> Value {
+    let addr = if offset == 0 {
+      addr
+    } else {
+      self
+        .builder
+        .build_add(addr, Value::Immediate(ImmediateValue::Int64(offset as i64)))
+    };
+    self.builder.build_inttoptr(addr, ty.get_pointer_ty())
+  }
+
+  /// Loads a `ty` at `offset` bytes past the address `addr`, an `Int64`.
+  fn build_load_at(&mut self, addr: Value, offset: usize, ty: types::Type) -> Value {
+    let ptr = self.build_pointer_at(addr, offset, ty);
+    self.builder.build_load(ptr)
+  }
+
+  /// Reads a property of `obj_v` with the runtime helper `get_fn`, passing `cache` if any,
+  /// and calls the getter if the property is an accessor.
+  fn build_get_property(
+    &mut self,
+    get_fn: &str,
+    obj_v: Value,
+    key_v: Value,
+    cache: Option<Value>,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let key_v = self.coerce(key_v, types::Type::Int64, pos)?;
+    let mut args = vec![obj_v, key_v];
+    args.extend(cache);
+    let v = self.call_runtime(get_fn, args);
+    self.build_exception_check(pos);
+    let is_accessor = self
+      .builder
+      .build_icmp(ICmpKind::Eq, v, boxed_immediate(value::EMPTY));
+    let data_bb = self.builder.append_basic_block();
+    let getter_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_accessor, getter_bb, data_bb);
+
+    self.builder.set_insert_point(data_bb);
+    self.builder.build_br(cont_bb);
+
+    self.builder.set_insert_point(getter_bb);
+    let getter_v = self.call_runtime("rapidus.rt.get_getter", vec![obj_v, key_v]);
+    let getter_ret_v = self.build_indirect_call(getter_v, obj_v, vec![], pos)?;
+    let getter_ret_v = self.coerce(getter_ret_v, types::Type::Int64, pos)?;
+    let getter_end_bb = self.build_br_from_new_block(cont_bb);
+
+    self.builder.set_insert_point(cont_bb);
+    Ok(self
+      .builder
+      .build_phi(vec![(v, data_bb), (getter_ret_v, getter_end_bb)]))
+  }
+
+  /// Stores `v` to a property of `obj_v` with the runtime helper `set_fn`, passing `cache` if any,
+  /// and calls the setter if the property is an accessor.
+  fn build_set_property(
+    &mut self,
+    set_fn: &str,
+    obj_v: Value,
+    key_v: Value,
+    v: Value,
+    cache: Option<Value>,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let key_v = self.coerce(key_v, types::Type::Int64, pos)?;
+    let v = self.coerce(v, types::Type::Int64, pos)?;
+    let mut args = vec![obj_v, key_v, v];
+    args.extend(cache);
+    let setter_v = self.call_runtime(set_fn, args);
+    self.build_exception_check(pos);
+    let has_setter = self
+      .builder
+      .build_icmp(ICmpKind::Ne, setter_v, boxed_immediate(value::UNDEFINED));
+    let setter_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(has_setter, setter_bb, cont_bb);
+    self.builder.set_insert_point(setter_bb);
+    self.build_indirect_call(setter_v, obj_v, vec![v], pos)?;
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  fn build_delete(
+    &mut self,
+    obj_v: Value,
+    key_v: Value,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    let obj_v = self.coerce(obj_v, types::Type::Int64, pos)?;
+    let key_v = self.coerce(key_v, types::Type::Int64, pos)?;
+    let deleted = self.call_runtime("rapidus.rt.delete_property", vec![obj_v, key_v]);
+    self.build_exception_check(pos);
+    Ok(self.builder.build_icmp(
+      ICmpKind::Ne,
+      deleted,
+      Value::Immediate(ImmediateValue::Int32(0)),
+    ))
+  }
+
+  /// Branches to `dest` through a new block and returns the block.
+  /// Used for phi operands after helpers like `build_indirect_call`,
+  /// which leave the insert point in a block the caller does not know.
+  fn build_br_from_new_block(&mut self, dest: BasicBlockId) -> BasicBlockId {
+    let bb = self.builder.append_basic_block();
+    self.builder.build_br(bb);
+    self.builder.set_insert_point(bb);
+    self.builder.build_br(dest);
+    bb
+  }
+
+  fn load_variable(&mut self, name: &String, pos: usize) -> Result<Value, CompileError> {
+    if let Some(v) = self.variable_map.get(name) {
+      let v = *v;
+      return Ok(self.builder.build_load(v));
+    }
+    if let Some((env, index)) = self.env_slot(name) {
+      let v = if self.in_outer_tdz(name) {
+        let name_v = self.string_literal(scope::source_name(name));
+        let v = self.call_runtime("rapidus.rt.env_get_checked", vec![env, index, name_v]);
+        self.build_exception_check(pos);
+        v
+      } else {
+        self.call_runtime("rapidus.rt.env_get", vec![env, index])
+      };
+      let ty = self.var_type(name);
+      return self.coerce(v, ty, pos);
+    }
+    match self.arguments_map.get(name) {
+      Some(i) => Ok(self.builder.get_param(*i).unwrap()),
+      None => unreachable!("undeclared variable: {}", name),
+    }
+  }
+
+  /// Stores `v`, which has the type of the variable, to the variable `name`.
+  fn store_variable(&mut self, name: &String, v: Value, pos: usize) -> Result<(), CompileError> {
+    if let Some(var_v) = self.variable_map.get(name) {
+      let var_v = *var_v;
+      self.builder.build_store(v, var_v);
+      return Ok(());
+    }
+    match self.env_slot(name) {
+      Some((env, index)) => {
+        let v = self.coerce(v, types::Type::Int64, pos)?;
+        if self.in_outer_tdz(name) {
+          let name_v = self.string_literal(scope::source_name(name));
+          let args = vec![env, index, v, name_v];
+          self.call_runtime("rapidus.rt.env_set_checked", args);
+          self.build_exception_check(pos);
+        } else {
+          self.call_runtime("rapidus.rt.env_set", vec![env, index, v]);
+        }
+        Ok(())
+      }
+      None if self.arguments_map.contains_key(name) => Err(CompileError::unsupported(
+        format!("Assignment to '{}' is not supported yet", name),
+        pos,
+      )),
+      None => Err(not_defined(name, pos)),
+    }
+  }
+
+  /// Whether the variable `name` of this function is captured by nested functions.
+  fn is_captured(&self, name: &String) -> bool {
+    self.scope_info.is_captured(&self.function_name, name)
+  }
+
+  /// Whether `name` is a `let` or `const` binding of an enclosing function, which may still be
+  /// in its TDZ when this function runs. References in the declaring function are checked
+  /// at compile time instead (see `scope`).
+  fn in_outer_tdz(&self, name: &String) -> bool {
+    let owner = self.scope_info.owner(&self.function_name, name);
+    owner != self.function_name && self.scope_info.function(&owner).lexical.contains(name)
+  }
+
+  /// The environment record holding the variable `name` and its index in the record,
+  /// or `None` if the variable is not captured.
+  fn env_slot(&mut self, name: &String) -> Option<(Value, Value)> {
+    let scope_info = self.scope_info;
+    let owner = scope_info.owner(&self.function_name, name);
+    let (block, index) = scope_info.env_index(&owner, name)?;
+    let env = self.environment(&owner, block);
+    Some((env, Value::Immediate(ImmediateValue::Int32(index as i32))))
+  }
+
+  /// The environment record of the function `distance` levels up from this one.
+  fn environment_of(&mut self, distance: usize) -> Value {
+    let owner = scope::ancestor(&self.function_name, distance).to_string();
+    self.environment(&owner, None)
+  }
+
+  /// The environment record of `block` (or of the whole function if `None`) in the function
+  /// `owner`, which is this function or one of its ancestors.
+  fn environment(&mut self, owner: &str, block: Option<usize>) -> Value {
+    if owner == self.function_name {
+      return match block {
+        Some(block) => self.block_env(block),
+        None => self.env.unwrap(),
+      };
+    }
+    let distance = self
+      .scope_info
+      .env_distance(&self.function_name, owner, block);
+    let mut env = self.parent_env.unwrap();
+    for _ in 0..distance {
+      let zero = Value::Immediate(ImmediateValue::Int32(0));
+      env = self.call_runtime("rapidus.rt.env_get", vec![env, zero]);
+    }
+    env
+  }
+
+  /// The current environment record of the enclosing `block`.
+  fn block_env(&mut self, block: usize) -> Value {
+    let slot = self.env_slots[&block];
+    self.builder.build_load(slot)
+  }
+
+  /// Creates the environment record of the block at `pos` if it holds captured bindings,
+  /// linked to the innermost record enclosing it, and returns whether it did.
+  fn enter_block_env(&mut self, pos: usize) -> bool {
+    let scope_info = self.scope_info;
+    let len = match scope_info.function(&self.function_name).blocks.get(&pos) {
+      Some(names) => names.len(),
+      None => return false,
+    };
+    let parent_env = match self.block_envs.last() {
+      Some(&block) => self.block_env(block),
+      None => self.env.unwrap(),
+    };
+    let len = Value::Immediate(ImmediateValue::Int32(len as i32));
+    let env = self.call_runtime("rapidus.rt.env_new", vec![parent_env, len]);
+    self.builder.build_store(env, self.env_slots[&pos]);
+    self.block_envs.push(pos);
+    let names = &scope_info.function(&self.function_name).blocks[&pos];
+    self.build_tdz_slots(env, names);
+    true
+  }
+
+  /// Puts the `let` and `const` bindings among `names`, the bindings of the new record `env`,
+  /// in their TDZ.
+  fn build_tdz_slots(&mut self, env: Value, names: &[String]) {
+    let scope_info = self.scope_info;
+    let lexical = &scope_info.function(&self.function_name).lexical;
+    for (i, name) in names.iter().enumerate() {
+      if lexical.contains(name) {
+        let index = Value::Immediate(ImmediateValue::Int32(i as i32 + 1));
+        let uninitialized = boxed_immediate(value::UNINITIALIZED);
+        self.call_runtime("rapidus.rt.env_set", vec![env, index, uninitialized]);
+      }
+    }
+  }
+
+  /// Replaces the environment record of the block at `pos` with a copy of it,
+  /// so that closures created before keep the bindings they captured.
+  fn build_env_copy(&mut self, pos: usize) {
+    let env = self.block_env(pos);
+    let env = self.call_runtime("rapidus.rt.env_copy", vec![env]);
+    self.builder.build_store(env, self.env_slots[&pos]);
+  }
+
+  /// Whether `name` refers to a builtin function, which scripts can shadow.
+  fn is_builtin(&self, name: &String) -> bool {
+    !self.is_declared(name)
+      && self
+        .type_info
+        .resolve_function(&self.function_name, name)
+        .is_none()
+  }
+
+  fn is_declared(&self, name: &String) -> bool {
+    self.variable_map.contains_key(name)
+      || self.arguments_map.contains_key(name)
+      || self.is_captured(name)
+      || self
+        .scope_info
+        .function(&self.function_name)
+        .outer
+        .contains_key(name)
+  }
+
+  fn var_type(&self, name: &String) -> types::Type {
+    let owner = self.scope_info.owner(&self.function_name, name);
+    match self.type_info.function(&owner).vars.get(name) {
+      Some(ty) => ty.to_cilk_type(),
+      None => Ty::Unknown.to_cilk_type(),
+    }
+  }
+
+  /// The value of a variable before it is assigned. A declaration without an initializer makes
+  /// the variable `Dynamic` (see `typing`), so other types only show up for a hoisted `var`
+  /// read before its declaration, and for return values nobody reads.
+  fn uninitialized_value(&self, ty: types::Type) -> Value {
+    match ty {
+      types::Type::Int64 => boxed_immediate(value::UNDEFINED),
+      _ => Value::Immediate(ImmediateValue::Int32(0)),
+    }
+  }
+
+  fn get_type(&self, v: Value) -> types::Type {
+    v.get_type(self.builder.module).clone()
+  }
+
+  fn is_dynamic(&self, v: Value) -> bool {
+    self.get_type(v) == types::Type::Int64
+  }
+
+  /// Converts `v` into a value of type `ty`.
+  /// Booleans become 0 or 1, and a number used as a condition is true unless it is 0 or NaN.
+  /// `Int64` values are NaN-boxed and converted by the runtime.
+  /// Values that cannot be converted, such as the result of `console.log()`, are reported at `pos`.
+  fn coerce(&mut self, v: Value, ty: types::Type, pos: usize) -> Result<Value, CompileError> {
+    let v_ty = self.get_type(v);
+    if v_ty == ty {
+      return Ok(v);
+    }
+    let v = match (v_ty, ty) {
+      (types::Type::Int1, types::Type::Int64) => {
+        let v = self.builder.build_zext(v, types::Type::Int32);
+        self.call_runtime("rapidus.rt.from_bool", vec![v])
+      }
+      (types::Type::Int32, types::Type::Int64) => self.call_runtime("rapidus.rt.from_i32", vec![v]),
+      (types::Type::F64, types::Type::Int64) => self.call_runtime("rapidus.rt.from_f64", vec![v]),
+      (types::Type::Int64, types::Type::Int32) => self.call_runtime("rapidus.rt.to_i32", vec![v]),
+      (types::Type::Int64, types::Type::F64) => self.call_runtime("rapidus.rt.to_f64", vec![v]),
+      (types::Type::Int64, types::Type::Int1) => {
+        let b = self.call_runtime("rapidus.rt.to_boolean", vec![v]);
+        let zero = Value::Immediate(ImmediateValue::Int32(0));
+        self.builder.build_icmp(ICmpKind::Ne, b, zero)
+      }
+      (types::Type::Int1, types::Type::Int32) => self.builder.build_zext(v, types::Type::Int32),
+      (types::Type::Int1, types::Type::F64) => {
+        let v = self.builder.build_zext(v, types::Type::Int32);
+        self.builder.build_sitofp(v, types::Type::F64)
+      }
+      (types::Type::Int32, types::Type::F64) => self.builder.build_sitofp(v, types::Type::F64),
+      (types::Type::F64, types::Type::Int32) => self.builder.build_fptosi(v, types::Type::Int32),
+      (types::Type::Int32, types::Type::Int1) => {
+        let zero = Value::Immediate(ImmediateValue::Int32(0));
+        self.builder.build_icmp(ICmpKind::Ne, v, zero)
+      }
+      (types::Type::F64, types::Type::Int1) => {
+        let zero = Value::Immediate(ImmediateValue::F64(0.0));
+        self.builder.build_fcmp(FCmpKind::ONe, v, zero)
+      }
+      // E.g. the result of `console.log()`, which the compiler does not give a value.
+      (types::Type::Void, _) => {
+        return Err(CompileError::unsupported(
+          "Using the result of this expression is not supported yet",
+          pos,
+        ));
+      }
+      (from, to) => {
+        return Err(CompileError::unsupported(
+          format!("Conversion from {:?} to {:?} is not supported yet", from, to),
+          pos,
+        ));
+      }
+    };
+    Ok(v)
+  }
+
+  fn push_loop(&mut self, break_bb: BasicBlockId, continue_bb: BasicBlockId) {
+    let labels = ::std::mem::replace(&mut self.pending_labels, vec![]);
+    self.break_targets.push(BreakTarget {
+      labels,
+      break_bb,
+      continue_bb: Some(continue_bb),
+      accepts_unlabeled_break: true,
+    });
+  }
+
+  /// Iterates arrays and strings in the runtime, and other values through the iterator protocol.
+  /// https://tc39.github.io/ecma262/#sec-runtime-semantics-forin-div-ofbodyevaluation-lhs-stmt-iterator-lhskind-labelset
+  fn build_for_of(
+    &mut self,
+    binding: &Node,
+    iterable_v: Value,
+    body: &Node,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let iterable_v = self.coerce(iterable_v, types::Type::Int64, pos)?;
+    let iter_slot = self.iterator_slots[&pos];
+    let fast_iter_v = self.call_runtime("rapidus.rt.for_of_iterator", vec![iterable_v]);
+    let is_fast = self
+      .builder
+      .build_icmp(ICmpKind::Ne, fast_iter_v, boxed_immediate(value::EMPTY));
+    let fast_init_bb = self.builder.append_basic_block();
+    let generic_init_bb = self.builder.append_basic_block();
+    let loop_bb = self.builder.append_basic_block();
+    let fast_bb = self.builder.append_basic_block();
+    let fast_value_bb = self.builder.append_basic_block();
+    let generic_bb = self.builder.append_basic_block();
+    let generic_value_bb = self.builder.append_basic_block();
+    let body_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self
+      .builder
+      .build_cond_br(is_fast, fast_init_bb, generic_init_bb);
+
+    self.builder.set_insert_point(fast_init_bb);
+    self.builder.build_store(fast_iter_v, iter_slot);
+    self.builder.build_br(loop_bb);
+
+    self.builder.set_insert_point(generic_init_bb);
+    let method_v = self.call_runtime("rapidus.rt.iterator_method", vec![iterable_v]);
+    self.build_exception_check(pos);
+    let iter_v = self.build_indirect_call(method_v, iterable_v, vec![], pos)?;
+    let iter_v = self.coerce(iter_v, types::Type::Int64, pos)?;
+    self.builder.build_store(iter_v, iter_slot);
+    self.builder.build_br(loop_bb);
+
+    self.builder.set_insert_point(loop_bb);
+    self.build_gc_safepoint();
+    let iter_v = self.builder.build_load(iter_slot);
+    self.builder.build_cond_br(is_fast, fast_bb, generic_bb);
+
+    self.builder.set_insert_point(fast_bb);
+    let fast_v = self.call_runtime("rapidus.rt.iterator_next", vec![iter_v]);
+    let is_done = self
+      .builder
+      .build_icmp(ICmpKind::Eq, fast_v, boxed_immediate(value::EMPTY));
+    self.builder.build_cond_br(is_done, cont_bb, fast_value_bb);
+    self.builder.set_insert_point(fast_value_bb);
+    self.builder.build_br(body_bb);
+
+    self.builder.set_insert_point(generic_bb);
+    let next_v = self.build_get_member(iter_v, &"next".to_string(), pos)?;
+    let result_v = self.build_indirect_call(next_v, iter_v, vec![], pos)?;
+    let done_v = self.build_get_member(result_v, &"done".to_string(), pos)?;
+    let is_done = self.coerce(done_v, types::Type::Int1, pos)?;
+    self
+      .builder
+      .build_cond_br(is_done, cont_bb, generic_value_bb);
+    self.builder.set_insert_point(generic_value_bb);
+    let generic_v = self.build_get_member(result_v, &"value".to_string(), pos)?;
+    let generic_end_bb = self.build_br_from_new_block(body_bb);
+
+    self.builder.set_insert_point(body_bb);
+    let v = self
+      .builder
+      .build_phi(vec![(fast_v, fast_value_bb), (generic_v, generic_end_bb)]);
+    self.build_for_in_of_body(binding, v, body, loop_bb, cont_bb, pos)?;
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  /// Binds `v` to the head of the for-in/of statement at `pos` and runs its body,
+  /// going back to `loop_bb` for the next value.
+  fn build_for_in_of_body(
+    &mut self,
+    binding: &Node,
+    v: Value,
+    body: &Node,
+    loop_bb: BasicBlockId,
+    cont_bb: BasicBlockId,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    self.push_loop(cont_bb, loop_bb);
+    // Captured bindings of the head are in the block wrapping the statement (see `For`),
+    // and each iteration binds them in a record of its own.
+    if self.block_envs.last() == Some(&pos) {
+      self.build_env_copy(pos);
+    }
+    self.build_binding(binding, v)?;
+    self.visit(body)?;
+    self.break_targets.pop();
+    self.builder.build_br(loop_bb);
+    Ok(())
+  }
+
+  /// Assigns the boxed value `v` to `target`: a declared binding, an assignment target,
+  /// or a destructuring pattern.
+  /// https://tc39.github.io/ecma262/#sec-runtime-semantics-bindinginitialization
+  fn build_binding(&mut self, target: &Node, v: Value) -> Result<(), CompileError> {
+    match &target.base {
+      NodeBase::ForBinding(pattern, _) => self.build_binding(pattern, v)?,
+      NodeBase::Identifier(name) => {
+        let ty = self.var_type(name);
+        let v = self.coerce(v, ty, target.pos)?;
+        self.store_variable(name, v, target.pos)?;
+      }
+      NodeBase::Member(obj, name) => {
+        let obj_v = self.visit(obj)?;
+        self.build_set_member(obj_v, name, v, target.pos)?;
+      }
+      NodeBase::Index(obj, key) => {
+        let obj_v = self.visit(obj)?;
+        let key_v = self.visit(key)?;
+        self.build_set_index(obj_v, key_v, v, target.pos)?;
+      }
+      // Elements are read by index rather than through the iterator protocol.
+      NodeBase::ArrayPattern(elements) => {
+        for (i, element) in elements.iter().enumerate() {
+          if let Some(element) = element {
+            let index_v = Value::Immediate(ImmediateValue::Int32(i as i32));
+            let elem_v = self.build_get_index(v, index_v, target.pos)?;
+            self.build_pattern_element(element, elem_v)?;
+          }
+        }
+      }
+      NodeBase::ObjectPattern(properties) => {
+        for (name, element) in properties {
+          let property_v = self.build_get_member(v, name, target.pos)?;
+          self.build_pattern_element(element, property_v)?;
+        }
+      }
+      _ => {
+        return Err(CompileError::unsupported(
+          "Only identifiers, properties and patterns can be assigned to yet",
+          target.pos,
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Binds `v`, or the default value of `element` if `v` is `undefined`.
+  fn build_pattern_element(
+    &mut self,
+    element: &PatternElement,
+    v: Value,
+  ) -> Result<(), CompileError> {
+    let init = match &element.init {
+      Some(init) => init,
+      None => return self.build_binding(&element.target, v),
+    };
+    let is_undefined = self
+      .builder
+      .build_icmp(ICmpKind::Eq, v, boxed_immediate(value::UNDEFINED));
+    let value_bb = self.builder.append_basic_block();
+    let default_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self
+      .builder
+      .build_cond_br(is_undefined, default_bb, value_bb);
+    self.builder.set_insert_point(value_bb);
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(default_bb);
+    let init_v = self.visit(init)?;
+    let init_v = self.coerce(init_v, types::Type::Int64, init.pos)?;
+    let default_end_bb = self.build_br_from_new_block(cont_bb);
+    self.builder.set_insert_point(cont_bb);
+    let v = self
+      .builder
+      .build_phi(vec![(v, value_bb), (init_v, default_end_bb)]);
+    self.build_binding(&element.target, v)
+  }
+
+  /// Finds the statement that `break label` or `continue label` at `pos` refers to,
+  /// returning its index in `break_targets`.
+  /// Labels are validated by `check_labels` beforehand, so this only fails on a compiler bug.
+  fn find_break_target(
+    &self,
+    label: &Option<String>,
+    is_continue: bool,
+    pos: usize,
+  ) -> Result<usize, CompileError> {
+    let index = self.break_targets.iter().rposition(|target| match label {
+      Some(label) => target.labels.contains(label),
+      None if is_continue => target.continue_bb.is_some(),
+      None => target.accepts_unlabeled_break,
+    });
+    index.ok_or_else(|| {
+      let statement = if is_continue { "continue" } else { "break" };
+      CompileError::syntax_error(format!("Illegal {} statement", statement), pos)
+    })
+  }
+
+  /// Lowers a `switch` statement. The clause bodies are laid out in source order so that
+  /// control falls through from one to the next, and the dispatch jumps into them:
+  /// dense integer cases go through a jump table (see `build_jump_table`) and any other
+  /// cases are tested one by one with strict equality.
+  fn build_switch(
+    &mut self,
+    discriminant: &Node,
+    clauses: &[CaseClause],
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let discriminant_v = self.visit(discriminant)?;
+    let has_env = self.enter_block_env(pos);
+    let body_bbs: Vec<BasicBlockId> =
+      clauses.iter().map(|_| self.builder.append_basic_block()).collect();
+    let cont_bb = self.builder.append_basic_block();
+    let default_bb = match clauses.iter().position(|clause| clause.test.is_none()) {
+      Some(i) => body_bbs[i],
+      None => cont_bb,
+    };
+
+    match self.jump_table(discriminant_v, clauses, &body_bbs) {
+      Some(table) => self.build_jump_table(discriminant_v, table, default_bb),
+      None => {
+        for (clause, &body_bb) in clauses.iter().zip(body_bbs.iter()) {
+          let test = match &clause.test {
+            Some(test) => test,
+            None => continue,
+          };
+          let test_v = self.visit(test)?;
+          let (lhs_v, rhs_v) = self.box_if_types_differ(discriminant_v, test_v, test.pos)?;
+          let cond_v = self.build_compare(
+            ICmpKind::Eq,
+            FCmpKind::OEq,
+            "rapidus.rt.seq",
+            lhs_v,
+            rhs_v,
+            test.pos,
+          )?;
+          let next_bb = self.builder.append_basic_block();
+          self.builder.build_cond_br(cond_v, body_bb, next_bb);
+          self.builder.set_insert_point(next_bb);
+        }
+        self.builder.build_br(default_bb);
+      }
+    }
+
+    self.break_targets.push(BreakTarget {
+      labels: vec![],
+      break_bb: cont_bb,
+      continue_bb: None,
+      accepts_unlabeled_break: true,
+    });
+    for (i, clause) in clauses.iter().enumerate() {
+      self.builder.set_insert_point(body_bbs[i]);
+      for node in &clause.body {
+        self.visit(node)?;
+      }
+      let next_bb = body_bbs.get(i + 1).copied().unwrap_or(cont_bb);
+      self.builder.build_br(next_bb);
+    }
+    self.break_targets.pop();
+    if has_env {
+      self.block_envs.pop();
+    }
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  /// Returns the jump table of a `switch` statement whose cases are dense integer literals
+  /// compared with an integer: the first value of each run of values and the clause body
+  /// they jump to, sorted by value.
+  /// Sparse cases are cheaper to compare one by one, so `None` is returned for them.
+  fn jump_table(
+    &self,
+    discriminant_v: Value,
+    clauses: &[CaseClause],
+    body_bbs: &[BasicBlockId],
+  ) -> Option<Vec<(i32, Option<BasicBlockId>)>> {
+    const MIN_CASES: usize = 4;
+
+    if self.get_type(discriminant_v) != types::Type::Int32 {
+      return None;
+    }
+    let mut cases = vec![];
+    for (clause, &body_bb) in clauses.iter().zip(body_bbs.iter()) {
+      if let Some(test) = &clause.test {
+        cases.push((case_int(test)?, body_bb));
+      }
+    }
+    if cases.len() < MIN_CASES {
+      return None;
+    }
+    let min = cases.iter().map(|(n, _)| *n).min().unwrap();
+    let max = cases.iter().map(|(n, _)| *n).max().unwrap();
+    let span = max as i64 - min as i64 + 1;
+    if span > 2 * cases.len() as i64 {
+      return None;
+    }
+
+    // Holes go to the default clause, which is represented by `None`.
+    let mut table: Vec<Option<BasicBlockId>> = vec![None; span as usize];
+    // The first clause wins if the same value appears twice.
+    for (n, body_bb) in cases.into_iter().rev() {
+      table[(n as i64 - min as i64) as usize] = Some(body_bb);
+    }
+
+    let mut runs: Vec<(i32, Option<BasicBlockId>)> = vec![];
+    if min != ::std::i32::MIN {
+      runs.push((::std::i32::MIN, None));
+    }
+    for (i, dest) in table.into_iter().enumerate() {
+      if runs.last().map(|(_, last)| *last) != Some(dest) {
+        runs.push(((min as i64 + i as i64) as i32, dest));
+      }
+    }
+    if max != ::std::i32::MAX {
+      runs.push((max + 1, None));
+    }
+    Some(runs)
+  }
+
+  /// Jumps to the block that `v` selects in `runs`, which is built by `jump_table` for a
+  /// `switch` statement, and by `compile_dispatcher` from function ids.
+  /// cilk has no indirect branch, so the table is lowered to a balanced tree of comparisons
+  /// against the boundaries of the runs, which takes a logarithmic number of branches.
+  fn build_jump_table(
+    &mut self,
+    v: Value,
+    mut runs: Vec<(i32, Option<BasicBlockId>)>,
+    default_bb: BasicBlockId,
+  ) {
+    if runs.len() == 1 {
+      self.builder.build_br(runs[0].1.unwrap_or(default_bb));
+      return;
+    }
+    let upper = runs.split_off(runs.len() / 2);
+    let boundary = Value::Immediate(ImmediateValue::Int32(upper[0].0));
+    let cond_v = self.builder.build_icmp(ICmpKind::Lt, v, boundary);
+    let lower_bb = self.builder.append_basic_block();
+    let upper_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(cond_v, lower_bb, upper_bb);
+    self.builder.set_insert_point(lower_bb);
+    self.build_jump_table(v, runs, default_bb);
+    self.builder.set_insert_point(upper_bb);
+    self.build_jump_table(v, upper, default_bb);
+  }
+
+  /// Lowers a `try` statement. The `finally` clause is compiled on every path leaving
+  /// the statement: after the block or the `catch` clause completes, before an exception
+  /// propagates, and before `return`, `break` and `continue` (see `build_finally_clauses`).
+  fn build_try(
+    &mut self,
+    try_: &Node,
+    catch: &Node,
+    param: &Node,
+    finally: &Node,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let finally = match finally.base {
+      NodeBase::Nope => None,
+      _ => Some(finally.clone()),
+    };
+    let handler_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.push_try_context(handler_bb, &finally);
+    self.visit(try_)?;
+    self.try_contexts.pop();
+    if let Some(finally) = &finally {
+      self.visit(finally)?;
+    }
+    self.builder.build_br(cont_bb);
+
+    self.builder.set_insert_point(handler_bb);
+    if catch.base == NodeBase::Nope {
+      // The parser rejects a `try` statement that has neither `catch` nor `finally`.
+      self.build_finally_and_rethrow(finally.as_ref().unwrap(), pos)?;
+    } else {
+      // Exceptions thrown in the `catch` clause run the `finally` clause before propagating.
+      let catch_handler_bb = match finally {
+        Some(_) => {
+          let catch_handler_bb = self.builder.append_basic_block();
+          self.push_try_context(catch_handler_bb, &finally);
+          Some(catch_handler_bb)
+        }
+        None => None,
+      };
+      // The parameter and the declarations of the `catch` clause share a scope.
+      let has_env = self.enter_block_env(pos);
+      let exception_v = self.call_runtime("rapidus.rt.take_exception", vec![]);
+      if let NodeBase::Identifier(name) = &param.base {
+        let ty = self.var_type(name);
+        let exception_v = self.coerce(exception_v, ty, param.pos)?;
+        self.store_variable(name, exception_v, param.pos)?;
+      }
+      self.visit(catch)?;
+      if has_env {
+        self.block_envs.pop();
+      }
+      if let (Some(catch_handler_bb), Some(finally)) = (catch_handler_bb, &finally) {
+        self.try_contexts.pop();
+        self.visit(finally)?;
+        self.builder.build_br(cont_bb);
+        self.builder.set_insert_point(catch_handler_bb);
+        self.build_finally_and_rethrow(finally, pos)?;
+      } else {
+        self.builder.build_br(cont_bb);
+      }
+    }
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  fn push_try_context(&mut self, handler_bb: BasicBlockId, finally: &Option<Node>) {
+    self.try_contexts.push(TryContext {
+      handler_bb,
+      finally: finally.clone(),
+      break_depth: self.break_targets.len(),
+      block_env_depth: self.block_envs.len(),
+    });
+  }
+
+  /// Runs `finally` with the pending exception of the `try` statement at `pos` put aside,
+  /// and throws the exception again.
+  fn build_finally_and_rethrow(&mut self, finally: &Node, pos: usize) -> Result<(), CompileError> {
+    let (value_slot, line_slot) = self.exception_slots[&pos];
+    let line_v = self.call_runtime("rapidus.rt.exception_line", vec![]);
+    self.builder.build_store(line_v, line_slot);
+    let exception_v = self.call_runtime("rapidus.rt.take_exception", vec![]);
+    self.builder.build_store(exception_v, value_slot);
+    self.visit(finally)?;
+    let exception_v = self.builder.build_load(value_slot);
+    let line_v = self.builder.build_load(line_slot);
+    self.call_runtime("rapidus.rt.throw", vec![exception_v, line_v]);
+    let handler_bb = self.exception_handler();
+    self.build_jump(handler_bb);
+    Ok(())
+  }
+
+  /// Runs the `finally` clauses of the `try` statements that a jump to a statement enclosed by
+  /// `break_depth` break targets leaves, from the innermost one.
+  /// Each clause is compiled as if outside of its own `try` statement.
+  fn build_finally_clauses(&mut self, break_depth: usize) -> Result<(), CompileError> {
+    let mut left = vec![];
+    while let Some(context) = self.try_contexts.pop() {
+      if context.break_depth < break_depth {
+        self.try_contexts.push(context);
+        break;
+      }
+      if let Some(finally) = &context.finally {
+        let inner_envs = self.block_envs.split_off(context.block_env_depth);
+        self.visit(finally)?;
+        self.block_envs.extend(inner_envs);
+      }
+      left.push(context);
+    }
+    self.try_contexts.extend(left.into_iter().rev());
+    Ok(())
+  }
+
+  /// The block handling an exception thrown at the current insert point.
+  fn exception_handler(&mut self) -> BasicBlockId {
+    if let Some(context) = self.try_contexts.last() {
+      return context.handler_bb;
+    }
+    match self.unwind_bb {
+      Some(unwind_bb) => unwind_bb,
+      None => {
+        let unwind_bb = self.builder.append_basic_block();
+        self.unwind_bb = Some(unwind_bb);
+        unwind_bb
+      }
+    }
+  }
+
+  /// Branches to the exception handler if the call just built, at `pos`, threw an exception.
+  /// An exception thrown by a runtime helper gets the line of `pos` on the way.
+  fn build_exception_check(&mut self, pos: usize) {
+    let pending_v = self.call_runtime("rapidus.rt.has_exception", vec![]);
+    let zero = Value::Immediate(ImmediateValue::Int32(0));
+    let is_pending = self.builder.build_icmp(ICmpKind::Ne, pending_v, zero);
+    let locate_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(is_pending, locate_bb, cont_bb);
+    self.builder.set_insert_point(locate_bb);
+    let line_v = self.line_of(pos);
+    self.call_runtime("rapidus.rt.locate_exception", vec![line_v]);
+    let handler_bb = self.exception_handler();
+    self.builder.build_br(handler_bb);
+    self.builder.set_insert_point(cont_bb);
+  }
+
+  /// The line of the position `pos` in the script, as an `Int32`.
+  fn line_of(&self, pos: usize) -> Value {
+    let line = self
+      .pos_line_list
+      .iter()
+      .take_while(|(start, _)| *start <= pos)
+      .last()
+      .map_or(1, |(_, line)| *line);
+    Value::Immediate(ImmediateValue::Int32(line as i32))
+  }
+
+  /// Jumps to `dest`. Code following the jump is unreachable, so it goes to a new basic block.
+  fn build_jump(&mut self, dest: BasicBlockId) -> Value {
+    self.builder.build_br(dest);
+    let dead_bb = self.builder.append_basic_block();
+    self.builder.set_insert_point(dead_bb);
+    Value::None
+  }
+
+  /// Returns `ret_v` after leaving the GC frame of this call.
+  fn build_return(&mut self, ret_v: Value, pos: usize) -> Result<Value, CompileError> {
+    let ret_ty = self.type_info.function(&self.function_name).ret.to_cilk_type();
+    let ret_v = self.coerce(ret_v, ret_ty.clone(), pos)?;
+    let boxed_ret_v = if ret_ty == types::Type::Int64 {
+      ret_v
+    } else {
+      boxed_immediate(value::UNDEFINED)
+    };
+    let height = self.frame_height;
+    self.call_runtime("rapidus.rt.frame_leave", vec![height, boxed_ret_v]);
+    Ok(self.builder.build_ret(ret_v))
+  }
+
+  /// Drops the temporaries of this call from the GC shadow stack at a loop header,
+  /// where only variables are live, and roots the current values of the variables again.
+  fn build_gc_safepoint(&mut self) {
+    let height = self.frame_height;
+    self.call_runtime("rapidus.rt.frame_reset", vec![height]);
+    if let Some(env) = self.env {
+      self.call_runtime("rapidus.rt.root_env", vec![env]);
+    }
+    for block in self.block_envs.clone() {
+      let env = self.block_env(block);
+      self.call_runtime("rapidus.rt.root_env", vec![env]);
+    }
+    let mut names: Vec<String> = self.variable_map.keys().cloned().collect();
+    names.sort();
+    for name in names {
+      if self.var_type(&name) == types::Type::Int64 {
+        let v = self.builder.build_load(self.variable_map[&name]);
+        self.call_runtime("rapidus.rt.root", vec![v]);
+      }
+    }
+    let mut positions: Vec<usize> = self.exception_slots.keys().cloned().collect();
+    positions.sort();
+    for pos in positions {
+      let (value_slot, _) = self.exception_slots[&pos];
+      let v = self.builder.build_load(value_slot);
+      self.call_runtime("rapidus.rt.root", vec![v]);
+    }
+    let mut positions: Vec<usize> = self.iterator_slots.keys().cloned().collect();
+    positions.sort();
+    for pos in positions {
+      let v = self.builder.build_load(self.iterator_slots[&pos]);
+      self.call_runtime("rapidus.rt.root", vec![v]);
+    }
+  }
+
+  /// Lowers `++` and `--`. A prefix update yields the new value, and a postfix one the old value.
+  /// A variable is updated in `Int32` if `is_int32`, i.e. `typing` proved it cannot overflow.
+  fn build_update(
+    &mut self,
+    target: &Node,
+    is_inc: bool,
+    is_prefix: bool,
+    is_int32: bool,
+  ) -> Result<Value, CompileError> {
+    match &target.base {
+      NodeBase::Identifier(name) => {
+        if !self.is_declared(name) {
+          return Err(not_defined(name, target.pos));
+        }
+        let old_v = self.load_variable(name, target.pos)?;
+        let ty = if is_int32 {
+          types::Type::Int32
+        } else {
+          types::Type::F64
+        };
+        let old_v = self.coerce(old_v, ty, target.pos)?;
+        let new_v = self.build_increment(old_v, is_inc);
+        let ty = self.var_type(name);
+        let stored_v = self.coerce(new_v, ty, target.pos)?;
+        self.store_variable(name, stored_v, target.pos)?;
+        Ok(if is_prefix { new_v } else { old_v })
+      }
+      NodeBase::Member(obj, name) => {
+        let obj_v = self.visit(obj)?;
+        let old_v = self.build_get_member(obj_v, name, target.pos)?;
+        let old_v = self.coerce(old_v, types::Type::F64, target.pos)?;
+        let new_v = self.build_increment(old_v, is_inc);
+        self.build_set_member(obj_v, name, new_v, target.pos)?;
+        Ok(if is_prefix { new_v } else { old_v })
+      }
+      NodeBase::Index(obj, key) => {
+        let obj_v = self.visit(obj)?;
+        let key_v = self.visit(key)?;
+        let old_v = self.build_get_index(obj_v, key_v, target.pos)?;
+        let old_v = self.coerce(old_v, types::Type::F64, target.pos)?;
+        let new_v = self.build_increment(old_v, is_inc);
+        self.build_set_index(obj_v, key_v, new_v, target.pos)?;
+        Ok(if is_prefix { new_v } else { old_v })
+      }
+      _ => Err(CompileError::unsupported(
+        "Only identifiers and properties can be incremented or decremented yet",
+        target.pos,
+      )),
+    }
+  }
+
+  /// Adds or subtracts 1 to the `Int32` or `F64` `v`.
+  fn build_increment(&mut self, v: Value, is_inc: bool) -> Value {
+    let one = if self.get_type(v) == types::Type::Int32 {
+      Value::Immediate(ImmediateValue::Int32(1))
+    } else {
+      Value::Immediate(ImmediateValue::F64(1.0))
+    };
+    if is_inc {
+      self.builder.build_add(v, one)
+    } else {
+      self.builder.build_sub(v, one)
+    }
+  }
+
+  /// The type `+`, `-`, `*` and `%` compute `node` in: `Int32` where `typing` proved the result
+  /// is one, and `F64` otherwise.
+  fn arithmetic_type(&self, node: &Node) -> types::Type {
+    if self.type_info.int32_binary_ops.contains(&node.pos) {
+      types::Type::Int32
+    } else {
+      types::Type::F64
+    }
+  }
+
+  /// Lowers `typeof expr == 'type'` and its variants.
+  fn build_typeof_comparison(
+    &mut self,
+    lhs: &Node,
+    rhs: &Node,
+    op: &BinOp,
+  ) -> Result<Value, CompileError> {
+    let (expr, type_name) = match (&lhs.base, &rhs.base) {
+      (NodeBase::UnaryOp(expr, UnaryOp::Typeof), NodeBase::String(type_name))
+      | (NodeBase::String(type_name), NodeBase::UnaryOp(expr, UnaryOp::Typeof)) => {
+        (expr, type_name)
+      }
+      _ => unreachable!(),
+    };
+    let is_eq = match op {
+      BinOp::Eq | BinOp::SEq => true,
+      _ => false,
+    };
+    let expected = match value::TYPEOF_NAMES.iter().position(|name| *name == type_name.as_str()) {
+      Some(expected) => expected as i32,
+      // typeof never yields such a string.
+      None => return Ok(Value::Immediate(ImmediateValue::Int1(!is_eq))),
+    };
+    let actual = match self.visit_typeof_operand(expr)? {
+      TypeofOperand::Known(actual) => actual,
+      TypeofOperand::Dynamic(v) => {
+        let code = self.call_runtime("rapidus.rt.typeof_code", vec![v]);
+        let expected = Value::Immediate(ImmediateValue::Int32(expected));
+        let kind = if is_eq { ICmpKind::Eq } else { ICmpKind::Ne };
+        return Ok(self.builder.build_icmp(kind, code, expected));
+      }
+    };
+    Ok(Value::Immediate(ImmediateValue::Int1(
+      (actual as i32 == expected) == is_eq,
+    )))
+  }
+
+  /// Evaluates the operand of `typeof`. An undeclared identifier is not an error there.
+  fn visit_typeof_operand(&mut self, expr: &Node) -> Result<TypeofOperand, CompileError> {
+    if let NodeBase::Identifier(name) = &expr.base {
+      if !self.is_declared(name) && name != "undefined" {
+        return Ok(TypeofOperand::Known(
+          match self.type_info.resolve_function(&self.function_name, name) {
+            Some(_) => value::TYPEOF_FUNCTION,
+            None => value::TYPEOF_UNDEFINED,
+          },
+        ));
+      }
+    }
+    let v = self.visit(expr)?;
+    Ok(match self.get_type(v) {
+      types::Type::Int1 => TypeofOperand::Known(value::TYPEOF_BOOLEAN),
+      types::Type::Int32 | types::Type::F64 => TypeofOperand::Known(value::TYPEOF_NUMBER),
+      _ => TypeofOperand::Dynamic(v),
+    })
+  }
+
+  /// Lowers `cond ? then_ : else_`. Only the selected arm is evaluated.
+  fn build_conditional(
+    &mut self,
+    cond: &Node,
+    then_: &Node,
+    else_: &Node,
+  ) -> Result<Value, CompileError> {
+    let cond_v = self.visit(cond)?;
+    let cond_v = self.coerce(cond_v, types::Type::Int1, cond.pos)?;
+    let then_bb = self.builder.append_basic_block();
+    let else_bb = self.builder.append_basic_block();
+    let then_exit_bb = self.builder.append_basic_block();
+    let else_exit_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    self.builder.build_cond_br(cond_v, then_bb, else_bb);
+    self.builder.set_insert_point(then_bb);
+    let then_v = self.visit(then_)?;
+    self.builder.build_br(then_exit_bb);
+    self.builder.set_insert_point(else_bb);
+    let else_v = self.visit(else_)?;
+    self.builder.build_br(else_exit_bb);
+
+    let ty = self.join_types(then_v, else_v);
+    self.builder.set_insert_point(then_exit_bb);
+    let then_v = self.coerce(then_v, ty.clone(), then_.pos)?;
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(else_exit_bb);
+    let else_v = self.coerce(else_v, ty, else_.pos)?;
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(cont_bb);
+    Ok(self
+      .builder
+      .build_phi(vec![(then_v, then_exit_bb), (else_v, else_exit_bb)]))
+  }
+
+  /// Lowers `lhs && rhs` and `lhs || rhs`.
+  /// The right-hand side runs only when the left-hand side does not decide the result,
+  /// and the result is the value of the operand that was evaluated last.
+  fn build_logical(&mut self, lhs: &Node, rhs: &Node, op: &BinOp) -> Result<Value, CompileError> {
+    let lhs_v = self.visit(lhs)?;
+    let cond_v = self.coerce(lhs_v, types::Type::Int1, lhs.pos)?;
+    let rhs_bb = self.builder.append_basic_block();
+    let lhs_exit_bb = self.builder.append_basic_block();
+    let rhs_exit_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    match op {
+      BinOp::LAnd => self.builder.build_cond_br(cond_v, rhs_bb, lhs_exit_bb),
+      _ => self.builder.build_cond_br(cond_v, lhs_exit_bb, rhs_bb),
+    };
+    self.builder.set_insert_point(rhs_bb);
+    let rhs_v = self.visit(rhs)?;
+    self.builder.build_br(rhs_exit_bb);
+
+    let ty = self.join_types(lhs_v, rhs_v);
+    self.builder.set_insert_point(lhs_exit_bb);
+    let lhs_v = self.coerce(lhs_v, ty.clone(), lhs.pos)?;
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(rhs_exit_bb);
+    let rhs_v = self.coerce(rhs_v, ty, rhs.pos)?;
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(cont_bb);
+    Ok(self
+      .builder
+      .build_phi(vec![(lhs_v, lhs_exit_bb), (rhs_v, rhs_exit_bb)]))
+  }
+
+  /// The type that can hold both `lhs` and `rhs` without losing information.
+  fn join_types(&self, lhs: Value, rhs: Value) -> types::Type {
+    match (self.get_type(lhs), self.get_type(rhs)) {
+      (lhs_ty, rhs_ty) if lhs_ty == rhs_ty => lhs_ty,
+      (types::Type::Int32, types::Type::F64) | (types::Type::F64, types::Type::Int32) => {
+        types::Type::F64
+      }
+      _ => types::Type::Int64,
+    }
+  }
+
+  /// ToNumber: https://tc39.github.io/ecma262/#sec-tonumber
+  fn to_number(&mut self, v: Value, pos: usize) -> Result<Value, CompileError> {
+    match self.get_type(v) {
+      types::Type::Int32 | types::Type::F64 => Ok(v),
+      types::Type::Int1 => self.coerce(v, types::Type::Int32, pos),
+      _ => self.coerce(v, types::Type::F64, pos),
+    }
+  }
+
+  /// ToInt32: https://tc39.github.io/ecma262/#sec-toint32
+  fn to_int32(&mut self, v: Value, pos: usize) -> Result<Value, CompileError> {
+    match self.get_type(v) {
+      types::Type::F64 => Ok(self.call_runtime("rapidus.rt.f64_to_int32", vec![v])),
+      _ => self.coerce(v, types::Type::Int32, pos),
+    }
+  }
+
+  /// Only the lower 5 bits of the right operand of a shift operator are used.
+  fn to_shift_count(&mut self, v: Value, pos: usize) -> Result<Value, CompileError> {
+    let v = self.to_int32(v, pos)?;
+    Ok(self
+      .builder
+      .build_and(v, Value::Immediate(ImmediateValue::Int32(0x1f))))
+  }
+
+  /// Strict equality never converts its operands,
+  /// so operands of different static types are compared as boxed values.
+  fn box_if_types_differ(
+    &mut self,
+    lhs: Value,
+    rhs: Value,
+    pos: usize,
+  ) -> Result<(Value, Value), CompileError> {
+    let lhs_ty = self.get_type(lhs);
+    let rhs_ty = self.get_type(rhs);
+    let is_number = |ty: &types::Type| *ty == types::Type::Int32 || *ty == types::Type::F64;
+    if lhs_ty == rhs_ty || (is_number(&lhs_ty) && is_number(&rhs_ty)) {
+      return Ok((lhs, rhs));
+    }
+    let lhs = self.coerce(lhs, types::Type::Int64, pos)?;
+    let rhs = self.coerce(rhs, types::Type::Int64, pos)?;
+    Ok((lhs, rhs))
+  }
+
+  /// Compares two values, using an integer comparison only when both sides are integers.
+  /// If either side is dynamically typed, the runtime function `rt_name` decides the result.
+  fn build_compare(
+    &mut self,
+    ikind: ICmpKind,
+    fkind: FCmpKind,
+    rt_name: &str,
+    lhs: Value,
+    rhs: Value,
+    pos: usize,
+  ) -> Result<Value, CompileError> {
+    if self.is_dynamic(lhs) || self.is_dynamic(rhs) {
+      let lhs = self.coerce(lhs, types::Type::Int64, pos)?;
+      let rhs = self.coerce(rhs, types::Type::Int64, pos)?;
+      let b = self.call_runtime(rt_name, vec![lhs, rhs]);
+      return self.coerce(b, types::Type::Int1, pos);
+    }
+    if self.get_type(lhs) == types::Type::Int32 && self.get_type(rhs) == types::Type::Int32 {
+      return Ok(self.builder.build_icmp(ikind, lhs, rhs));
+    }
+    let lhs = self.coerce(lhs, types::Type::F64, pos)?;
+    let rhs = self.coerce(rhs, types::Type::F64, pos)?;
+    Ok(self.builder.build_fcmp(fkind, lhs, rhs))
+  }
+
+  /// Prints a number like `console.log` does: integral values are printed without a fraction.
+  fn build_println(&mut self, v: Value, pos: usize) -> Result<(), CompileError> {
+    let println_i32 = self.find_function("cilk.println.i32");
+    let println_f64 = self.find_function("cilk.println.f64");
+    match self.get_type(v) {
+      types::Type::F64 => {}
+      types::Type::Int1 | types::Type::Int64 => {
+        let v = self.coerce(v, types::Type::Int64, pos)?;
+        self.call_runtime("rapidus.rt.println", vec![v]);
+        return Ok(());
+      }
+      _ => {
+        let v = self.coerce(v, types::Type::Int32, pos)?;
+        self.builder.build_call(Value::Function(println_i32), vec![v]);
+        return Ok(());
+      }
+    }
+    let int_bb = self.builder.append_basic_block();
+    let float_bb = self.builder.append_basic_block();
+    let cont_bb = self.builder.append_basic_block();
+    let v_i32 = self.builder.build_fptosi(v, types::Type::Int32);
+    let v_back = self.builder.build_sitofp(v_i32, types::Type::F64);
+    let is_int = self.builder.build_fcmp(FCmpKind::OEq, v, v_back);
+    self.builder.build_cond_br(is_int, int_bb, float_bb);
+    self.builder.set_insert_point(int_bb);
+    self.builder.build_call(Value::Function(println_i32), vec![v_i32]);
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(float_bb);
+    self.builder.build_call(Value::Function(println_f64), vec![v]);
+    self.builder.build_br(cont_bb);
+    self.builder.set_insert_point(cont_bb);
+    Ok(())
+  }
+
+  /// A string literal, allocated once per module and freed with it.
+  fn string_literal(&mut self, s: &str) -> Value {
+    boxed_immediate(self.constants.string(s))
+  }
+
+  fn find_function(&self, name: &str) -> FunctionId {
+    self.builder.module.find_function_by_name(name).unwrap()
+  }
+
+  fn call_runtime(&mut self, name: &str, args: Vec<Value>) -> Value {
+    let func_id = self.find_function(name);
+    self.builder.build_call(Value::Function(func_id), args)
   }
 }
@@ -1,5 +1,5 @@
 use crate::parser::Error;
-use crate::token::{convert_reserved_keyword, Kind, Symbol, Token};
+use crate::token::{convert_reserved_keyword, Keyword, Kind, Symbol, TemplatePart, Token};
 
 use std::collections::VecDeque;
 
@@ -32,6 +32,35 @@ pub struct Lexer {
 
     /// Saved states
     pub states: Vec<usize>,
+
+    /// Number of unclosed braces in each template substitution (``${ ... }``) being read.
+    /// The ``}`` closing a substitution itself starts the rest of the template.
+    pub template_braces: Vec<usize>,
+
+    /// For each unclosed ``(``, whether it opens the head of an ``if``, ``while`` or ``for``.
+    pub parens: Vec<bool>,
+
+    /// Whether the last ``)`` closed the head of an ``if``, ``while`` or ``for``,
+    /// so that a statement starts after it.
+    pub closed_head: bool,
+
+    /// The states at each ``/`` or ``/=`` read as a division, in the order of the tokens
+    /// (see ``reread_as_regexp``).
+    pub divisions: Vec<Division>,
+}
+
+/// The state of the lexer at a ``/`` or ``/=`` read as a division, from which the rest of the
+/// script is read again if it starts a regular expression literal instead.
+#[derive(Clone, Debug)]
+pub struct Division {
+    /// Position of the token in ``buf``.
+    pub index: usize,
+    pub line: usize,
+    /// Length of ``pos_line_list``.
+    pub lines: usize,
+    pub template_braces: Vec<usize>,
+    pub parens: Vec<bool>,
+    pub closed_head: bool,
 }
 
 impl Lexer {
@@ -45,6 +74,10 @@ impl Lexer {
             token_pos: 0,
             prev_token_pos: 0,
             states: vec![],
+            template_braces: vec![],
+            parens: vec![],
+            closed_head: false,
+            divisions: vec![],
         }
     }
 
@@ -52,27 +85,78 @@ impl Lexer {
     pub fn tokenize_all(&mut self) -> Result<(), Error> {
         loop {
             match self.tokenize() {
-                Ok(tok) => self.buf.push_back(tok),
-                Err(Error::NormalEOF) => break,
-                Err(err) => {
-                    // When error occurs in tokenizer, pos_line_list is not completed.
-                    self.skip_char_while(|c| c != '\n')?;
-                    self.take_char().unwrap_or(' ');
-                    self.pos_line_list.push((self.pos + 1, self.line + 1));
-                    return Err(err);
+                Ok(mut tok) => {
+                    self.track_parens(&tok);
+                    self.save_division(&tok);
+                    tok.prev_pos = self.buf.back().map_or(0, |prev| prev.pos);
+                    self.buf.push_back(tok)
                 }
+                Err(Error::NormalEOF) => break,
+                Err(err) => return Err(self.complete_line(err)),
             };
         }
 
         self.pos_line_list.push((self.pos + 1, self.line + 1));
 
-        let mut prev_pos = 0;
-        for mut tok in &mut self.buf {
-            tok.prev_pos = prev_pos;
-            prev_pos = tok.pos;
+        Ok(())
+    }
+
+    /// When error occurs in tokenizer, pos_line_list is not completed.
+    fn complete_line(&mut self, err: Error) -> Error {
+        if let Err(err) = self.skip_char_while(|c| c != '\n') {
+            return err;
         }
+        self.take_char().unwrap_or(' ');
+        self.pos_line_list.push((self.pos + 1, self.line + 1));
+        err
+    }
 
-        Ok(())
+    /// Saves the state at ``tok`` if it is a division, which ``reread_as_regexp`` goes back to.
+    fn save_division(&mut self, tok: &Token) {
+        match tok.kind {
+            Kind::Symbol(Symbol::Div) | Kind::Symbol(Symbol::AssignDiv) => {
+                self.divisions.push(Division {
+                    index: self.buf.len(),
+                    line: self.line,
+                    lines: self.pos_line_list.len(),
+                    template_braces: self.template_braces.clone(),
+                    parens: self.parens.clone(),
+                    closed_head: self.closed_head,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    /// Reads the ``/`` or ``/=`` just returned by ``next()`` as the start of a regular expression
+    /// literal instead, and returns the literal. The parser calls it when it finds the token
+    /// where an expression starts, which ``regexp_allowed`` cannot always tell.
+    /// The tokens after the literal were read as code, so the script is tokenized again from it.
+    pub fn reread_as_regexp(&mut self) -> Result<Token, Error> {
+        let index = self.token_pos - 1;
+        let at = self
+            .divisions
+            .binary_search_by_key(&index, |division| division.index)
+            .unwrap();
+        // The divisions after it are read again as well.
+        self.divisions.truncate(at + 1);
+        let division = self.divisions.pop().unwrap();
+        let prev_pos = self.buf[index].prev_pos;
+        self.pos = self.buf[index].pos;
+        self.buf.truncate(index);
+        self.line = division.line;
+        self.pos_line_list.truncate(division.lines);
+        self.template_braces = division.template_braces;
+        self.parens = division.parens;
+        self.closed_head = division.closed_head;
+        let mut tok = match self.read_regexp_literal() {
+            Ok(tok) => tok,
+            Err(err) => return Err(self.complete_line(err)),
+        };
+        tok.prev_pos = prev_pos;
+        self.buf.push_back(tok);
+        self.tokenize_all()?;
+        Ok(self.buf[index].clone())
     }
 
     pub fn print_buf(&self) {
@@ -235,6 +319,9 @@ impl Lexer {
             'a'..='z' | 'A'..='Z' | '_' | '$' => self.read_identifier(),
             '0'..='9' => self.read_number(),
             '\'' | '\"' => self.read_string_literal(),
+            '`' => self.read_template(),
+            '}' if self.template_braces.last() == Some(&0) => self.read_template(),
+            '/' if self.regexp_allowed() => self.read_regexp_literal(),
             '\n' => self.read_line_terminator(),
             c if c.is_whitespace() => {
                 self.skip_whitespace()?;
@@ -414,6 +501,152 @@ impl Lexer {
         Ok(Token::new_string(s, pos))
     }
 
+    /// Read a template token, which starts at a backquote or at the ``}`` closing a substitution.
+    fn read_template(&mut self) -> Result<Token, Error> {
+        let pos = self.pos;
+        let is_head = self.take_char()? == '`';
+        let mut cooked = "".to_string();
+        let raw_start = self.pos;
+        let (part, raw_end) = loop {
+            let raw_end = self.pos;
+            let c = match self.take_char() {
+                Ok(c) => c,
+                Err(Error::NormalEOF) => {
+                    return Err(Error::General(pos, "unterminated template".to_string()));
+                }
+                Err(err) => return Err(err),
+            };
+            match c {
+                '`' if is_head => break (TemplatePart::NoSubstitution, raw_end),
+                '`' => {
+                    self.template_braces.pop();
+                    break (TemplatePart::Tail, raw_end);
+                }
+                '$' if self.take_char_if('{')? => {
+                    // A middle part stays in the substitution opened by the head.
+                    if is_head {
+                        self.template_braces.push(0);
+                        break (TemplatePart::Head, raw_end);
+                    }
+                    break (TemplatePart::Middle, raw_end);
+                }
+                // Line continuation
+                '\\' if self.take_char_if('\n')? => {
+                    self.line += 1;
+                    self.pos_line_list.push((self.pos, self.line));
+                }
+                '\\' => {
+                    for c in self.read_escaped_char()? {
+                        cooked.push(c)
+                    }
+                }
+                '\n' => {
+                    cooked.push(c);
+                    self.line += 1;
+                    self.pos_line_list.push((self.pos, self.line));
+                }
+                c => cooked.push(c),
+            }
+        };
+        let raw = self.code[raw_start..raw_end].to_string();
+        Ok(Token::new_template(part, cooked, raw, pos))
+    }
+
+    /// Whether a ``/`` starts a regular expression literal rather than a division.
+    /// That depends on the previous token, of which only those an expression clearly follows
+    /// are recognized here. After the others, a ``/`` is read as a division,
+    /// and the parser has it read again if an expression starts there.
+    fn regexp_allowed(&self) -> bool {
+        let prev = self
+            .buf
+            .iter()
+            .rev()
+            .find(|tok| tok.kind != Kind::LineTerminator);
+        match prev.map(|tok| &tok.kind) {
+            None => true,
+            Some(Kind::Keyword(Keyword::This)) | Some(Kind::Keyword(Keyword::Arguments)) => false,
+            Some(Kind::Keyword(_)) => true,
+            Some(Kind::Template(TemplatePart::Head, _, _))
+            | Some(Kind::Template(TemplatePart::Middle, _, _)) => true,
+            Some(Kind::Symbol(Symbol::ClosingParen)) => self.closed_head,
+            Some(Kind::Symbol(Symbol::OpeningParen))
+            | Some(Kind::Symbol(Symbol::OpeningBrace))
+            | Some(Kind::Symbol(Symbol::ClosingBrace))
+            | Some(Kind::Symbol(Symbol::OpeningBoxBracket))
+            | Some(Kind::Symbol(Symbol::Comma))
+            | Some(Kind::Symbol(Symbol::Semicolon))
+            | Some(Kind::Symbol(Symbol::Colon))
+            | Some(Kind::Symbol(Symbol::Not))
+            | Some(Kind::Symbol(Symbol::Question))
+            | Some(Kind::Symbol(Symbol::Assign))
+            | Some(Kind::Symbol(Symbol::LAnd))
+            | Some(Kind::Symbol(Symbol::LOr))
+            | Some(Kind::Symbol(Symbol::FatArrow)) => true,
+            _ => false,
+        }
+    }
+
+    /// Keeps track of whether a ``)`` closes the head of an ``if``, ``while`` or ``for``,
+    /// where a ``/`` after it starts a statement and so a regular expression literal.
+    fn track_parens(&mut self, tok: &Token) {
+        match tok.kind {
+            Kind::Symbol(Symbol::OpeningParen) => {
+                let prev = self
+                    .buf
+                    .iter()
+                    .rev()
+                    .find(|tok| tok.kind != Kind::LineTerminator);
+                let opens_head = match prev.map(|tok| &tok.kind) {
+                    Some(Kind::Keyword(Keyword::If))
+                    | Some(Kind::Keyword(Keyword::While))
+                    | Some(Kind::Keyword(Keyword::For)) => true,
+                    _ => false,
+                };
+                self.parens.push(opens_head);
+            }
+            Kind::Symbol(Symbol::ClosingParen) => {
+                self.closed_head = self.parens.pop().unwrap_or(false);
+            }
+            _ => {}
+        }
+    }
+
+    /// Read a regular expression literal. Its pattern is parsed when it is compiled.
+    fn read_regexp_literal(&mut self) -> Result<Token, Error> {
+        let pos = self.pos;
+        assert_eq!(self.take_char()?, '/');
+        let mut pattern = "".to_string();
+        let mut in_class = false;
+        let mut escaped = false;
+        loop {
+            let c = match self.take_char() {
+                Ok('\n') | Err(Error::NormalEOF) => {
+                    return Err(Error::General(
+                        pos,
+                        "unterminated regular expression literal".to_string(),
+                    ));
+                }
+                Ok(c) => c,
+                Err(err) => return Err(err),
+            };
+            // A ``/`` in a class or escaped by a backslash does not end the literal.
+            if escaped {
+                escaped = false;
+            } else {
+                match c {
+                    '/' if !in_class => break,
+                    '[' => in_class = true,
+                    ']' => in_class = false,
+                    '\\' => escaped = true,
+                    _ => {}
+                }
+            }
+            pattern.push(c);
+        }
+        let flags = self.take_char_while(|c| c.is_alphanumeric())?;
+        Ok(Token::new_regexp(pattern, flags, pos))
+    }
+
     // TODO: Support more escape sequences
     fn read_escaped_char(&mut self) -> Result<Vec<char>, Error> {
         let c = self.take_char()?;
@@ -607,8 +840,18 @@ impl Lexer {
             ')' => symbol = Symbol::ClosingParen,
             '[' => symbol = Symbol::OpeningBoxBracket,
             ']' => symbol = Symbol::ClosingBoxBracket,
-            '{' => symbol = Symbol::OpeningBrace,
-            '}' => symbol = Symbol::ClosingBrace,
+            '{' => {
+                if let Some(braces) = self.template_braces.last_mut() {
+                    *braces += 1;
+                }
+                symbol = Symbol::OpeningBrace
+            }
+            '}' => {
+                if let Some(braces) = self.template_braces.last_mut() {
+                    *braces -= 1;
+                }
+                symbol = Symbol::ClosingBrace
+            }
             ',' => symbol = Symbol::Comma,
             ';' => symbol = Symbol::Semicolon,
             ':' => symbol = Symbol::Colon,
@@ -978,3 +1221,155 @@ fn comment() {
         Kind::Identifier("y".to_string())
     );
 }
+
+#[test]
+fn template() {
+    let mut lexer = Lexer::new("`a${ {b: 1}.b }c\\n` `${x}${y}`".to_string());
+    lexer.tokenize_all().unwrap();
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Template(TemplatePart::Head, "a".to_string(), "a".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Symbol(Symbol::OpeningBrace)
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("b".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Colon));
+    assert_eq!(lexer.next().unwrap().kind, Kind::Number(1.0));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Symbol(Symbol::ClosingBrace)
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Point));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("b".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Template(TemplatePart::Tail, "c\n".to_string(), "c\\n".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Template(TemplatePart::Head, "".to_string(), "".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("x".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Template(TemplatePart::Middle, "".to_string(), "".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("y".to_string())
+    );
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Template(TemplatePart::Tail, "".to_string(), "".to_string())
+    );
+    assert!(lexer.template_braces.is_empty());
+}
+
+#[test]
+fn regexp() {
+    let mut lexer = Lexer::new("x = /[/\\]]+/gi; a / b / c; (1) / 2, /\\//".to_string());
+    lexer.tokenize_all().unwrap();
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("x".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Assign));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::RegExp("[/\\]]+".to_string(), "gi".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Semicolon));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("a".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Div));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("b".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Div));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Identifier("c".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Semicolon));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Symbol(Symbol::OpeningParen)
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Number(1.0));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::Symbol(Symbol::ClosingParen)
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Div));
+    assert_eq!(lexer.next().unwrap().kind, Kind::Number(2.0));
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Comma));
+    assert_eq!(
+        lexer.next().unwrap().kind,
+        Kind::RegExp("\\/".to_string(), "".to_string())
+    );
+
+    let mut lexer = Lexer::new("/abc\n/".to_string());
+    assert!(lexer.tokenize_all().is_err());
+}
+
+#[test]
+fn regexp_after_paren() {
+    let mut lexer = Lexer::new(
+        "if (ok) /x/.test(s)\nwhile (f(c)) /a/g.exec(s)\nfor (;;) /b/\n(a) / b / c".to_string(),
+    );
+    lexer.tokenize_all().unwrap();
+    let slashes: Vec<Kind> = lexer
+        .buf
+        .iter()
+        .map(|tok| tok.kind.clone())
+        .filter(|kind| match kind {
+            Kind::RegExp(_, _) | Kind::Symbol(Symbol::Div) => true,
+            _ => false,
+        })
+        .collect();
+    assert_eq!(
+        slashes,
+        vec![
+            Kind::RegExp("x".to_string(), "".to_string()),
+            Kind::RegExp("a".to_string(), "g".to_string()),
+            Kind::RegExp("b".to_string(), "".to_string()),
+            Kind::Symbol(Symbol::Div),
+            Kind::Symbol(Symbol::Div),
+        ]
+    );
+}
+
+#[test]
+fn reread_as_regexp() {
+    let mut lexer = Lexer::new("a * /b/g\n/=c/".to_string());
+    lexer.tokenize_all().unwrap();
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::Div));
+    assert_eq!(
+        lexer.reread_as_regexp().unwrap().kind,
+        Kind::RegExp("b".to_string(), "g".to_string())
+    );
+    assert_eq!(lexer.next().unwrap().kind, Kind::LineTerminator);
+    assert_eq!(lexer.next().unwrap().kind, Kind::Symbol(Symbol::AssignDiv));
+    assert_eq!(
+        lexer.reread_as_regexp().unwrap().kind,
+        Kind::RegExp("=c".to_string(), "".to_string())
+    );
+    assert!(lexer.next().is_err());
+    assert_eq!(lexer.pos_line_list, vec![(0, 1), (9, 2), (14, 3)]);
+}
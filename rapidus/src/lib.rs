@@ -1,10 +1,14 @@
 #![feature(box_patterns)]
 #![feature(repeat_generic_slice)]
 #![feature(type_ascription)]
+pub mod error;
 pub mod lexer;
 pub mod node;
 pub mod parser;
+pub mod runtime;
+pub mod scope;
 pub mod token;
+pub mod typing;
 pub mod util;
 pub mod executer;
 
@@ -3,7 +3,8 @@ extern crate clap;
 extern crate libc;
 extern crate rustyline;
 use clap::{App, Arg};
-use rapidus::executer;
+use rapidus::error::CompileError;
+use rapidus::{executer, parser, runtime};
 
 const VERSION_STR: &'static str = env!("CARGO_PKG_VERSION");
 
@@ -12,7 +13,17 @@ pub fn main() {
     .version(VERSION_STR)
     .author("uint256_t")
     .about("A toy JavaScript engine")
-    .arg(Arg::with_name("file").help("Input file name").index(1));
+    .arg(Arg::with_name("file").help("Input file name").index(1))
+    .arg(
+      Arg::with_name("gc-stats")
+        .long("gc-stats")
+        .help("Print statistics of the garbage collector after execution"),
+    )
+    .arg(
+      Arg::with_name("ic-stats")
+        .long("ic-stats")
+        .help("Print the hit rate of inline caches after execution"),
+    );
   let app_matches = app.clone().get_matches();
   let file_name = match app_matches.value_of("file") {
     Some(file_name) => file_name,
@@ -20,8 +31,33 @@ pub fn main() {
       return;
     }
   };
-  
-  let mut m = executer::compile_file(file_name).unwrap();
+
+  let mut parser = match parser::Parser::load_module(file_name) {
+    Ok(parser) => parser,
+    Err(err) => {
+      eprintln!("Error: {}", CompileError::from_parse_error(&err, 0).message);
+      std::process::exit(1);
+    }
+  };
+  let mut m = match executer::compile(&mut parser) {
+    Ok(m) => m,
+    Err(err) => {
+      err.show(&parser);
+      std::process::exit(1);
+    }
+  };
   let ret = executer::execute_jit(&mut m);
-  println!("jit: {:?}", ret);
+  match &ret {
+    Ok(v) => println!("jit: {:?}", v),
+    Err(msg) => eprintln!("{}", msg),
+  }
+  if app_matches.is_present("gc-stats") {
+    runtime::gc::print_stats();
+  }
+  if app_matches.is_present("ic-stats") {
+    runtime::ic::print_stats(m.inline_caches());
+  }
+  if ret.is_err() {
+    std::process::exit(1);
+  }
 }
@@ -19,6 +19,40 @@ impl FormalParameter {
     }
 }
 
+/// A `case` or `default` clause of a switch statement. `test` is `None` for `default`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseClause {
+    pub test: Option<Node>,
+    pub body: Vec<Node>,
+}
+
+impl CaseClause {
+    pub fn new(test: Option<Node>, body: Vec<Node>) -> CaseClause {
+        CaseClause {
+            test: test,
+            body: body,
+        }
+    }
+}
+
+/// An element of a destructuring pattern: the target, which is an identifier, a property or
+/// a nested pattern, and the default value used when the value is `undefined`.
+// TODO: Support rest elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternElement {
+    pub target: Node,
+    pub init: Option<Node>,
+}
+
+impl PatternElement {
+    pub fn new(target: Node, init: Option<Node>) -> PatternElement {
+        PatternElement {
+            target: target,
+            init: init,
+        }
+    }
+}
+
 // TODO: Support all features: https://tc39.github.io/ecma262/#prod-PropertyDefinition
 #[derive(Clone, Debug, PartialEq)]
 pub enum PropertyDefinition {
@@ -61,7 +95,14 @@ pub enum NodeBase {
     Call(Box<Node>, Vec<Node>),
     If(Box<Node>, Box<Node>, Box<Node>), // Cond, Then, Else
     While(Box<Node>, Box<Node>),         // Cond, Body
+    DoWhile(Box<Node>, Box<Node>),       // Body, Cond
     For(Box<Node>, Box<Node>, Box<Node>, Box<Node>), // Init, Cond, Step, Body
+    ForIn(Box<Node>, Box<Node>, Box<Node>), // Binding, object, body
+    ForOf(Box<Node>, Box<Node>, Box<Node>), // Binding, iterable, body
+    ForBinding(Box<Node>, VarKind),      // Identifier or pattern declared in a for-in/of head
+    ArrayPattern(Vec<Option<PatternElement>>), // Elements, `None` for holes
+    ObjectPattern(Vec<(String, PatternElement)>), // Property names and elements
+    Switch(Box<Node>, Vec<CaseClause>),  // Discriminant, clauses
     Assign(Box<Node>, Box<Node>),
     UnaryOp(Box<Node>, UnaryOp),
     BinaryOp(Box<Node>, Box<Node>, BinOp),
@@ -74,6 +115,9 @@ pub enum NodeBase {
     Throw(Box<Node>),
     Array(Vec<Node>),
     Object(Vec<PropertyDefinition>),
+    TemplateLiteral(Vec<String>, Vec<Node>), // Strings, substitutions between them
+    TemplateStrings(Vec<String>, Vec<String>), // Cooked strings, raw strings. Passed to a tag.
+    RegExp(String, String),                  // Pattern, flags
     Identifier(String),
     This,
     // Arguments,
@@ -92,6 +136,32 @@ impl Node {
             pos: pos,
         }
     }
+
+    /// The names of the variables a binding target or a destructuring pattern assigns to.
+    /// https://tc39.github.io/ecma262/#sec-static-semantics-boundnames
+    pub fn bound_names(&self) -> Vec<&String> {
+        let mut names = vec![];
+        self.collect_bound_names(&mut names);
+        names
+    }
+
+    fn collect_bound_names<'a>(&'a self, names: &mut Vec<&'a String>) {
+        match &self.base {
+            NodeBase::Identifier(name) => names.push(name),
+            NodeBase::ForBinding(target, _) => target.collect_bound_names(names),
+            NodeBase::ArrayPattern(elements) => {
+                for element in elements.iter().flatten() {
+                    element.target.collect_bound_names(names);
+                }
+            }
+            NodeBase::ObjectPattern(properties) => {
+                for (_, element) in properties {
+                    element.target.collect_bound_names(names);
+                }
+            }
+            _ => {}
+        }
+    }
     /*
     pub fn definitely_returns(&self) -> bool {
         match self.base {
@@ -253,6 +323,7 @@ pub enum BinOp {
     Gt,
     Le,
     Ge,
+    InstanceOf,
     Shl,
     Shr,
     ZFShr,
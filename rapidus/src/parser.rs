@@ -1,9 +1,9 @@
 pub use crate::lexer;
 use crate::node::{
-    BinOp, FormalParameter, FormalParameters, MethodDefinitionKind, Node, NodeBase,
-    PropertyDefinition, UnaryOp, VarKind,
+    BinOp, CaseClause, FormalParameter, FormalParameters, MethodDefinitionKind, Node, NodeBase,
+    PatternElement, PropertyDefinition, UnaryOp, VarKind,
 };
-use crate::token::{get_string_for_symbol, Keyword, Kind, Symbol, Token};
+use crate::token::{get_string_for_symbol, Keyword, Kind, Symbol, TemplatePart, Token};
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::Path;
@@ -112,10 +112,15 @@ impl Parser {
     /// * `pos` - A char position in the source script.
     /// * `msg` - An error message text.
     pub fn show_error_at(&self, pos: usize, msg: impl Into<String>) {
+        self.show_error_of_kind_at("SyntaxError", pos, msg)
+    }
+
+    /// Display an error message of `kind` (e.g. `ReferenceError`) with the source around `pos`.
+    pub fn show_error_of_kind_at(&self, kind: &str, pos: usize, msg: impl Into<String>) {
         let (source_at_err_point, _pos, line) = self.lexer.get_code_around_err_point(pos);
         eprintln!(
             "{}: line {}: {}\n{}",
-            Colour::Red.bold().paint("SyntaxError"),
+            Colour::Red.bold().paint(kind),
             line,
             msg.into(): String,
             source_at_err_point,
@@ -279,7 +284,9 @@ impl Parser {
             Kind::Keyword(Keyword::If) => self.read_if_statement(),
             Kind::Keyword(Keyword::Var) => self.read_variable_statement(),
             Kind::Keyword(Keyword::While) => self.read_while_statement(),
+            Kind::Keyword(Keyword::Do) => self.read_do_while_statement(),
             Kind::Keyword(Keyword::For) => self.read_for_statement(),
+            Kind::Keyword(Keyword::Switch) => self.read_switch_statement(),
             Kind::Keyword(Keyword::Return) => self.read_return_statement(),
             Kind::Keyword(Keyword::Break) => self.read_break_statement(),
             Kind::Keyword(Keyword::Continue) => self.read_continue_statement(),
@@ -456,11 +463,51 @@ impl Parser {
         ))
     }
 
+    /// https://tc39.github.io/ecma262/#sec-do-while-statement
+    fn read_do_while_statement(&mut self) -> Result<Node, Error> {
+        let pos = self.lexer.get_prev_pos();
+
+        let body = self.read_statement()?;
+
+        expect!(self, Kind::Keyword(Keyword::While), "expect 'while'");
+        expect!(self, Kind::Symbol(Symbol::OpeningParen), "expect '('");
+
+        let cond = self.read_expression()?;
+
+        expect!(self, Kind::Symbol(Symbol::ClosingParen), "expect ')'");
+
+        // The semicolon after `)` is inserted automatically even if no line terminator follows.
+        // https://tc39.github.io/ecma262/#sec-rules-of-automatic-semicolon-insertion
+        Ok(Node::new(
+            NodeBase::DoWhile(Box::new(body), Box::new(cond)),
+            pos,
+        ))
+    }
+
     fn read_for_statement(&mut self) -> Result<Node, Error> {
         let pos = self.lexer.get_prev_pos();
 
         expect!(self, Kind::Symbol(Symbol::OpeningParen), "expect '('");
 
+        let save_pos = self.lexer.token_pos;
+        if let Some((binding, is_of)) = self.read_for_in_of_head()? {
+            let target = if is_of {
+                self.read_assignment_expression()?
+            } else {
+                self.read_expression()?
+            };
+            expect!(self, Kind::Symbol(Symbol::ClosingParen), "expect ')'");
+            let body = self.read_statement()?;
+            let (binding, target, body) = (Box::new(binding), Box::new(target), Box::new(body));
+            let node = if is_of {
+                NodeBase::ForOf(binding, target, body)
+            } else {
+                NodeBase::ForIn(binding, target, body)
+            };
+            return Ok(Node::new(NodeBase::Block(vec![Node::new(node, pos)]), pos));
+        }
+        self.lexer.token_pos = save_pos;
+
         let init = match self.lexer.peek(0)?.kind {
             Kind::Keyword(Keyword::Var) => {
                 assert_eq!(self.lexer.next()?.kind, Kind::Keyword(Keyword::Var));
@@ -504,6 +551,296 @@ impl Parser {
 
         Ok(Node::new(NodeBase::Block(vec![for_node]), pos))
     }
+
+    /// Reads the binding of a for-in or for-of statement and the following `in` or `of`,
+    /// returning the binding and whether the statement is for-of.
+    /// Returns `None` if the statement turns out to be a C-style `for`.
+    fn read_for_in_of_head(&mut self) -> Result<Option<(Node, bool)>, Error> {
+        let tok = self.lexer.next_skip_lineterminator()?;
+        let var_kind = match tok.kind {
+            Kind::Keyword(Keyword::Var) => Some(VarKind::Var),
+            Kind::Keyword(Keyword::Let) => Some(VarKind::Let),
+            Kind::Keyword(Keyword::Const) => Some(VarKind::Const),
+            _ => None,
+        };
+        let binding = match var_kind {
+            Some(var_kind) => match self.read_binding_target() {
+                Ok(target) => Node::new(NodeBase::ForBinding(Box::new(target), var_kind), tok.pos),
+                Err(_) => return Ok(None),
+            },
+            None => {
+                self.lexer.unget();
+                match self.read_left_hand_side_expression() {
+                    Ok(lhs) => lhs,
+                    Err(_) => return Ok(None),
+                }
+            }
+        };
+        let is_of = match self.lexer.next_skip_lineterminator() {
+            Ok(Token {
+                kind: Kind::Keyword(Keyword::In),
+                ..
+            }) => false,
+            Ok(Token {
+                kind: Kind::Identifier(ref name),
+                ..
+            }) if name == "of" => true,
+            _ => return Ok(None),
+        };
+        match binding.base {
+            NodeBase::ForBinding(_, _) => Ok(Some((binding, is_of))),
+            _ => Ok(Some((to_assignment_target(binding)?, is_of))),
+        }
+    }
+
+    /// https://tc39.github.io/ecma262/#prod-ForBinding
+    fn read_binding_target(&mut self) -> Result<Node, Error> {
+        let tok = self.lexer.next_skip_lineterminator()?;
+        match tok.kind {
+            Kind::Identifier(name) => Ok(Node::new(NodeBase::Identifier(name), tok.pos)),
+            Kind::Symbol(Symbol::OpeningBoxBracket) => self.read_array_binding_pattern(tok.pos),
+            Kind::Symbol(Symbol::OpeningBrace) => self.read_object_binding_pattern(tok.pos),
+            _ => Err(Error::UnexpectedToken(
+                tok.pos,
+                "expected identifier or destructuring pattern.".to_string(),
+            )),
+        }
+    }
+
+    /// https://tc39.github.io/ecma262/#prod-BindingElement
+    fn read_binding_element(&mut self) -> Result<PatternElement, Error> {
+        let target = self.read_binding_target()?;
+        let init = if self
+            .lexer
+            .next_if_skip_lineterminator(Kind::Symbol(Symbol::Assign))?
+        {
+            Some(self.read_assignment_expression()?)
+        } else {
+            None
+        };
+        Ok(PatternElement::new(target, init))
+    }
+
+    /// https://tc39.github.io/ecma262/#prod-ArrayBindingPattern
+    fn read_array_binding_pattern(&mut self, pos: usize) -> Result<Node, Error> {
+        let mut elements = vec![];
+
+        loop {
+            if self
+                .lexer
+                .next_if_skip_lineterminator(Kind::Symbol(Symbol::ClosingBoxBracket))?
+            {
+                break;
+            }
+            if self
+                .lexer
+                .next_if_skip_lineterminator(Kind::Symbol(Symbol::Comma))?
+            {
+                elements.push(None);
+                continue;
+            }
+            elements.push(Some(self.read_binding_element()?));
+            let tok = self.lexer.next_skip_lineterminator()?;
+            match tok.kind {
+                Kind::Symbol(Symbol::ClosingBoxBracket) => break,
+                Kind::Symbol(Symbol::Comma) => {}
+                _ => return Err(Error::Expect(tok.pos, "expect ',' or ']'.".to_string())),
+            }
+        }
+
+        Ok(Node::new(NodeBase::ArrayPattern(elements), pos))
+    }
+
+    /// https://tc39.github.io/ecma262/#prod-ObjectBindingPattern
+    fn read_object_binding_pattern(&mut self, pos: usize) -> Result<Node, Error> {
+        let mut properties = vec![];
+
+        loop {
+            if self
+                .lexer
+                .next_if_skip_lineterminator(Kind::Symbol(Symbol::ClosingBrace))?
+            {
+                break;
+            }
+            let tok = self.lexer.next_skip_lineterminator()?;
+            let element = if self
+                .lexer
+                .next_if_skip_lineterminator(Kind::Symbol(Symbol::Colon))?
+            {
+                self.read_binding_element()?
+            } else if let Kind::Identifier(ref name) = tok.kind {
+                // `{ name = init }` binds the property to the variable of the same name.
+                let target = Node::new(NodeBase::Identifier(name.clone()), tok.pos);
+                let init = if self
+                    .lexer
+                    .next_if_skip_lineterminator(Kind::Symbol(Symbol::Assign))?
+                {
+                    Some(self.read_assignment_expression()?)
+                } else {
+                    None
+                };
+                PatternElement::new(target, init)
+            } else {
+                return Err(Error::Expect(tok.pos, "expect ':'.".to_string()));
+            };
+            let name = match tok.kind {
+                Kind::Identifier(name) | Kind::String(name) => name,
+                Kind::Number(n) => format!("{}", n),
+                _ => {
+                    return Err(Error::UnexpectedToken(
+                        tok.pos,
+                        "expected property name.".to_string(),
+                    ));
+                }
+            };
+            properties.push((name, element));
+            let tok = self.lexer.next_skip_lineterminator()?;
+            match tok.kind {
+                Kind::Symbol(Symbol::ClosingBrace) => break,
+                Kind::Symbol(Symbol::Comma) => {}
+                _ => return Err(Error::Expect(tok.pos, "expect ',' or '}'.".to_string())),
+            }
+        }
+
+        Ok(Node::new(NodeBase::ObjectPattern(properties), pos))
+    }
+}
+
+/// Reinterprets the expression before `in` or `of` as an assignment target, turning array and
+/// object literals into destructuring patterns.
+/// https://tc39.github.io/ecma262/#sec-destructuring-assignment
+fn to_assignment_target(node: Node) -> Result<Node, Error> {
+    let base = match node.base {
+        NodeBase::Identifier(_) | NodeBase::Member(_, _) | NodeBase::Index(_, _) => node.base,
+        NodeBase::Array(elements) => {
+            let mut pattern = vec![];
+            for element in elements {
+                pattern.push(match element.base {
+                    NodeBase::Nope => None,
+                    _ => Some(to_pattern_element(element)?),
+                });
+            }
+            NodeBase::ArrayPattern(pattern)
+        }
+        NodeBase::Object(properties) => {
+            let mut pattern = vec![];
+            for property in properties {
+                pattern.push(match property {
+                    PropertyDefinition::IdentifierReference(name) => {
+                        let target = Node::new(NodeBase::Identifier(name.clone()), node.pos);
+                        (name, PatternElement::new(target, None))
+                    }
+                    PropertyDefinition::Property(name, value) => (name, to_pattern_element(value)?),
+                    PropertyDefinition::MethodDefinition(_, _, _) => {
+                        return Err(Error::General(
+                            node.pos,
+                            "invalid destructuring assignment target".to_string(),
+                        ));
+                    }
+                });
+            }
+            NodeBase::ObjectPattern(pattern)
+        }
+        _ => {
+            return Err(Error::General(
+                node.pos,
+                "invalid left-hand side in for-in/of loop".to_string(),
+            ));
+        }
+    };
+    Ok(Node::new(base, node.pos))
+}
+
+/// An element of an array or object literal reinterpreted as a destructuring pattern,
+/// where `target = init` gives a default value.
+fn to_pattern_element(node: Node) -> Result<PatternElement, Error> {
+    match node.base {
+        NodeBase::Assign(target, init) => Ok(PatternElement::new(
+            to_assignment_target(*target)?,
+            Some(*init),
+        )),
+        _ => Ok(PatternElement::new(to_assignment_target(node)?, None)),
+    }
+}
+
+impl Parser {
+    /// https://tc39.github.io/ecma262/#prod-SwitchStatement
+    fn read_switch_statement(&mut self) -> Result<Node, Error> {
+        let pos = self.lexer.get_prev_pos();
+
+        expect!(self, Kind::Symbol(Symbol::OpeningParen), "expect '('");
+        let discriminant = self.read_expression()?;
+        expect!(self, Kind::Symbol(Symbol::ClosingParen), "expect ')'");
+        expect!(self, Kind::Symbol(Symbol::OpeningBrace), "expect '{'");
+
+        let mut clauses = vec![];
+        let mut has_default = false;
+
+        loop {
+            let tok = match self.lexer.next_skip_lineterminator() {
+                Ok(tok) => tok,
+                Err(Error::NormalEOF) => {
+                    return Err(Error::UnexpectedEOF("expected '}'.".to_string()))
+                }
+                Err(e) => return Err(e),
+            };
+            let test = match tok.kind {
+                Kind::Symbol(Symbol::ClosingBrace) => break,
+                Kind::Keyword(Keyword::Case) => Some(self.read_expression()?),
+                Kind::Keyword(Keyword::Default) if !has_default => {
+                    has_default = true;
+                    None
+                }
+                Kind::Keyword(Keyword::Default) => {
+                    return Err(Error::General(
+                        tok.pos,
+                        "more than one default clause in switch statement".to_string(),
+                    ));
+                }
+                _ => {
+                    return Err(Error::UnexpectedToken(
+                        tok.pos,
+                        "expected 'case', 'default' or '}'.".to_string(),
+                    ));
+                }
+            };
+            expect!(self, Kind::Symbol(Symbol::Colon), "expect ':'");
+            clauses.push(CaseClause::new(test, self.read_case_clause_body()?));
+        }
+
+        Ok(Node::new(
+            NodeBase::Switch(Box::new(discriminant), clauses),
+            pos,
+        ))
+    }
+
+    /// Reads the statements of a case clause up to the next `case`, `default` or `}`.
+    fn read_case_clause_body(&mut self) -> Result<Vec<Node>, Error> {
+        let mut body = vec![];
+
+        loop {
+            match self.lexer.peek_skip_lineterminator().map(|tok| tok.kind) {
+                Ok(Kind::Keyword(Keyword::Case))
+                | Ok(Kind::Keyword(Keyword::Default))
+                | Ok(Kind::Symbol(Symbol::ClosingBrace)) => break,
+                Ok(_) => {}
+                Err(Error::NormalEOF) => {
+                    return Err(Error::UnexpectedEOF("expected '}'.".to_string()));
+                }
+                Err(e) => return Err(e),
+            }
+
+            body.push(self.read_statement_list_item()?);
+
+            while self
+                .lexer
+                .next_if_skip_lineterminator(Kind::Symbol(Symbol::Semicolon))
+                .unwrap_or(false)
+            {}
+        }
+
+        Ok(body)
+    }
 }
 
 impl Parser {
@@ -715,11 +1052,26 @@ impl Parser {
     );
 
     /// https://tc39.github.io/ecma262/#prod-RelationalExpression
-    expression!(
-        read_relational_expression,
-        read_shift_expression,
-        [Symbol::Lt, Symbol::Gt, Symbol::Le, Symbol::Ge]
-    );
+    fn read_relational_expression(&mut self) -> Result<Node, Error> {
+        let mut lhs = self.read_shift_expression()?;
+        while let Ok(tok) = self.lexer.peek_skip_lineterminator() {
+            let op = match tok.kind {
+                Kind::Symbol(Symbol::Lt) => BinOp::Lt,
+                Kind::Symbol(Symbol::Gt) => BinOp::Gt,
+                Kind::Symbol(Symbol::Le) => BinOp::Le,
+                Kind::Symbol(Symbol::Ge) => BinOp::Ge,
+                Kind::Keyword(Keyword::Instanceof) => BinOp::InstanceOf,
+                _ => break,
+            };
+            self.lexer.next_skip_lineterminator().unwrap();
+            let pos = self.lexer.get_current_pos();
+            lhs = Node::new(
+                NodeBase::BinaryOp(Box::new(lhs), Box::new(self.read_shift_expression()?), op),
+                pos,
+            );
+        }
+        Ok(lhs)
+    }
 
     /// https://tc39.github.io/ecma262/#prod-ShiftExpression
     expression!(
@@ -942,6 +1294,10 @@ impl Parser {
                     }
                     lhs = Node::new(NodeBase::Index(Box::new(lhs), Box::new(idx)), pos);
                 }
+                Kind::Template(TemplatePart::NoSubstitution, _, _)
+                | Kind::Template(TemplatePart::Head, _, _) => {
+                    lhs = self.read_tagged_template(lhs, tok)?
+                }
                 _ => {
                     self.lexer.unget();
                     break;
@@ -994,6 +1350,10 @@ impl Parser {
                     }
                     lhs = Node::new(NodeBase::Index(Box::new(lhs), Box::new(idx)), pos);
                 }
+                Kind::Template(TemplatePart::NoSubstitution, _, _)
+                | Kind::Template(TemplatePart::Head, _, _) => {
+                    lhs = self.read_tagged_template(lhs, tok)?
+                }
                 _ => {
                     self.lexer.unget();
                     break;
@@ -1049,7 +1409,11 @@ impl Parser {
 
         match tok.kind {
             Kind::Keyword(Keyword::This) => Ok(Node::new(NodeBase::This, tok.pos)),
-            // Kind::Keyword(Keyword::Arguments) => Ok(Node::new(NodeBase::Arguments, tok.pos)),
+            // `arguments` is resolved like a variable, so that arrow functions capture it.
+            Kind::Keyword(Keyword::Arguments) => Ok(Node::new(
+                NodeBase::Identifier("arguments".to_string()),
+                tok.pos,
+            )),
             Kind::Keyword(Keyword::Function) => self.read_function_expression(),
             Kind::Symbol(Symbol::OpeningParen) => {
                 let expr = self.read_expression();
@@ -1071,6 +1435,24 @@ impl Parser {
             Kind::Identifier(ident) => Ok(Node::new(NodeBase::Identifier(ident), tok.pos)),
             Kind::String(s) => Ok(Node::new(NodeBase::String(s), tok.pos)),
             Kind::Number(num) => Ok(Node::new(NodeBase::Number(num), tok.pos)),
+            Kind::RegExp(pattern, flags) => {
+                Ok(Node::new(NodeBase::RegExp(pattern, flags), tok.pos))
+            }
+            // The lexer has taken the start of a regular expression literal for a division.
+            Kind::Symbol(Symbol::Div) | Kind::Symbol(Symbol::AssignDiv) => {
+                match self.lexer.reread_as_regexp()?.kind {
+                    Kind::RegExp(pattern, flags) => {
+                        Ok(Node::new(NodeBase::RegExp(pattern, flags), tok.pos))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Kind::Template(TemplatePart::NoSubstitution, _, _)
+            | Kind::Template(TemplatePart::Head, _, _) => {
+                let pos = tok.pos;
+                let (strings, _, exprs) = self.read_template_literal(tok)?;
+                Ok(Node::new(NodeBase::TemplateLiteral(strings, exprs), pos))
+            }
             _ => Err(Error::UnexpectedToken(
                 tok.pos,
                 format!("unexpected token."),
@@ -1078,6 +1460,47 @@ impl Parser {
         }
     }
 
+    /// https://tc39.github.io/ecma262/#prod-TemplateLiteral
+    /// Returns the cooked strings, the raw strings, and the substitutions between them.
+    fn read_template_literal(
+        &mut self,
+        first: Token,
+    ) -> Result<(Vec<String>, Vec<String>, Vec<Node>), Error> {
+        let mut strings = vec![];
+        let mut raw_strings = vec![];
+        let mut exprs = vec![];
+        let mut tok = first;
+        loop {
+            match tok.kind {
+                Kind::Template(part, cooked, raw) => {
+                    strings.push(cooked);
+                    raw_strings.push(raw);
+                    if part == TemplatePart::NoSubstitution || part == TemplatePart::Tail {
+                        break;
+                    }
+                }
+                _ => return Err(Error::Expect(tok.pos, "expect '}'".to_string())),
+            }
+            exprs.push(self.read_expression()?);
+            tok = self.lexer.next_skip_lineterminator()?;
+        }
+        Ok((strings, raw_strings, exprs))
+    }
+
+    /// https://tc39.github.io/ecma262/#sec-tagged-templates
+    /// The tag is called with the strings of the template followed by the substitutions.
+    fn read_tagged_template(&mut self, tag: Node, first: Token) -> Result<Node, Error> {
+        let pos = tag.pos;
+        let strings_pos = first.pos;
+        let (strings, raw_strings, exprs) = self.read_template_literal(first)?;
+        let mut args = vec![Node::new(
+            NodeBase::TemplateStrings(strings, raw_strings),
+            strings_pos,
+        )];
+        args.extend(exprs);
+        Ok(Node::new(NodeBase::Call(Box::new(tag), args), pos))
+    }
+
     /// https://www.ecma-international.org/ecma-262/6.0/#sec-arrow-function-definitions
     fn read_arrow_function(&mut self, is_parenthesized_param: bool) -> Result<Node, Error> {
         let params;
@@ -1334,8 +1757,13 @@ impl Parser {
         let finally = if is_finally {
             skip_symbol_or_error!(self.lexer, Symbol::OpeningBrace);
             self.read_block_statement()?
-        } else {
+        } else if is_catch {
             Node::new(NodeBase::Nope, pos_finally)
+        } else {
+            return Err(Error::General(
+                pos_finally,
+                "Missing catch or finally after try".to_string(),
+            ));
         };
 
         Ok(Node::new(
@@ -1517,8 +1945,15 @@ impl Parser {
                 "expect identifier (unsupported feature)".to_string(),
             ));
         };
-        // TODO: Implement initializer.
-        Ok(FormalParameter::new(name, None, false))
+        let init = if self
+            .lexer
+            .next_if_skip_lineterminator(Kind::Symbol(Symbol::Assign))?
+        {
+            Some(self.read_assignment_expression()?)
+        } else {
+            None
+        };
+        Ok(FormalParameter::new(name, init, false))
     }
 
     fn read_function_rest_parameter(&mut self) -> Result<FormalParameter, Error> {
@@ -1774,6 +2209,7 @@ fn simple_expr_rel() {
         ("1 + 2 > 3", BinOp::Gt, 8),
         ("1 + 2 <= 3", BinOp::Le, 9),
         ("1 + 2 >= 3", BinOp::Ge, 9),
+        ("1 + 2 instanceof 3", BinOp::InstanceOf, 17),
     ]
     .iter()
     {
@@ -2348,6 +2784,31 @@ fn while_() {
     );
 }
 
+#[test]
+fn do_while() {
+    let mut parser = Parser::new("test", "do {} while (true) x".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![
+                Node::new(
+                    NodeBase::DoWhile(
+                        Box::new(Node::new(NodeBase::Block(vec![]), 3)),
+                        Box::new(Node::new(NodeBase::Boolean(true), 13)),
+                    ),
+                    0,
+                ),
+                Node::new(NodeBase::Identifier("x".to_string()), 19),
+            ]),
+            0
+        )
+    );
+    for input in ["do {}", "do {} while true", "do {} until (true)"].iter() {
+        let mut parser = Parser::new("test", input.to_string());
+        parser.parse_all().expect_err("should be error");
+    }
+}
+
 #[test]
 fn for1() {
     let mut parser = Parser::new("test", "for (;;) { }".to_string());
@@ -2386,6 +2847,138 @@ fn for1() {
     }
 }
 
+#[test]
+fn for_in_of() {
+    let mut parser = Parser::new("test", "for (let k in o) {}".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::Block(vec![Node::new(
+                    NodeBase::ForIn(
+                        Box::new(Node::new(
+                            NodeBase::ForBinding(
+                                Box::new(Node::new(NodeBase::Identifier("k".to_string()), 9)),
+                                VarKind::Let,
+                            ),
+                            5,
+                        )),
+                        Box::new(Node::new(NodeBase::Identifier("o".to_string()), 14)),
+                        Box::new(Node::new(NodeBase::Block(vec![]), 17)),
+                    ),
+                    0,
+                )]),
+                0,
+            )]),
+            0
+        )
+    );
+
+    let mut parser = Parser::new("test", "for (const {x, y: [z]} of a) {}".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::Block(vec![Node::new(
+                    NodeBase::ForOf(
+                        Box::new(Node::new(
+                            NodeBase::ForBinding(
+                                Box::new(Node::new(
+                                    NodeBase::ObjectPattern(vec![
+                                        (
+                                            "x".to_string(),
+                                            PatternElement::new(
+                                                Node::new(
+                                                    NodeBase::Identifier("x".to_string()),
+                                                    12
+                                                ),
+                                                None,
+                                            ),
+                                        ),
+                                        (
+                                            "y".to_string(),
+                                            PatternElement::new(
+                                                Node::new(
+                                                    NodeBase::ArrayPattern(vec![Some(
+                                                        PatternElement::new(
+                                                            Node::new(
+                                                                NodeBase::Identifier(
+                                                                    "z".to_string()
+                                                                ),
+                                                                19,
+                                                            ),
+                                                            None,
+                                                        ),
+                                                    )]),
+                                                    18,
+                                                ),
+                                                None,
+                                            ),
+                                        ),
+                                    ]),
+                                    11,
+                                )),
+                                VarKind::Const,
+                            ),
+                            5,
+                        )),
+                        Box::new(Node::new(NodeBase::Identifier("a".to_string()), 26)),
+                        Box::new(Node::new(NodeBase::Block(vec![]), 29)),
+                    ),
+                    0,
+                )]),
+                0,
+            )]),
+            0
+        )
+    );
+
+    // An array literal before `of` is reinterpreted as a destructuring assignment target.
+    let mut parser = Parser::new("test", "for ([a, b = 1] of xs) {}".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::Block(vec![Node::new(
+                    NodeBase::ForOf(
+                        Box::new(Node::new(
+                            NodeBase::ArrayPattern(vec![
+                                Some(PatternElement::new(
+                                    Node::new(NodeBase::Identifier("a".to_string()), 6),
+                                    None,
+                                )),
+                                Some(PatternElement::new(
+                                    Node::new(NodeBase::Identifier("b".to_string()), 9),
+                                    Some(Node::new(NodeBase::Number(1.0), 13)),
+                                )),
+                            ]),
+                            6,
+                        )),
+                        Box::new(Node::new(NodeBase::Identifier("xs".to_string()), 19)),
+                        Box::new(Node::new(NodeBase::Block(vec![]), 23)),
+                    ),
+                    0,
+                )]),
+                0,
+            )]),
+            0
+        )
+    );
+
+    for input in [
+        "for (f() of xs) {}",
+        "for (x of) {}",
+        "for (let x of a, b) {}",
+        "for (let [a] = 1 in o) {}",
+        "for (const {1} of a) {}",
+    ]
+    .iter()
+    {
+        let mut parser = Parser::new("test", input.to_string());
+        parser.parse_all().expect_err("should be error");
+    }
+}
+
 #[test]
 fn function_decl() {
     for (input, node) in [
@@ -2447,6 +3040,24 @@ fn function_decl() {
                 0,
             ),
         ),
+        (
+            "function f(a, b = 1) {}",
+            Node::new(
+                NodeBase::FunctionDecl(
+                    "f".to_string(),
+                    vec![
+                        FormalParameter::new("a".to_string(), None, false),
+                        FormalParameter::new(
+                            "b".to_string(),
+                            Some(Node::new(NodeBase::Number(1.0), 18)),
+                            false,
+                        ),
+                    ],
+                    Box::new(Node::new(NodeBase::StatementList(vec![]), 21)),
+                ),
+                0,
+            ),
+        ),
     ]
     .iter()
     {
@@ -2665,6 +3276,42 @@ fn throw() {
         parser.parse_all().expect_err("should be error");
     }
 }
+#[test]
+fn switch() {
+    use crate::node::CaseClause;
+
+    let mut parser = Parser::new("test", "switch (x) { case 1: break; default: }".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::Switch(
+                    Box::new(Node::new(NodeBase::Identifier("x".to_string()), 8)),
+                    vec![
+                        CaseClause::new(
+                            Some(Node::new(NodeBase::Number(1.0), 18)),
+                            vec![Node::new(NodeBase::Break(None), 21)]
+                        ),
+                        CaseClause::new(None, vec![]),
+                    ]
+                ),
+                0
+            )]),
+            0
+        )
+    );
+    for input in [
+        "switch (x) { default: default: }",
+        "switch (x) { 1: }",
+        "switch (x) { case 1: ",
+    ]
+    .iter()
+    {
+        let mut parser = Parser::new("test", input.to_string());
+        parser.parse_all().expect_err("should be error");
+    }
+}
+
 #[test]
 fn try_catch1() {
     let mut parser = Parser::new("test", "try {} catch(e){} finally{}".to_string());
@@ -2683,7 +3330,15 @@ fn try_catch1() {
             0
         )
     );
-    for input in ["try {} catch", "try {} catch {}", "try {} catch(7)"].iter() {
+    for input in [
+        "try {} catch",
+        "try {} catch {}",
+        "try {} catch(7)",
+        "try {}",
+        "try {} x",
+    ]
+    .iter()
+    {
         let mut parser = Parser::new("test", input.to_string());
         parser.parse_all().expect_err("should be error");
     }
@@ -2728,3 +3383,72 @@ fn try_catch3() {
         )
     );
 }
+
+#[test]
+fn template_literal() {
+    let mut parser = Parser::new("test", "`a${x}b`".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::TemplateLiteral(
+                    vec!["a".to_string(), "b".to_string()],
+                    vec![Node::new(NodeBase::Identifier("x".to_string()), 4)]
+                ),
+                0
+            )]),
+            0
+        )
+    );
+}
+
+#[test]
+fn tagged_template() {
+    let mut parser = Parser::new("test", "f`x${1}\\n`".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::Call(
+                    Box::new(Node::new(NodeBase::Identifier("f".to_string()), 0)),
+                    vec![
+                        Node::new(
+                            NodeBase::TemplateStrings(
+                                vec!["x".to_string(), "\n".to_string()],
+                                vec!["x".to_string(), "\\n".to_string()]
+                            ),
+                            1
+                        ),
+                        Node::new(NodeBase::Number(1.0), 5)
+                    ]
+                ),
+                0
+            )]),
+            0
+        )
+    );
+}
+
+#[test]
+fn regexp() {
+    use crate::node::BinOp;
+
+    let mut parser = Parser::new("test", "a / /b|c/g".to_string());
+    assert_eq!(
+        parser.parse_all().unwrap(),
+        Node::new(
+            NodeBase::StatementList(vec![Node::new(
+                NodeBase::BinaryOp(
+                    Box::new(Node::new(NodeBase::Identifier("a".to_string()), 0)),
+                    Box::new(Node::new(
+                        NodeBase::RegExp("b|c".to_string(), "g".to_string()),
+                        4
+                    )),
+                    BinOp::Div
+                ),
+                4
+            )]),
+            0
+        )
+    );
+}
@@ -0,0 +1,46 @@
+//! Exceptions thrown by scripts.
+//!
+//! cilk has no unwinding, so a thrown value is stored as the pending exception of the thread,
+//! and compiled code checks whether an exception is pending after every call that may throw.
+//! If so, it branches to the innermost handler (a `catch` or `finally` clause), or returns
+//! from the function so that its caller handles the exception in turn.
+
+use super::value::BoxedValue;
+use std::cell::Cell;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Exception {
+  pub value: BoxedValue,
+  /// The line of the `throw` statement, or of the call whose runtime helper threw the exception.
+  /// The runtime does not know lines, so it leaves 0 and compiled code fills in the line.
+  pub line: usize,
+}
+
+thread_local! {
+  static PENDING: Cell<Option<Exception>> = Cell::new(None);
+}
+
+pub fn throw(value: BoxedValue, line: usize) {
+  PENDING.with(|pending| pending.set(Some(Exception { value, line })));
+}
+
+/// The exception being propagated, if any. The garbage collector treats its value as a root.
+pub fn pending() -> Option<Exception> {
+  PENDING.with(|pending| pending.get())
+}
+
+/// Clears the pending exception, returning it, when a handler catches it.
+pub fn take() -> Option<Exception> {
+  PENDING.with(|pending| pending.take())
+}
+
+/// Gives the pending exception the line `line` if it was thrown by the runtime.
+pub fn locate(line: usize) {
+  PENDING.with(|pending| {
+    if let Some(e) = pending.get() {
+      if e.line == 0 {
+        pending.set(Some(Exception { line, ..e }));
+      }
+    }
+  })
+}
@@ -0,0 +1,250 @@
+//! A mark-and-sweep garbage collector for the values compiled code allocates.
+//!
+//! Instead of scanning native frames, roots are kept on a shadow stack maintained by runtime
+//! helpers, so that collection works the same whether the code runs on the cilk interpreter
+//! or on the JIT:
+//!
+//! - Every compiled function calls `frame_enter` on entry, which returns the current height of the
+//!   shadow stack, and `frame_leave` before returning, which pops everything above that height
+//!   and pushes the return value for the caller.
+//! - Every heap value a runtime helper allocates or loads is pushed onto the shadow stack,
+//!   so temporaries stay alive until their frame is left.
+//! - At loop headers, where no temporaries are live, compiled code calls `frame_reset`
+//!   and pushes the values of its variables again, so that loops do not grow the shadow stack.
+//!
+//! Values that live as long as the thread, such as the global object, are rooted permanently,
+//! string literals are pinned while the module embedding them is alive,
+//! and the pending exception (see `exception`) is a root while it propagates.
+//!
+//! Each thread has its own heap. Collections run when enough values have been allocated,
+//! or when the script calls `gc()`.
+
+use super::exception;
+use super::object::{
+  Closure, Environment, JsArray, JsIterator, JsObject, JsRegExp, JsString, ObjectHeader,
+  ObjectKind, Property,
+};
+use super::value::BoxedValue;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+/// The number of allocations after which the first collection runs.
+const MIN_THRESHOLD: usize = 10000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GcStats {
+  pub collections: usize,
+  pub freed: usize,
+  pub live: usize,
+}
+
+struct Heap {
+  objects: Vec<*mut ObjectHeader>,
+  roots: Vec<BoxedValue>,
+  permanent_roots: Vec<BoxedValue>,
+  pinned: HashSet<BoxedValue>,
+  allocated: usize,
+  threshold: usize,
+  stats: GcStats,
+}
+
+thread_local! {
+  static HEAP: RefCell<Heap> = RefCell::new(Heap {
+    objects: vec![],
+    roots: vec![],
+    permanent_roots: vec![],
+    pinned: HashSet::new(),
+    allocated: 0,
+    threshold: MIN_THRESHOLD,
+    stats: GcStats::default(),
+  });
+}
+
+/// Moves `value` to the heap and pushes it onto the shadow stack.
+/// `T` must begin with an `ObjectHeader`.
+pub fn alloc<T>(value: T) -> *mut T {
+  let p = Box::into_raw(Box::new(value));
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.objects.push(p as *mut ObjectHeader);
+    heap.roots.push(BoxedValue::from_pointer(p));
+    heap.allocated += 1;
+    if heap.allocated >= heap.threshold {
+      heap.collect();
+    }
+  });
+  p
+}
+
+/// Moves `value` to the heap and keeps it alive until it is unpinned,
+/// for values compiled code refers to by address. `T` must begin with an `ObjectHeader`.
+pub fn alloc_pinned<T>(value: T) -> *mut T {
+  let p = Box::into_raw(Box::new(value));
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    heap.objects.push(p as *mut ObjectHeader);
+    heap.pinned.insert(BoxedValue::from_pointer(p));
+  });
+  p
+}
+
+/// Lets the collector free `values`, allocated by `alloc_pinned`, once they are unreachable.
+pub fn unpin(values: &[BoxedValue]) {
+  HEAP.with(|heap| {
+    let mut heap = heap.borrow_mut();
+    for v in values {
+      heap.pinned.remove(v);
+    }
+  });
+}
+
+/// Pushes `v` onto the shadow stack if it is a heap value, and returns it.
+pub fn root(v: BoxedValue) -> BoxedValue {
+  if v.is_pointer() {
+    HEAP.with(|heap| heap.borrow_mut().roots.push(v));
+  }
+  v
+}
+
+/// Keeps `v` alive until the thread exits.
+pub fn root_permanently(v: BoxedValue) {
+  HEAP.with(|heap| heap.borrow_mut().permanent_roots.push(v));
+}
+
+pub fn frame_enter() -> usize {
+  HEAP.with(|heap| heap.borrow().roots.len())
+}
+
+/// Pops the roots of a frame, keeping its return value `ret` alive for the caller.
+pub fn frame_leave(height: usize, ret: BoxedValue) {
+  frame_reset(height);
+  root(ret);
+}
+
+/// Pops the roots of a frame pushed after it was entered.
+pub fn frame_reset(height: usize) {
+  HEAP.with(|heap| heap.borrow_mut().roots.truncate(height));
+}
+
+pub fn collect() {
+  HEAP.with(|heap| heap.borrow_mut().collect());
+}
+
+pub fn stats() -> GcStats {
+  HEAP.with(|heap| heap.borrow().stats)
+}
+
+pub fn print_stats() {
+  let stats = stats();
+  eprintln!(
+    "gc: {} collections, {} values freed, {} values live",
+    stats.collections, stats.freed, stats.live
+  );
+}
+
+impl Heap {
+  fn collect(&mut self) {
+    let mut worklist: Vec<*mut ObjectHeader> = vec![];
+    for root in self.roots.iter().chain(&self.permanent_roots).chain(&self.pinned) {
+      mark_value(*root, &mut worklist);
+    }
+    if let Some(e) = exception::pending() {
+      mark_value(e.value, &mut worklist);
+    }
+    while let Some(p) = worklist.pop() {
+      unsafe { trace(p, &mut worklist) };
+    }
+
+    let live_before = self.objects.len();
+    self.objects.retain(|p| unsafe {
+      if (**p).marked {
+        (**p).marked = false;
+        true
+      } else {
+        free(*p);
+        false
+      }
+    });
+
+    self.stats.collections += 1;
+    self.stats.freed += live_before - self.objects.len();
+    self.stats.live = self.objects.len();
+    self.allocated = 0;
+    self.threshold = MIN_THRESHOLD.max(self.objects.len());
+  }
+}
+
+fn mark_value(v: BoxedValue, worklist: &mut Vec<*mut ObjectHeader>) {
+  if v.is_pointer() {
+    mark(v.as_pointer(), worklist);
+  }
+}
+
+fn mark(p: *mut ObjectHeader, worklist: &mut Vec<*mut ObjectHeader>) {
+  let header = unsafe { &mut *p };
+  if !header.marked {
+    header.marked = true;
+    worklist.push(p);
+  }
+}
+
+/// Marks the values `p` refers to.
+unsafe fn trace(p: *mut ObjectHeader, worklist: &mut Vec<*mut ObjectHeader>) {
+  match (*p).kind {
+    ObjectKind::String => {}
+    ObjectKind::Function => {
+      let closure = &*(p as *mut Closure);
+      if closure.env != 0 {
+        mark(closure.env as *mut ObjectHeader, worklist);
+      }
+      mark_value(closure.name, worklist);
+      trace_object(&closure.object, worklist);
+    }
+    ObjectKind::Array => {
+      for elem in &(*(p as *mut JsArray)).elements {
+        mark_value(*elem, worklist);
+      }
+      mark_value((*(p as *mut JsArray)).raw, worklist);
+    }
+    ObjectKind::Object => trace_object(&*(p as *mut JsObject), worklist),
+    ObjectKind::RegExp => trace_object(&(*(p as *mut JsRegExp)).object, worklist),
+    ObjectKind::Environment => {
+      let env = &*(p as *mut Environment);
+      if env.slots[0] != 0 {
+        mark(env.slots[0] as *mut ObjectHeader, worklist);
+      }
+      for slot in &env.slots[1..] {
+        mark_value(BoxedValue(*slot), worklist);
+      }
+      for (_, function) in &env.functions {
+        mark_value(*function, worklist);
+      }
+    }
+    ObjectKind::Iterator => mark_value((*(p as *mut JsIterator)).target, worklist),
+  }
+}
+
+fn trace_object(obj: &JsObject, worklist: &mut Vec<*mut ObjectHeader>) {
+  for property in obj.slots.iter() {
+    match property {
+      Property::Data(v) => mark_value(*v, worklist),
+      Property::Accessor { get, set } => {
+        mark_value(*get, worklist);
+        mark_value(*set, worklist);
+      }
+    }
+  }
+  mark_value(obj.proto, worklist);
+}
+
+unsafe fn free(p: *mut ObjectHeader) {
+  match (*p).kind {
+    ObjectKind::Function => drop(Box::from_raw(p as *mut Closure)),
+    ObjectKind::String => drop(Box::from_raw(p as *mut JsString)),
+    ObjectKind::Array => drop(Box::from_raw(p as *mut JsArray)),
+    ObjectKind::Object => drop(Box::from_raw(p as *mut JsObject)),
+    ObjectKind::RegExp => drop(Box::from_raw(p as *mut JsRegExp)),
+    ObjectKind::Environment => drop(Box::from_raw(p as *mut Environment)),
+    ObjectKind::Iterator => drop(Box::from_raw(p as *mut JsIterator)),
+  }
+}
@@ -0,0 +1,140 @@
+//! Inline caches for property accesses with a constant name, such as `obj.x`.
+//!
+//! Every such access in compiled code owns an `InlineCache`, which is allocated at compile time,
+//! embedded in the code as an immediate and freed with the module (see `Constants`).
+//! A cache remembers where the property is in objects of the shapes seen at the access.
+//! Compiled code checks it inline: it loads the shape of the object, compares it with the
+//! cached shapes, and on a match reads or writes the slot at the cached offset
+//! (see `FuncCompiler::build_inline_cache`). Only on a miss does it call
+//! `rapidus.rt.get_member` or `rapidus.rt.set_member`, which look the property up and fill the
+//! cache. A cache with one entry is monomorphic, and one with up to `MAX_ENTRIES` is polymorphic.
+//! Shapes beyond that are not cached.
+//!
+//! Only data properties are cached, since accessors are called by compiled code anyway.
+//! A data property may become an accessor without changing the shape of the object,
+//! so a hit also checks the kind of the property in the slot.
+//!
+//! Each cache counts its hits, which compiled code increments, and its misses, which the runtime
+//! does. The counts of the caches of a module are summed by `stats`.
+
+use super::object::{JsObject, ObjectKind, Property, Slots};
+use super::shape::Shape;
+use super::value;
+use std::mem;
+use std::ptr;
+
+pub const MAX_ENTRIES: usize = 4;
+
+thread_local! {
+  static LAYOUT: ObjectLayout = ObjectLayout::new();
+}
+
+/// Laid out so that compiled code can read the entries and count hits
+/// (see `shape_offset`, `slot_offset` and `hits_offset`).
+#[repr(C)]
+pub struct InlineCache {
+  /// The shapes seen at the access, null in unused entries.
+  shapes: [*const Shape; MAX_ENTRIES],
+  /// The offset in bytes of the property in the slots of an object of each shape.
+  slots: [usize; MAX_ENTRIES],
+  hits: usize,
+  misses: usize,
+}
+
+impl Default for InlineCache {
+  fn default() -> InlineCache {
+    InlineCache {
+      shapes: [ptr::null(); MAX_ENTRIES],
+      slots: [0; MAX_ENTRIES],
+      hits: 0,
+      misses: 0,
+    }
+  }
+}
+
+impl InlineCache {
+  /// Where the shape of the entry `i` is, in bytes from the start of the cache.
+  pub fn shape_offset(i: usize) -> usize {
+    i * mem::size_of::<*const Shape>()
+  }
+
+  /// Where the offset of the slot of the entry `i` is, in bytes from the start of the cache.
+  pub fn slot_offset(i: usize) -> usize {
+    mem::size_of::<[*const Shape; MAX_ENTRIES]>() + i * mem::size_of::<usize>()
+  }
+
+  /// Where the number of hits is, in bytes from the start of the cache.
+  pub fn hits_offset() -> usize {
+    mem::size_of::<[*const Shape; MAX_ENTRIES]>() + mem::size_of::<[usize; MAX_ENTRIES]>()
+  }
+
+  /// Caches the slot of a data property in objects of `shape`,
+  /// after compiled code missed it. Counts the miss.
+  pub fn update(&mut self, shape: *const Shape, slot: usize) {
+    self.count_miss();
+    if let Some(i) = self.shapes.iter().position(|cached| cached.is_null()) {
+      self.shapes[i] = shape;
+      self.slots[i] = slot * mem::size_of::<Property>();
+    }
+  }
+
+  /// Counts a miss on a property that is not cached, i.e. an accessor or a missing property.
+  pub fn count_miss(&mut self) {
+    self.misses += 1;
+  }
+}
+
+/// Where compiled code finds what it reads in a value with properties,
+/// in bytes from the start of its `JsObject`.
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectLayout {
+  /// The `ObjectKind` in the header, of which the kinds less than `ObjectKind::String`
+  /// begin with a `JsObject`.
+  pub kind: usize,
+  pub shape: usize,
+  /// The pointer to the slots.
+  pub slots: usize,
+  /// The value of a data property, from the start of its slot.
+  pub data: usize,
+}
+
+impl ObjectLayout {
+  fn new() -> ObjectLayout {
+    let object = JsObject::init(ObjectKind::Object, value::NULL);
+    let base = &object as *const JsObject as usize;
+    ObjectLayout {
+      kind: &object.header.kind as *const ObjectKind as usize - base,
+      shape: &object.shape as *const *const Shape as usize - base,
+      // `Slots` begins with the pointer to its buffer.
+      slots: &object.slots as *const Slots as usize - base,
+      // `Property` begins with a `u64` discriminant.
+      data: mem::size_of::<u64>(),
+    }
+  }
+
+  pub fn get() -> ObjectLayout {
+    LAYOUT.with(|layout| *layout)
+  }
+}
+
+/// The numbers of hits and misses of `caches` so far.
+pub fn stats(caches: &[*mut InlineCache]) -> (usize, usize) {
+  caches.iter().fold((0, 0), |(hits, misses), cache| {
+    let cache = unsafe { &**cache };
+    (hits + cache.hits, misses + cache.misses)
+  })
+}
+
+pub fn print_stats(caches: &[*mut InlineCache]) {
+  let (hits, misses) = stats(caches);
+  let total = hits + misses;
+  let rate = if total == 0 {
+    0.0
+  } else {
+    hits as f64 / total as f64 * 100.0
+  };
+  eprintln!(
+    "inline caches: {} hits, {} misses ({:.1}% hit rate)",
+    hits, misses, rate
+  );
+}
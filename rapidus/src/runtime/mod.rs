@@ -0,0 +1,1205 @@
+//! Runtime helpers called from the code `FuncCompiler` generates for dynamically typed values.
+//!
+//! Every helper is declared in the cilk `Module` under the name listed in `runtime_functions`,
+//! and its address is registered to the interpreter and the JIT before execution.
+//! Dynamically typed values are passed around as NaN-boxed `Int64`s (see `value::BoxedValue`).
+
+pub mod exception;
+pub mod gc;
+pub mod ic;
+pub mod object;
+pub mod regexp;
+pub mod shape;
+pub mod value;
+
+use self::ic::InlineCache;
+use self::object::{
+  Closure, Environment, IterationKind, JsArray, JsIterator, JsObject, JsRegExp, JsString,
+  ObjectKind, Property,
+};
+use self::regexp::{Captures, Regex};
+use self::value::BoxedValue;
+use cilk::codegen::x64::exec::jit::JITCompiler;
+use cilk::exec::interpreter::interp::Interpreter;
+use cilk::ir::{function, module::Module, types::Type};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+pub struct RuntimeFunction {
+  pub name: &'static str,
+  pub ret: Type,
+  pub params: Vec<Type>,
+  pub addr: u64,
+}
+
+macro_rules! runtime_functions {
+  ($( $name:expr => $func:ident($($param:expr),*) -> $ret:expr ),* $(,)*) => {
+    pub fn runtime_functions() -> Vec<RuntimeFunction> {
+      vec![$(
+        RuntimeFunction {
+          name: $name,
+          ret: $ret,
+          params: vec![$($param),*],
+          addr: $func as *const () as u64,
+        }
+      ),*]
+    }
+  };
+}
+
+runtime_functions! {
+  "rapidus.rt.from_f64"        => from_f64(Type::F64) -> Type::Int64,
+  "rapidus.rt.from_i32"        => from_i32(Type::Int32) -> Type::Int64,
+  "rapidus.rt.from_bool"       => from_bool(Type::Int32) -> Type::Int64,
+  "rapidus.rt.to_f64"          => to_f64(Type::Int64) -> Type::F64,
+  "rapidus.rt.to_i32"          => to_i32(Type::Int64) -> Type::Int32,
+  "rapidus.rt.to_boolean"      => to_boolean(Type::Int64) -> Type::Int32,
+  "rapidus.rt.add"             => add(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.eq"              => eq(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.ne"              => ne(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.seq"             => seq(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.sne"             => sne(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.lt"              => lt(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.le"              => le(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.gt"              => gt(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.ge"              => ge(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.f64_to_int32"    => f64_to_int32(Type::F64) -> Type::Int32,
+  "rapidus.rt.pow"             => pow(Type::F64, Type::F64) -> Type::F64,
+  "rapidus.rt.typeof_code"     => typeof_code(Type::Int64) -> Type::Int32,
+  "rapidus.rt.typeof_string"   => typeof_string(Type::Int64) -> Type::Int64,
+  "rapidus.rt.println"         => println(Type::Int64) -> Type::Void,
+  "rapidus.rt.print"           => print(Type::Int64, Type::Int32) -> Type::Void,
+  "rapidus.rt.get_member"      => get_member(Type::Int64, Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.set_member"      => set_member(Type::Int64, Type::Int64, Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.get_index"       => get_index(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.set_index"       => set_index(Type::Int64, Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.get_getter"      => get_getter(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.delete_property" => delete_property(Type::Int64, Type::Int64) -> Type::Int32,
+  "rapidus.rt.object_new"      => object_new() -> Type::Int64,
+  "rapidus.rt.define_property" => define_property(Type::Int64, Type::Int64, Type::Int64) -> Type::Void,
+  "rapidus.rt.define_accessor" => define_accessor(Type::Int64, Type::Int64, Type::Int64, Type::Int64) -> Type::Void,
+  "rapidus.rt.array_new"       => array_new(Type::Int32) -> Type::Int64,
+  "rapidus.rt.array_push"      => array_push(Type::Int64, Type::Int64) -> Type::Void,
+  "rapidus.rt.regexp_new"      => regexp_new(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.builtin_method"  => builtin_method(Type::Int64, Type::Int64) -> Type::Int32,
+  "rapidus.rt.call_builtin"    => call_builtin(Type::Int32, Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.env_new"         => env_new(Type::Int64, Type::Int32) -> Type::Int64,
+  "rapidus.rt.env_get"         => env_get(Type::Int64, Type::Int32) -> Type::Int64,
+  "rapidus.rt.env_set"         => env_set(Type::Int64, Type::Int32, Type::Int64) -> Type::Void,
+  "rapidus.rt.env_copy"        => env_copy(Type::Int64) -> Type::Int64,
+  "rapidus.rt.env_get_checked" => env_get_checked(Type::Int64, Type::Int32, Type::Int64) -> Type::Int64,
+  "rapidus.rt.env_set_checked" => env_set_checked(Type::Int64, Type::Int32, Type::Int64, Type::Int64) -> Type::Void,
+  "rapidus.rt.closure_new"     => closure_new(Type::Int32, Type::Int64, Type::Int64, Type::Int32) -> Type::Int64,
+  "rapidus.rt.function_object" => function_object(Type::Int32, Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.closure_func"    => closure_func(Type::Int64) -> Type::Int32,
+  "rapidus.rt.closure_env"     => closure_env(Type::Int64) -> Type::Int64,
+  "rapidus.rt.construct"       => construct(Type::Int64) -> Type::Int64,
+  "rapidus.rt.construct_result" => construct_result(Type::Int64, Type::Int64) -> Type::Int64,
+  "rapidus.rt.instance_of"     => instance_of(Type::Int64, Type::Int64) -> Type::Int32,
+  "rapidus.rt.for_in_iterator" => for_in_iterator(Type::Int64) -> Type::Int64,
+  "rapidus.rt.for_of_iterator" => for_of_iterator(Type::Int64) -> Type::Int64,
+  "rapidus.rt.iterator_method" => iterator_method(Type::Int64) -> Type::Int64,
+  "rapidus.rt.iterator_next"   => iterator_next(Type::Int64) -> Type::Int64,
+  "rapidus.rt.frame_enter"     => frame_enter() -> Type::Int32,
+  "rapidus.rt.frame_leave"     => frame_leave(Type::Int32, Type::Int64) -> Type::Void,
+  "rapidus.rt.frame_reset"     => frame_reset(Type::Int32) -> Type::Void,
+  "rapidus.rt.root"            => root(Type::Int64) -> Type::Void,
+  "rapidus.rt.root_env"        => root_env(Type::Int64) -> Type::Void,
+  "rapidus.rt.gc"              => gc() -> Type::Void,
+  "rapidus.rt.throw"           => throw(Type::Int64, Type::Int32) -> Type::Void,
+  "rapidus.rt.locate_exception" => locate_exception(Type::Int32) -> Type::Void,
+  "rapidus.rt.has_exception"   => has_exception() -> Type::Int32,
+  "rapidus.rt.exception_line"  => exception_line() -> Type::Int32,
+  "rapidus.rt.take_exception"  => take_exception() -> Type::Int64,
+}
+
+/// Declares every runtime helper in `module`.
+pub fn declare_runtime_functions(module: &mut Module) {
+  for f in runtime_functions() {
+    module.add_function(function::Function::new(f.name, f.ret, f.params));
+  }
+}
+
+pub fn register_to_jit(jit: &mut JITCompiler) {
+  for f in runtime_functions() {
+    jit.add_external_function(f.name, f.addr);
+  }
+}
+
+pub fn register_to_interpreter(interp: &mut Interpreter) {
+  for f in runtime_functions() {
+    interp.add_external_function(f.name, f.addr);
+  }
+}
+
+/// The values compiled code refers to by address, owned by the module containing the code:
+/// string literals, which are pinned in the heap while the module is alive (see `gc`),
+/// and inline caches (see `ic`), which are freed with it.
+#[derive(Debug, Default)]
+pub struct Constants {
+  /// Each distinct string literal is allocated once.
+  strings: HashMap<String, BoxedValue>,
+  /// The strings arrays of tagged templates and their arrays of raw strings.
+  templates: Vec<BoxedValue>,
+  caches: Vec<*mut InlineCache>,
+}
+
+impl Constants {
+  pub fn string(&mut self, s: &str) -> BoxedValue {
+    if let Some(v) = self.strings.get(s) {
+      return *v;
+    }
+    let v = BoxedValue::from_pointer(JsString::new_pinned(s.to_string()));
+    self.strings.insert(s.to_string(), v);
+    v
+  }
+
+  /// The strings array of a tagged template, with `raw` attached, created once for the
+  /// call site so that the tag gets the same array every time the template is evaluated.
+  pub fn template(&mut self, strings: &[String], raw: &[String]) -> BoxedValue {
+    let raw = raw.iter().map(|s| self.string(s)).collect();
+    let raw = BoxedValue::from_pointer(JsArray::new_pinned(raw, value::UNDEFINED));
+    let strings = strings.iter().map(|s| self.string(s)).collect();
+    let strings = BoxedValue::from_pointer(JsArray::new_pinned(strings, raw));
+    self.templates.push(raw);
+    self.templates.push(strings);
+    strings
+  }
+
+  pub fn inline_cache(&mut self) -> *mut InlineCache {
+    let cache = Box::into_raw(Box::new(InlineCache::default()));
+    self.caches.push(cache);
+    cache
+  }
+
+  pub fn inline_caches(&self) -> &[*mut InlineCache] {
+    &self.caches
+  }
+}
+
+impl Drop for Constants {
+  fn drop(&mut self) {
+    let strings: Vec<BoxedValue> = self.strings.values().cloned().collect();
+    gc::unpin(&strings);
+    gc::unpin(&self.templates);
+    for cache in &self.caches {
+      drop(unsafe { Box::from_raw(*cache) });
+    }
+  }
+}
+
+pub extern "C" fn from_f64(f: f64) -> BoxedValue {
+  BoxedValue::from_f64(f)
+}
+
+pub extern "C" fn from_i32(i: i32) -> BoxedValue {
+  BoxedValue::from_i32(i)
+}
+
+pub extern "C" fn from_bool(b: i32) -> BoxedValue {
+  BoxedValue::from_bool(b != 0)
+}
+
+pub extern "C" fn to_f64(v: BoxedValue) -> f64 {
+  v.to_number()
+}
+
+pub extern "C" fn to_i32(v: BoxedValue) -> i32 {
+  v.to_int32()
+}
+
+pub extern "C" fn to_boolean(v: BoxedValue) -> i32 {
+  v.to_boolean() as i32
+}
+
+pub extern "C" fn f64_to_int32(f: f64) -> i32 {
+  value::f64_to_int32(f)
+}
+
+/// https://tc39.github.io/ecma262/#sec-applying-the-exp-operator
+pub extern "C" fn pow(base: f64, exponent: f64) -> f64 {
+  if exponent.is_nan() || ((base == 1.0 || base == -1.0) && exponent.is_infinite()) {
+    return ::std::f64::NAN;
+  }
+  base.powf(exponent)
+}
+
+/// https://tc39.github.io/ecma262/#sec-addition-operator-plus
+pub extern "C" fn add(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  if lhs.is_string() || rhs.is_string() {
+    return BoxedValue::from_string(lhs.to_string() + &rhs.to_string());
+  }
+  if lhs.is_i32() && rhs.is_i32() {
+    if let Some(i) = lhs.as_i32().checked_add(rhs.as_i32()) {
+      return BoxedValue::from_i32(i);
+    }
+  }
+  BoxedValue::from_number(lhs.to_number() + rhs.to_number())
+}
+
+pub extern "C" fn eq(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(lhs.loose_equals(rhs))
+}
+
+pub extern "C" fn ne(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(!lhs.loose_equals(rhs))
+}
+
+pub extern "C" fn seq(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(lhs.strict_equals(rhs))
+}
+
+pub extern "C" fn sne(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(!lhs.strict_equals(rhs))
+}
+
+/// https://tc39.github.io/ecma262/#sec-abstract-relational-comparison
+/// Returns `None` if the result is undefined, i.e. either side is NaN.
+fn compare(lhs: BoxedValue, rhs: BoxedValue) -> Option<Ordering> {
+  if lhs.is_string() && rhs.is_string() {
+    // Strings are compared by their UTF-16 code units.
+    return Some(lhs.as_str().encode_utf16().cmp(rhs.as_str().encode_utf16()));
+  }
+  lhs.to_number().partial_cmp(&rhs.to_number())
+}
+
+pub extern "C" fn lt(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(compare(lhs, rhs) == Some(Ordering::Less))
+}
+
+pub extern "C" fn le(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(match compare(lhs, rhs) {
+    Some(Ordering::Less) | Some(Ordering::Equal) => true,
+    _ => false,
+  })
+}
+
+pub extern "C" fn gt(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(compare(lhs, rhs) == Some(Ordering::Greater))
+}
+
+pub extern "C" fn ge(lhs: BoxedValue, rhs: BoxedValue) -> BoxedValue {
+  BoxedValue::from_bool(match compare(lhs, rhs) {
+    Some(Ordering::Greater) | Some(Ordering::Equal) => true,
+    _ => false,
+  })
+}
+
+pub extern "C" fn typeof_code(v: BoxedValue) -> i32 {
+  v.type_of() as i32
+}
+
+/// https://tc39.github.io/ecma262/#sec-typeof-operator
+pub extern "C" fn typeof_string(v: BoxedValue) -> BoxedValue {
+  BoxedValue::from_string(value::TYPEOF_NAMES[v.type_of()].to_string())
+}
+
+pub extern "C" fn println(v: BoxedValue) {
+  println!("{}", v.inspect());
+}
+
+/// Prints `v` followed by the character `end`, for `console.log` with several arguments.
+pub extern "C" fn print(v: BoxedValue, end: i32) {
+  print!("{}{}", v.inspect(), end as u8 as char);
+}
+
+/// Reads the property `name` (a string) of `obj` after compiled code missed the inline cache
+/// of the access, and caches the slot of the property for the shape of `obj` (see `ic`).
+/// Like every helper loading a heap value, it pushes the value onto the shadow stack (see `gc`).
+/// Returns `EMPTY` if the property is an accessor, whose getter compiled code has to call.
+pub extern "C" fn get_member(
+  obj: BoxedValue,
+  name: BoxedValue,
+  cache: *mut InlineCache,
+) -> BoxedValue {
+  if !obj.has_properties() {
+    return get_property(obj, name.as_str());
+  }
+  let object = obj.as_object();
+  // Only own properties are cached; inherited ones are looked up along the prototype chain.
+  let slot = match object.shape().slot(name.as_str()) {
+    Some(slot) => slot,
+    None => {
+      unsafe { &mut *cache }.count_miss();
+      return get_property(obj, name.as_str());
+    }
+  };
+  match object.slots[slot] {
+    Property::Data(v) => {
+      unsafe { &mut *cache }.update(object.shape, slot);
+      gc::root(v)
+    }
+    Property::Accessor { get, .. } => {
+      unsafe { &mut *cache }.count_miss();
+      if get.is_undefined() {
+        value::UNDEFINED
+      } else {
+        value::EMPTY
+      }
+    }
+  }
+}
+
+/// Performs `obj.name = v` after compiled code missed the inline cache of the access,
+/// and caches the slot of the property for the shape of `obj` (see `ic`).
+/// Returns the setter compiled code has to call if the property is an accessor,
+/// and `undefined` otherwise.
+pub extern "C" fn set_member(
+  obj: BoxedValue,
+  name: BoxedValue,
+  v: BoxedValue,
+  cache: *mut InlineCache,
+) -> BoxedValue {
+  if !obj.has_properties() {
+    return set_property(obj, name.as_str(), v);
+  }
+  let object = obj.as_object();
+  // Only stores to existing properties are cached; adding one changes the shape.
+  let slot = match object.shape().slot(name.as_str()) {
+    Some(slot) => slot,
+    None => {
+      unsafe { &mut *cache }.count_miss();
+      return set_property(obj, name.as_str(), v);
+    }
+  };
+  match object.slots[slot] {
+    Property::Data(_) => {
+      unsafe { &mut *cache }.update(object.shape, slot);
+      object.slots[slot] = Property::Data(v);
+      value::UNDEFINED
+    }
+    Property::Accessor { set, .. } => {
+      unsafe { &mut *cache }.count_miss();
+      set
+    }
+  }
+}
+
+/// Reads `obj[key]`. Returns `EMPTY` for accessors like `get_member`.
+pub extern "C" fn get_index(obj: BoxedValue, key: BoxedValue) -> BoxedValue {
+  if let Some(index) = key.to_array_index() {
+    match obj.object_kind() {
+      Some(ObjectKind::Array) => {
+        return match obj.as_elements().get(index) {
+          Some(elem) => gc::root(*elem),
+          None => value::UNDEFINED,
+        };
+      }
+      Some(ObjectKind::String) => {
+        return match obj.as_str().encode_utf16().nth(index) {
+          Some(unit) => BoxedValue::from_string(String::from_utf16_lossy(&[unit])),
+          None => value::UNDEFINED,
+        };
+      }
+      _ => {}
+    }
+  }
+  get_property(obj, &key.to_string())
+}
+
+/// How many elements past twice its length an array may grow by at once.
+/// Arrays have no holes, so a store far past the end would allocate the whole gap.
+const MAX_ARRAY_GAP: usize = 1 << 16;
+
+/// Performs `obj[key] = v`, returning the setter to call like `set_member`.
+/// Storing past the end of an array grows it, filling the gap with `undefined`,
+/// and throws a `RangeError` if the gap is too large.
+pub extern "C" fn set_index(obj: BoxedValue, key: BoxedValue, v: BoxedValue) -> BoxedValue {
+  if obj.is_array() {
+    if let Some(index) = key.to_array_index() {
+      let elements = obj.as_elements();
+      if index >= elements.len() {
+        if index > elements.len() * 2 + MAX_ARRAY_GAP {
+          throw_range_error(format!(
+            "Array index {} is too far past the end of an array of length {}",
+            index,
+            elements.len()
+          ));
+          return value::UNDEFINED;
+        }
+        elements.resize(index + 1, value::UNDEFINED);
+      }
+      elements[index] = v;
+      return value::UNDEFINED;
+    }
+  }
+  set_property(obj, &key.to_string(), v)
+}
+
+/// The getter of the accessor property `key` of `obj` or of its prototypes.
+pub extern "C" fn get_getter(obj: BoxedValue, key: BoxedValue) -> BoxedValue {
+  match obj.as_object().lookup(&key.to_string()) {
+    Some(Property::Accessor { get, .. }) => get,
+    _ => value::UNDEFINED,
+  }
+}
+
+/// https://tc39.github.io/ecma262/#sec-delete-operator-runtime-semantics-evaluation
+pub extern "C" fn delete_property(obj: BoxedValue, key: BoxedValue) -> i32 {
+  if obj.is_null() || obj.is_undefined() {
+    throw_type_error(format!("Cannot convert {} to object", obj.to_string()));
+    return 0;
+  }
+  match obj.object_kind() {
+    Some(ObjectKind::Object) | Some(ObjectKind::Function) | Some(ObjectKind::RegExp) => {
+      obj.as_object().delete(&key.to_string());
+    }
+    // Arrays have no holes, so a deleted element becomes `undefined`.
+    Some(ObjectKind::Array) => {
+      if let Some(index) = key.to_array_index() {
+        if let Some(elem) = obj.as_elements().get_mut(index) {
+          *elem = value::UNDEFINED;
+        }
+      }
+    }
+    _ => {}
+  }
+  1
+}
+
+pub extern "C" fn object_new() -> BoxedValue {
+  BoxedValue::from_pointer(JsObject::new(value::NULL))
+}
+
+/// Defines the data property `name` of a new object, for object literals.
+/// `__proto__: v` sets the prototype instead, if `v` is an object or `null`.
+pub extern "C" fn define_property(obj: BoxedValue, name: BoxedValue, v: BoxedValue) {
+  let obj = obj.as_object();
+  match name.as_str() {
+    "__proto__" => {
+      if v.has_properties() || v.is_null() {
+        obj.proto = v;
+      }
+    }
+    name => obj.set(name, Property::Data(v)),
+  }
+}
+
+/// Defines the getter or the setter (the other one is `undefined`) of `name`,
+/// keeping the other half if `name` is already an accessor.
+pub extern "C" fn define_accessor(
+  obj: BoxedValue,
+  name: BoxedValue,
+  get: BoxedValue,
+  set: BoxedValue,
+) {
+  let obj = obj.as_object();
+  let name = name.as_str();
+  let property = match obj.get(name) {
+    Some(Property::Accessor {
+      get: old_get,
+      set: old_set,
+    }) => Property::Accessor {
+      get: if get.is_undefined() { old_get } else { get },
+      set: if set.is_undefined() { old_set } else { set },
+    },
+    _ => Property::Accessor { get, set },
+  };
+  obj.set(name, property);
+}
+
+/// Throws a `TypeError` for `null` and `undefined`, which have no properties.
+fn get_property(obj: BoxedValue, name: &str) -> BoxedValue {
+  if obj.is_null() || obj.is_undefined() {
+    throw_type_error(format!(
+      "Cannot read property '{}' of {}",
+      name,
+      obj.to_string()
+    ));
+    return value::UNDEFINED;
+  }
+  match obj.object_kind() {
+    Some(ObjectKind::String) if name == "length" => {
+      BoxedValue::from_i32(obj.as_str().encode_utf16().count() as i32)
+    }
+    Some(ObjectKind::Array) if name == "length" => {
+      BoxedValue::from_number(obj.as_elements().len() as f64)
+    }
+    Some(ObjectKind::Array) if name == "raw" && !obj.as_array().raw.is_undefined() => {
+      obj.as_array().raw
+    }
+    Some(ObjectKind::Object) | Some(ObjectKind::Function) if name == "__proto__" => {
+      obj.as_object().proto
+    }
+    Some(ObjectKind::RegExp) if name == "source" => {
+      BoxedValue::from_string(obj.as_regexp().source.clone())
+    }
+    Some(ObjectKind::RegExp) if name == "flags" => {
+      BoxedValue::from_string(obj.as_regexp().flags.clone())
+    }
+    Some(ObjectKind::RegExp) if name == "global" => {
+      BoxedValue::from_bool(obj.as_regexp().regex.global)
+    }
+    Some(ObjectKind::RegExp) if name == "ignoreCase" => {
+      BoxedValue::from_bool(obj.as_regexp().regex.ignore_case)
+    }
+    Some(ObjectKind::RegExp) if name == "multiline" => {
+      BoxedValue::from_bool(obj.as_regexp().regex.multiline)
+    }
+    Some(ObjectKind::RegExp) if name == "sticky" => {
+      BoxedValue::from_bool(obj.as_regexp().regex.sticky)
+    }
+    Some(ObjectKind::Object) | Some(ObjectKind::Function) | Some(ObjectKind::RegExp) => {
+      match obj.as_object().lookup(name) {
+        Some(Property::Data(v)) => gc::root(v),
+        Some(Property::Accessor { get, .. }) if !get.is_undefined() => value::EMPTY,
+        _ => value::UNDEFINED,
+      }
+    }
+    _ => value::UNDEFINED,
+  }
+}
+
+/// Returns the setter to call like `set_member`.
+/// Throws a `TypeError` for `null` and `undefined`, which have no properties.
+fn set_property(obj: BoxedValue, name: &str, v: BoxedValue) -> BoxedValue {
+  if obj.is_null() || obj.is_undefined() {
+    throw_type_error(format!(
+      "Cannot set property '{}' of {}",
+      name,
+      obj.to_string()
+    ));
+    return value::UNDEFINED;
+  }
+  if !obj.has_properties() {
+    // Properties of other values are not supported yet, and are silently dropped.
+    return value::UNDEFINED;
+  }
+  let obj = obj.as_object();
+  if name == "__proto__" {
+    if v.has_properties() || v.is_null() {
+      obj.proto = v;
+    }
+    return value::UNDEFINED;
+  }
+  match obj.get(name) {
+    Some(Property::Data(_)) => obj.set(name, Property::Data(v)),
+    // Inherited setters are called too, while inherited data properties are shadowed.
+    _ => match obj.lookup(name) {
+      Some(Property::Accessor { set, .. }) => return set,
+      _ => obj.set(name, Property::Data(v)),
+    },
+  }
+  value::UNDEFINED
+}
+
+/// Allocates an empty array with room for `capacity` elements.
+pub extern "C" fn array_new(capacity: i32) -> BoxedValue {
+  BoxedValue::from_pointer(JsArray::new(Vec::with_capacity(capacity as usize)))
+}
+
+pub extern "C" fn array_push(arr: BoxedValue, v: BoxedValue) {
+  arr.as_elements().push(v);
+}
+
+/// Creates the regular expression of a literal. `pattern` and `flags` are strings, which the
+/// compiler has already checked to be valid.
+pub extern "C" fn regexp_new(pattern: BoxedValue, flags: BoxedValue) -> BoxedValue {
+  let (pattern, flags) = (pattern.as_str(), flags.as_str());
+  let regex = Regex::new(pattern, flags).unwrap();
+  BoxedValue::from_pointer(JsRegExp::new(regex, pattern.to_string(), flags.to_string()))
+}
+
+/// Methods implemented in the runtime. A method is identified by its index in this table.
+const BUILTIN_METHODS: &[(ObjectKind, &str, fn(BoxedValue, &[BoxedValue]) -> BoxedValue)] = &[
+  (ObjectKind::Array, "push", array_prototype_push),
+  (ObjectKind::Array, "pop", array_prototype_pop),
+  (ObjectKind::RegExp, "test", regexp_prototype_test),
+  (ObjectKind::RegExp, "exec", regexp_prototype_exec),
+  (ObjectKind::String, "replace", string_prototype_replace),
+  (ObjectKind::String, "match", string_prototype_match),
+];
+
+/// The id of the builtin method `obj.name`, or -1 if `obj` has no such builtin method.
+pub extern "C" fn builtin_method(obj: BoxedValue, name: BoxedValue) -> i32 {
+  let kind = obj.object_kind();
+  let name = name.as_str();
+  match BUILTIN_METHODS
+    .iter()
+    .position(|(k, n, _)| Some(*k) == kind && *n == name)
+  {
+    Some(id) => id as i32,
+    None => -1,
+  }
+}
+
+/// Calls the builtin method `id` on `obj`. `args` is an array holding the arguments.
+pub extern "C" fn call_builtin(id: i32, obj: BoxedValue, args: BoxedValue) -> BoxedValue {
+  let (_, _, method) = BUILTIN_METHODS[id as usize];
+  gc::root(method(obj, args.as_elements()))
+}
+
+/// https://tc39.github.io/ecma262/#sec-array.prototype.push
+fn array_prototype_push(arr: BoxedValue, args: &[BoxedValue]) -> BoxedValue {
+  let elements = arr.as_elements();
+  elements.extend_from_slice(args);
+  BoxedValue::from_number(elements.len() as f64)
+}
+
+/// https://tc39.github.io/ecma262/#sec-array.prototype.pop
+fn array_prototype_pop(arr: BoxedValue, _args: &[BoxedValue]) -> BoxedValue {
+  arr.as_elements().pop().unwrap_or(value::UNDEFINED)
+}
+
+/// https://tc39.github.io/ecma262/#sec-regexpbuiltinexec
+/// Searches `text` from `lastIndex` if `re` is global or sticky, updating `lastIndex`.
+/// Indices count chars rather than UTF-16 code units.
+fn regexp_builtin_exec(re: BoxedValue, text: &[char]) -> Option<Captures> {
+  let regex = &re.as_regexp().regex;
+  let uses_last_index = regex.global || regex.sticky;
+  let start = if uses_last_index {
+    match re.as_object().get("lastIndex") {
+      Some(Property::Data(v)) => v.to_number().max(0.0) as usize,
+      _ => 0,
+    }
+  } else {
+    0
+  };
+  let caps = regex.search(text, start);
+  if uses_last_index {
+    let last_index = match caps {
+      Some(ref caps) => caps[0].unwrap().1,
+      None => 0,
+    };
+    let last_index = BoxedValue::from_i32(last_index as i32);
+    re.as_object().set("lastIndex", Property::Data(last_index));
+  }
+  caps
+}
+
+/// The array `exec` returns: the matched string followed by the captures,
+/// which are `undefined` for groups that did not participate.
+fn captures_to_array(text: &[char], caps: &Captures) -> BoxedValue {
+  let elements = caps
+    .iter()
+    .map(|cap| match cap {
+      Some((start, end)) => BoxedValue::from_string(text[*start..*end].iter().collect()),
+      None => value::UNDEFINED,
+    })
+    .collect();
+  BoxedValue::from_pointer(JsArray::new(elements))
+}
+
+/// https://tc39.github.io/ecma262/#sec-regexp.prototype.test
+fn regexp_prototype_test(re: BoxedValue, args: &[BoxedValue]) -> BoxedValue {
+  let text: Vec<char> = args
+    .get(0)
+    .unwrap_or(&value::UNDEFINED)
+    .to_string()
+    .chars()
+    .collect();
+  BoxedValue::from_bool(regexp_builtin_exec(re, &text).is_some())
+}
+
+/// https://tc39.github.io/ecma262/#sec-regexp.prototype.exec
+/// The result has no `index` and `input` properties, since arrays cannot have named properties.
+fn regexp_prototype_exec(re: BoxedValue, args: &[BoxedValue]) -> BoxedValue {
+  let text: Vec<char> = args
+    .get(0)
+    .unwrap_or(&value::UNDEFINED)
+    .to_string()
+    .chars()
+    .collect();
+  match regexp_builtin_exec(re, &text) {
+    Some(caps) => captures_to_array(&text, &caps),
+    None => value::NULL,
+  }
+}
+
+/// Every match of the global regular expression `re` in `text`, from the beginning.
+/// An empty match advances the search by one char, so that it terminates.
+fn regexp_match_all(re: BoxedValue, text: &[char]) -> Vec<Captures> {
+  let zero = Property::Data(BoxedValue::from_i32(0));
+  re.as_object().set("lastIndex", zero);
+  let mut matches = vec![];
+  while let Some(caps) = regexp_builtin_exec(re, text) {
+    let (start, end) = caps[0].unwrap();
+    if start == end {
+      let next = BoxedValue::from_i32(end as i32 + 1);
+      re.as_object().set("lastIndex", Property::Data(next));
+    }
+    matches.push(caps);
+  }
+  matches
+}
+
+/// https://tc39.github.io/ecma262/#sec-string.prototype.match
+/// A pattern other than a regular expression is converted to one.
+fn string_prototype_match(s: BoxedValue, args: &[BoxedValue]) -> BoxedValue {
+  let text: Vec<char> = s.as_str().chars().collect();
+  let re = match args.get(0) {
+    Some(re) if re.is_regexp() => *re,
+    pattern => {
+      let pattern = match pattern {
+        Some(pattern) if !pattern.is_undefined() => pattern.to_string(),
+        _ => "".to_string(),
+      };
+      let regex = match Regex::new(&pattern, "") {
+        Ok(regex) => regex,
+        Err(msg) => {
+          let message = format!("Invalid regular expression: /{}/: {}", pattern, msg);
+          throw_error("SyntaxError", message);
+          return value::UNDEFINED;
+        }
+      };
+      BoxedValue::from_pointer(JsRegExp::new(regex, pattern, "".to_string()))
+    }
+  };
+  if !re.as_regexp().regex.global {
+    return regexp_prototype_exec(re, &[s]);
+  }
+  let matches = regexp_match_all(re, &text);
+  if matches.is_empty() {
+    return value::NULL;
+  }
+  let elements = matches
+    .iter()
+    .map(|caps| {
+      let (start, end) = caps[0].unwrap();
+      BoxedValue::from_string(text[start..end].iter().collect())
+    })
+    .collect();
+  BoxedValue::from_pointer(JsArray::new(elements))
+}
+
+/// https://tc39.github.io/ecma262/#sec-string.prototype.replace
+/// Replaces the first occurrence of a string, or the matches of a regular expression:
+/// every match if it is global. Replacement functions are not supported yet, since the
+/// runtime cannot call compiled functions, and throw a `TypeError`.
+fn string_prototype_replace(s: BoxedValue, args: &[BoxedValue]) -> BoxedValue {
+  let text: Vec<char> = s.as_str().chars().collect();
+  let pattern = *args.get(0).unwrap_or(&value::UNDEFINED);
+  let replacement = *args.get(1).unwrap_or(&value::UNDEFINED);
+  if replacement.object_kind() == Some(ObjectKind::Function) {
+    throw_type_error("Replacement functions are not supported yet".to_string());
+    return value::UNDEFINED;
+  }
+  let replacement: Vec<char> = replacement.to_string().chars().collect();
+  let matches = if pattern.is_regexp() {
+    if pattern.as_regexp().regex.global {
+      regexp_match_all(pattern, &text)
+    } else {
+      regexp_builtin_exec(pattern, &text).into_iter().collect()
+    }
+  } else {
+    let pattern: Vec<char> = pattern.to_string().chars().collect();
+    let position = (0..=text.len()).find(|i| text[*i..].starts_with(&pattern));
+    match position {
+      Some(i) => vec![vec![Some((i, i + pattern.len()))]],
+      None => return s,
+    }
+  };
+  let mut result = String::new();
+  let mut last = 0;
+  for caps in &matches {
+    let (start, end) = caps[0].unwrap();
+    result.extend(&text[last..start]);
+    result += &get_substitution(&text, caps, &replacement);
+    last = end;
+  }
+  result.extend(&text[last..]);
+  BoxedValue::from_string(result)
+}
+
+/// https://tc39.github.io/ecma262/#sec-getsubstitution
+/// Expands `$$`, `$&`, `` $` ``, `$'` and `$n` in `replacement`.
+fn get_substitution(text: &[char], caps: &Captures, replacement: &[char]) -> String {
+  let (start, end) = caps[0].unwrap();
+  let capture = |n: usize| match caps[n] {
+    Some((start, end)) => text[start..end].iter().collect(),
+    None => "".to_string(),
+  };
+  let digit = |i: usize| {
+    replacement
+      .get(i)
+      .and_then(|c| c.to_digit(10))
+      .map(|d| d as usize)
+  };
+  let mut result = String::new();
+  let mut i = 0;
+  while i < replacement.len() {
+    let c = replacement[i];
+    i += 1;
+    if c != '$' || i == replacement.len() {
+      result.push(c);
+      continue;
+    }
+    match replacement[i] {
+      '$' => result.push('$'),
+      '&' => result.extend(&text[start..end]),
+      '`' => result.extend(&text[..start]),
+      '\'' => result.extend(&text[end..]),
+      _ => match digit(i) {
+        // `$nn` refers to a group if there are that many, and otherwise `$n` is followed by a digit.
+        Some(n) => match digit(i + 1).map(|m| n * 10 + m) {
+          Some(nn) if nn >= 1 && nn < caps.len() => {
+            result += &capture(nn);
+            i += 1;
+          }
+          _ if n >= 1 && n < caps.len() => result += &capture(n),
+          _ => result.extend(&['$', replacement[i]]),
+        },
+        None => {
+          result.push('$');
+          continue;
+        }
+      },
+    }
+    i += 1;
+  }
+  result
+}
+
+/// Allocates the environment record of a function call or a block (see `object::Environment`)
+/// with `len` slots for captured variables, initialized to `undefined`.
+/// Compiled code refers to records by raw pointers.
+pub extern "C" fn env_new(parent: u64, len: i32) -> u64 {
+  let mut slots = vec![value::UNDEFINED.0; len as usize + 1];
+  slots[0] = parent;
+  Environment::new(slots) as u64
+}
+
+pub extern "C" fn env_get(env: u64, index: i32) -> u64 {
+  let v = unsafe { (*(env as *mut Environment)).slots[index as usize] };
+  if index == 0 {
+    v
+  } else {
+    gc::root(BoxedValue(v)).0
+  }
+}
+
+pub extern "C" fn env_set(env: u64, index: i32, v: u64) {
+  unsafe { (*(env as *mut Environment)).slots[index as usize] = v }
+}
+
+/// Reads a captured `let` or `const` binding from a nested function,
+/// throwing a `ReferenceError` if the binding `name` is still in its TDZ.
+pub extern "C" fn env_get_checked(env: u64, index: i32, name: BoxedValue) -> u64 {
+  let v = env_get(env, index);
+  if v == value::UNINITIALIZED.0 {
+    throw_reference_error(format!("Cannot access '{}' before initialization", name.as_str()));
+    return value::UNDEFINED.0;
+  }
+  v
+}
+
+/// Assigns to a captured `let` or `const` binding from a nested function,
+/// throwing a `ReferenceError` if the binding `name` is still in its TDZ.
+pub extern "C" fn env_set_checked(env: u64, index: i32, v: u64, name: BoxedValue) {
+  let slot = unsafe { (*(env as *mut Environment)).slots[index as usize] };
+  if slot == value::UNINITIALIZED.0 {
+    throw_reference_error(format!("Cannot access '{}' before initialization", name.as_str()));
+    return;
+  }
+  env_set(env, index, v)
+}
+
+/// Copies the environment record of a block for the next iteration of a loop,
+/// leaving the record of the previous iteration to the closures that captured it.
+pub extern "C" fn env_copy(env: u64) -> u64 {
+  let slots = unsafe { (*(env as *mut Environment)).slots.clone() };
+  Environment::new(slots) as u64
+}
+
+/// Creates a value of the function `function` named `name` (a string, empty if anonymous).
+/// A constructor gets a `prototype` object whose `constructor` refers back to it.
+pub extern "C" fn closure_new(
+  function: i32,
+  env: u64,
+  name: BoxedValue,
+  is_constructor: i32,
+) -> BoxedValue {
+  let f = BoxedValue::from_pointer(Closure::new(function, env, name, is_constructor != 0));
+  if is_constructor != 0 {
+    let prototype = JsObject::new(value::NULL);
+    unsafe { &mut *prototype }.set("constructor", Property::Data(f));
+    let prototype = Property::Data(BoxedValue::from_pointer(prototype));
+    f.as_object().set("prototype", prototype);
+  }
+  f
+}
+
+/// The value of the function `function` declared in the call whose environment record is `env`.
+/// The value is created on first use, so that properties set on it (e.g. `prototype`) persist.
+pub extern "C" fn function_object(function: i32, env: u64, name: BoxedValue) -> BoxedValue {
+  let functions = unsafe { &mut (*(env as *mut Environment)).functions };
+  if let Some((_, f)) = functions.iter().find(|(id, _)| *id == function) {
+    return gc::root(*f);
+  }
+  let f = closure_new(function, env, name, 1);
+  functions.push((function, f));
+  f
+}
+
+/// The id of the function `callee` refers to, or -1 after throwing a `TypeError`
+/// if it is not a function.
+pub extern "C" fn closure_func(callee: BoxedValue) -> i32 {
+  if callee.object_kind() != Some(ObjectKind::Function) {
+    throw_type_error(format!("{} is not a function", callee.to_string()));
+    return -1;
+  }
+  callee.as_closure().function
+}
+
+pub extern "C" fn closure_env(callee: BoxedValue) -> u64 {
+  callee.as_closure().env
+}
+
+/// Creates the `this` object of `new callee(...)`, inheriting from `callee.prototype`.
+/// https://tc39.github.io/ecma262/#sec-ordinarycreatefromconstructor
+pub extern "C" fn construct(callee: BoxedValue) -> BoxedValue {
+  if callee.object_kind() != Some(ObjectKind::Function) || !callee.as_closure().is_constructor {
+    throw_type_error(format!("{} is not a constructor", callee.to_string()));
+    return value::UNDEFINED;
+  }
+  let proto = match callee.as_object().lookup("prototype") {
+    Some(Property::Data(proto)) if proto.has_properties() => proto,
+    _ => value::NULL,
+  };
+  BoxedValue::from_pointer(JsObject::new(proto))
+}
+
+/// The value of `new`: what the constructor returned if it is an object, and `this` otherwise.
+pub extern "C" fn construct_result(ret: BoxedValue, this: BoxedValue) -> BoxedValue {
+  if ret.has_properties() || ret.is_array() {
+    ret
+  } else {
+    this
+  }
+}
+
+/// https://tc39.github.io/ecma262/#sec-ordinaryhasinstance
+pub extern "C" fn instance_of(v: BoxedValue, ctor: BoxedValue) -> i32 {
+  if ctor.object_kind() != Some(ObjectKind::Function) {
+    throw_type_error("Right-hand side of 'instanceof' is not callable".to_string());
+    return 0;
+  }
+  if !v.has_properties() {
+    return 0;
+  }
+  let prototype = match ctor.as_object().lookup("prototype") {
+    Some(Property::Data(prototype)) => prototype,
+    _ => value::UNDEFINED,
+  };
+  let mut proto = v.as_object().proto;
+  while proto.has_properties() {
+    if proto == prototype {
+      return 1;
+    }
+    proto = proto.as_object().proto;
+  }
+  0
+}
+
+/// The key a script stores its `[Symbol.iterator]` method under, as there are no symbols yet:
+/// an object with a function `'@@iterator'` property can be iterated by for-of.
+pub const ITERATOR_KEY: &str = "@@iterator";
+
+/// The keys for-in visits: the enumerable keys of `obj`, then those of its prototypes which are
+/// not shadowed. Arrays and strings have their indices as keys.
+/// https://tc39.github.io/ecma262/#sec-enumerate-object-properties
+fn enumerable_keys(obj: BoxedValue) -> Vec<String> {
+  let indices = |len: usize| -> Vec<String> { (0..len).map(|i| i.to_string()).collect() };
+  match obj.object_kind() {
+    Some(ObjectKind::Array) => indices(obj.as_elements().len()),
+    Some(ObjectKind::String) => indices(obj.as_str().encode_utf16().count()),
+    Some(ObjectKind::Object) | Some(ObjectKind::Function) | Some(ObjectKind::RegExp) => {
+      let mut keys = vec![];
+      let mut visited = HashSet::new();
+      let mut obj = obj;
+      while obj.has_properties() {
+        let object: &JsObject = obj.as_object();
+        for name in own_keys(object) {
+          // Non-enumerable properties shadow inherited ones too.
+          if visited.insert(name) && object.is_enumerable(name) {
+            keys.push(name.to_string());
+          }
+        }
+        obj = object.proto;
+      }
+      keys
+    }
+    _ => vec![],
+  }
+}
+
+/// Integer keys in ascending order, then the other keys in the order they were added.
+/// https://tc39.github.io/ecma262/#sec-ordinaryownpropertykeys
+fn own_keys(obj: &JsObject) -> Vec<&str> {
+  let mut keys: Vec<&str> = obj
+    .shape()
+    .names()
+    .iter()
+    .map(|name| name.as_str())
+    .collect();
+  keys.sort_by_key(|key| match key.parse::<u32>() {
+    Ok(index) if index.to_string() == *key => (0, index),
+    _ => (1, 0),
+  });
+  keys
+}
+
+/// Creates the iterator of `for (... in obj)`. The keys are collected up front, and those
+/// deleted before they are reached are skipped by `iterator_next`.
+pub extern "C" fn for_in_iterator(obj: BoxedValue) -> BoxedValue {
+  let keys = enumerable_keys(obj);
+  BoxedValue::from_pointer(JsIterator::new(IterationKind::Keys, obj, keys))
+}
+
+/// Creates the iterator of `for (... of v)` if `v` is an array or a string, which are iterated
+/// without calling into the script. Returns `EMPTY` otherwise, and compiled code falls back to
+/// the iterator protocol.
+pub extern "C" fn for_of_iterator(v: BoxedValue) -> BoxedValue {
+  let kind = match v.object_kind() {
+    Some(ObjectKind::Array) => IterationKind::ArrayValues,
+    Some(ObjectKind::String) => IterationKind::StringValues,
+    _ => return value::EMPTY,
+  };
+  BoxedValue::from_pointer(JsIterator::new(kind, v, vec![]))
+}
+
+/// The method compiled code calls to get the iterator of `v` (see `ITERATOR_KEY`).
+/// https://tc39.github.io/ecma262/#sec-getiterator
+pub extern "C" fn iterator_method(v: BoxedValue) -> BoxedValue {
+  if v.has_properties() {
+    if let Some(Property::Data(f)) = v.as_object().lookup(ITERATOR_KEY) {
+      if f.object_kind() == Some(ObjectKind::Function) {
+        return gc::root(f);
+      }
+    }
+  }
+  throw_type_error(format!("{} is not iterable", v.to_string()));
+  value::UNDEFINED
+}
+
+/// Advances an iterator created by `for_in_iterator` or `for_of_iterator`,
+/// returning `EMPTY` when it is done.
+pub extern "C" fn iterator_next(iter: BoxedValue) -> BoxedValue {
+  let iter = unsafe { &mut *iter.as_pointer::<JsIterator>() };
+  match iter.kind {
+    IterationKind::Keys => {
+      while let Some(key) = iter.keys.get(iter.index) {
+        iter.index += 1;
+        let exists = match iter.target.object_kind() {
+          Some(ObjectKind::Array) => {
+            key.parse::<usize>().unwrap() < iter.target.as_elements().len()
+          }
+          Some(ObjectKind::String) => true,
+          _ => iter.target.as_object().lookup(key).is_some(),
+        };
+        if exists {
+          return BoxedValue::from_string(key.clone());
+        }
+      }
+      value::EMPTY
+    }
+    // Elements pushed during the iteration are visited too.
+    IterationKind::ArrayValues => match iter.target.as_elements().get(iter.index) {
+      Some(elem) => {
+        iter.index += 1;
+        gc::root(*elem)
+      }
+      None => value::EMPTY,
+    },
+    IterationKind::StringValues => match iter.target.as_str()[iter.index..].chars().next() {
+      Some(c) => {
+        iter.index += c.len_utf8();
+        BoxedValue::from_string(c.to_string())
+      }
+      None => value::EMPTY,
+    },
+  }
+}
+
+thread_local! {
+  static GLOBAL_OBJECT: BoxedValue = {
+    let obj = BoxedValue::from_pointer(JsObject::new(value::NULL));
+    gc::root_permanently(obj);
+    obj
+  };
+}
+
+/// The global object, which is `this` in functions called without a receiver.
+pub fn global_object() -> BoxedValue {
+  GLOBAL_OBJECT.with(|obj| *obj)
+}
+
+/// Enters the frame of a compiled function, returning the height of the shadow stack.
+pub extern "C" fn frame_enter() -> i32 {
+  gc::frame_enter() as i32
+}
+
+/// Leaves the frame entered at `height`, keeping the return value `ret` alive.
+/// Functions that do not return a boxed value pass `undefined`.
+pub extern "C" fn frame_leave(height: i32, ret: BoxedValue) {
+  gc::frame_leave(height as usize, ret)
+}
+
+/// Drops the temporaries of the frame entered at `height`, at the header of a loop.
+pub extern "C" fn frame_reset(height: i32) {
+  gc::frame_reset(height as usize)
+}
+
+pub extern "C" fn root(v: BoxedValue) {
+  gc::root(v);
+}
+
+pub extern "C" fn root_env(env: u64) {
+  gc::root(BoxedValue::from_pointer(env as *mut Environment));
+}
+
+/// The `gc()` builtin, which forces a collection.
+pub extern "C" fn gc() {
+  gc::collect()
+}
+
+/// Throws an error like a `TypeError` from a runtime helper, which then returns a dummy value.
+/// There are no error constructors yet, so the error is a plain object with a `name`
+/// and a `message`.
+fn throw_error(name: &str, message: String) {
+  let error = BoxedValue::from_pointer(JsObject::new(value::NULL));
+  let name = BoxedValue::from_string(name.to_string());
+  error.as_object().set("name", Property::Data(name));
+  let message = BoxedValue::from_string(message);
+  error.as_object().set("message", Property::Data(message));
+  exception::throw(error, 0);
+}
+
+fn throw_type_error(message: String) {
+  throw_error("TypeError", message)
+}
+
+fn throw_reference_error(message: String) {
+  throw_error("ReferenceError", message)
+}
+
+fn throw_range_error(message: String) {
+  throw_error("RangeError", message)
+}
+
+/// Throws `v` from the `throw` statement at `line` (see `exception`).
+pub extern "C" fn throw(v: BoxedValue, line: i32) {
+  exception::throw(v, line as usize)
+}
+
+/// Gives an exception thrown by a runtime helper the line of the call to the helper.
+pub extern "C" fn locate_exception(line: i32) {
+  exception::locate(line as usize)
+}
+
+pub extern "C" fn has_exception() -> i32 {
+  exception::pending().is_some() as i32
+}
+
+/// The line the pending exception was thrown at, kept while a `finally` clause runs.
+pub extern "C" fn exception_line() -> i32 {
+  exception::pending().map_or(0, |e| e.line as i32)
+}
+
+/// Catches the pending exception, returning the thrown value.
+pub extern "C" fn take_exception() -> BoxedValue {
+  match exception::take() {
+    Some(e) => gc::root(e.value),
+    None => value::UNDEFINED,
+  }
+}
@@ -0,0 +1,365 @@
+use super::gc;
+use super::regexp::Regex;
+use super::shape::Shape;
+use super::value::{self, BoxedValue};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+/// Kinds of heap-allocated values, stored at the beginning of each of them.
+/// The kinds of values beginning with a `JsObject` come first, so that compiled code tells
+/// them apart with a single comparison (see `ic`).
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectKind {
+  Object,
+  Function,
+  RegExp,
+  String,
+  Array,
+  Environment,
+  Iterator,
+}
+
+/// The header shared by every heap-allocated value.
+#[repr(C)]
+pub struct ObjectHeader {
+  pub kind: ObjectKind,
+  /// Set while the garbage collector finds the value reachable.
+  pub marked: bool,
+}
+
+impl ObjectHeader {
+  pub fn new(kind: ObjectKind) -> ObjectHeader {
+    ObjectHeader {
+      kind,
+      marked: false,
+    }
+  }
+}
+
+/// A function value: a compiled function and the environment record it was created in.
+/// Functions are objects too, so a closure begins with a `JsObject`.
+#[repr(C)]
+pub struct Closure {
+  pub object: JsObject,
+  /// Index of the function in `TypeInfo::function_values`.
+  pub function: i32,
+  pub env: u64,
+  /// The name of the function, or an empty string.
+  pub name: BoxedValue,
+  /// Whether `new` can be applied to the function, i.e. it is not an arrow function.
+  pub is_constructor: bool,
+}
+
+impl Closure {
+  pub fn new(function: i32, env: u64, name: BoxedValue, is_constructor: bool) -> *mut Closure {
+    gc::alloc(Closure {
+      object: JsObject::init(ObjectKind::Function, value::NULL),
+      function,
+      env,
+      name,
+      is_constructor,
+    })
+  }
+}
+
+/// An immutable string.
+#[repr(C)]
+pub struct JsString {
+  pub header: ObjectHeader,
+  pub value: String,
+}
+
+impl JsString {
+  pub fn new(value: String) -> *mut JsString {
+    gc::alloc(JsString {
+      header: ObjectHeader::new(ObjectKind::String),
+      value,
+    })
+  }
+
+  /// Allocates a string literal embedded in compiled code, which is not collected until it is
+  /// unpinned (see `Constants`).
+  pub fn new_pinned(value: String) -> *mut JsString {
+    gc::alloc_pinned(JsString {
+      header: ObjectHeader::new(ObjectKind::String),
+      value,
+    })
+  }
+}
+
+#[repr(C)]
+pub struct JsArray {
+  pub header: ObjectHeader,
+  pub elements: Vec<BoxedValue>,
+  /// The array of raw strings if this is the strings array of a tagged template,
+  /// or `undefined`. Arrays have no other named properties yet.
+  pub raw: BoxedValue,
+}
+
+impl JsArray {
+  pub fn new(elements: Vec<BoxedValue>) -> *mut JsArray {
+    gc::alloc(JsArray {
+      header: ObjectHeader::new(ObjectKind::Array),
+      elements,
+      raw: value::UNDEFINED,
+    })
+  }
+
+  /// Allocates an array embedded in compiled code, which is not collected until it is
+  /// unpinned (see `Constants`).
+  pub fn new_pinned(elements: Vec<BoxedValue>, raw: BoxedValue) -> *mut JsArray {
+    gc::alloc_pinned(JsArray {
+      header: ObjectHeader::new(ObjectKind::Array),
+      elements,
+      raw,
+    })
+  }
+}
+
+/// A regular expression, created by a literal. Like functions, it is an object with properties,
+/// `lastIndex` among them.
+#[repr(C)]
+pub struct JsRegExp {
+  pub object: JsObject,
+  pub regex: Regex,
+  pub source: String,
+  pub flags: String,
+}
+
+impl JsRegExp {
+  pub fn new(regex: Regex, source: String, flags: String) -> *mut JsRegExp {
+    let mut object = JsObject::init(ObjectKind::RegExp, value::NULL);
+    object.set("lastIndex", Property::Data(BoxedValue::from_i32(0)));
+    gc::alloc(JsRegExp {
+      object,
+      regex,
+      source,
+      flags,
+    })
+  }
+}
+
+/// A property of an ordinary object.
+/// Compiled code reads and writes data properties in place (see `ic`), so the layout is fixed:
+/// a `u64` discriminant, 0 for `Data`, followed by the fields.
+#[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Property {
+  Data(BoxedValue),
+  /// `get` and `set` are functions, or `undefined` if missing.
+  Accessor { get: BoxedValue, set: BoxedValue },
+}
+
+/// The growable buffer of the properties of an object. Unlike a `Vec`, its layout is fixed:
+/// compiled code finds the buffer at the start (see `ic`).
+#[repr(C)]
+pub struct Slots {
+  ptr: *mut Property,
+  len: usize,
+  cap: usize,
+}
+
+impl Default for Slots {
+  fn default() -> Slots {
+    let mut vec = ManuallyDrop::new(vec![]);
+    Slots {
+      ptr: vec.as_mut_ptr(),
+      len: 0,
+      cap: vec.capacity(),
+    }
+  }
+}
+
+impl Slots {
+  /// Changes the buffer as a `Vec`, which is leaked rather than freed twice if `f` panics.
+  fn with_vec<T>(&mut self, f: impl FnOnce(&mut Vec<Property>) -> T) -> T {
+    let mut vec = ManuallyDrop::new(unsafe { Vec::from_raw_parts(self.ptr, self.len, self.cap) });
+    let result = f(&mut vec);
+    self.ptr = vec.as_mut_ptr();
+    self.len = vec.len();
+    self.cap = vec.capacity();
+    result
+  }
+
+  pub fn push(&mut self, property: Property) {
+    self.with_vec(|vec| vec.push(property))
+  }
+
+  pub fn remove(&mut self, slot: usize) -> Property {
+    self.with_vec(|vec| vec.remove(slot))
+  }
+}
+
+impl Deref for Slots {
+  type Target = [Property];
+
+  fn deref(&self) -> &[Property] {
+    unsafe { slice::from_raw_parts(self.ptr, self.len) }
+  }
+}
+
+impl DerefMut for Slots {
+  fn deref_mut(&mut self) -> &mut [Property] {
+    unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+  }
+}
+
+impl Drop for Slots {
+  fn drop(&mut self) {
+    drop(unsafe { Vec::from_raw_parts(self.ptr, self.len, self.cap) })
+  }
+}
+
+/// An ordinary object, created by an object literal or `new`.
+#[repr(C)]
+pub struct JsObject {
+  pub header: ObjectHeader,
+  pub shape: *const Shape,
+  /// Property values, in the slots `shape` assigns to them.
+  pub slots: Slots,
+  /// The prototype (`__proto__`): an object, or `null`.
+  pub proto: BoxedValue,
+}
+
+impl JsObject {
+  pub fn new(proto: BoxedValue) -> *mut JsObject {
+    gc::alloc(JsObject::init(ObjectKind::Object, proto))
+  }
+
+  /// An object without properties, to be embedded in a value of `kind`.
+  pub fn init(kind: ObjectKind, proto: BoxedValue) -> JsObject {
+    JsObject {
+      header: ObjectHeader::new(kind),
+      shape: Shape::root(),
+      slots: Slots::default(),
+      proto,
+    }
+  }
+
+  pub fn shape<'a>(&self) -> &'a Shape {
+    unsafe { &*self.shape }
+  }
+
+  pub fn get(&self, name: &str) -> Option<Property> {
+    self.shape().slot(name).map(|slot| self.slots[slot])
+  }
+
+  /// Adds the property `name`, or replaces it if it already exists.
+  pub fn set(&mut self, name: &str, property: Property) {
+    match self.shape().slot(name) {
+      Some(slot) => self.slots[slot] = property,
+      None => {
+        self.shape = self.shape().with_property(name);
+        self.slots.push(property);
+      }
+    }
+  }
+
+  /// Removes the property `name`, returning whether it existed.
+  /// The object gets the shape it would have if `name` had never been added.
+  pub fn delete(&mut self, name: &str) -> bool {
+    let slot = match self.shape().slot(name) {
+      Some(slot) => slot,
+      None => return false,
+    };
+    self.slots.remove(slot);
+    let mut shape = Shape::root();
+    for other in self.shape().names() {
+      if other != name {
+        shape = unsafe { &*shape }.with_property(other);
+      }
+    }
+    self.shape = shape;
+    true
+  }
+
+  /// Looks up the property `name` of this object or of its prototypes.
+  pub fn lookup(&self, name: &str) -> Option<Property> {
+    let mut obj = self;
+    loop {
+      if let Some(property) = obj.get(name) {
+        return Some(property);
+      }
+      if !obj.proto.has_properties() {
+        return None;
+      }
+      obj = obj.proto.as_object();
+    }
+  }
+
+  /// Properties have no attributes yet, so the built-in non-enumerable ones are told by name:
+  /// `constructor` of prototype objects, `prototype` of functions and `lastIndex` of regexps.
+  pub fn is_enumerable(&self, name: &str) -> bool {
+    match (self.header.kind, name) {
+      (_, "constructor") => false,
+      (ObjectKind::Function, "prototype") => false,
+      (ObjectKind::RegExp, "lastIndex") => false,
+      _ => true,
+    }
+  }
+
+  /// Properties in the order they were added, which is the order they are printed in.
+  pub fn properties(&self) -> Vec<(&str, Property)> {
+    let names = self.shape().names().iter().map(|name| name.as_str());
+    names.zip(self.slots.iter().cloned()).collect()
+  }
+}
+
+/// The environment record of a function call or a block, which holds the variables captured
+/// by nested functions. Slot 0 points to the record enclosing it (or is 0),
+/// and the others hold `BoxedValue`s.
+#[repr(C)]
+pub struct Environment {
+  pub header: ObjectHeader,
+  pub slots: Vec<u64>,
+  /// Values of the functions declared in the function call, created on first use,
+  /// so that every reference to a declared function yields the same object.
+  pub functions: Vec<(i32, BoxedValue)>,
+}
+
+impl Environment {
+  pub fn new(slots: Vec<u64>) -> *mut Environment {
+    gc::alloc(Environment {
+      header: ObjectHeader::new(ObjectKind::Environment),
+      slots,
+      functions: vec![],
+    })
+  }
+}
+
+/// What the values of an iterator are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IterationKind {
+  /// The property keys collected when the iterator was created, for for-in.
+  Keys,
+  /// The elements of an array, for for-of.
+  ArrayValues,
+  /// The code points of a string, for for-of.
+  StringValues,
+}
+
+/// The state of a for-in loop, or of a for-of loop over an array or a string.
+/// Iterators are never exposed to scripts: compiled code keeps them in a hidden variable.
+#[repr(C)]
+pub struct JsIterator {
+  pub header: ObjectHeader,
+  pub kind: IterationKind,
+  pub target: BoxedValue,
+  pub keys: Vec<String>,
+  /// The index of the next key or element, or the byte offset of the next code point.
+  pub index: usize,
+}
+
+impl JsIterator {
+  pub fn new(kind: IterationKind, target: BoxedValue, keys: Vec<String>) -> *mut JsIterator {
+    gc::alloc(JsIterator {
+      header: ObjectHeader::new(ObjectKind::Iterator),
+      kind,
+      target,
+      keys,
+      index: 0,
+    })
+  }
+}
@@ -0,0 +1,820 @@
+//! A backtracking engine for regular expressions.
+//! https://tc39.github.io/ecma262/#sec-regexp-regular-expression-objects
+//!
+//! A pattern is parsed into a tree, which is compiled to a program trying alternatives in order
+//! as the specification describes. The ways left to try are kept on an explicit stack rather than
+//! in recursive calls, so that a quantifier may repeat over long inputs.
+//! Positions are indices of chars rather than of UTF-16 code units.
+
+use std::ops::Range;
+
+/// The start and end positions of each capturing group, group 0 being the whole match.
+pub type Captures = Vec<Option<(usize, usize)>>;
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+  program: Vec<Inst>,
+  /// The number of registers `program` uses.
+  register_count: usize,
+  /// The number of capturing groups, including the whole match.
+  group_count: usize,
+  pub global: bool,
+  pub ignore_case: bool,
+  pub multiline: bool,
+  pub sticky: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+  Char(char),
+  /// `.`, which matches anything but line terminators.
+  Any,
+  Class(Class),
+  /// `^`
+  LineStart,
+  /// `$`
+  LineEnd,
+  /// `\b`, or `\B` if negated.
+  WordBoundary(bool),
+  /// A group, with the index of its captures unless it is `(?:...)`.
+  Group(Box<Node>, Option<usize>),
+  /// `(?=...)`, or `(?!...)` if negated.
+  Lookahead(Box<Node>, bool),
+  BackReference(usize),
+  Repeat(Repeat),
+  Sequence(Vec<Node>),
+  Alternation(Vec<Node>),
+}
+
+/// A quantified atom, such as `a*` or `(b|c){2,3}?`.
+#[derive(Clone, Debug, PartialEq)]
+struct Repeat {
+  node: Box<Node>,
+  min: usize,
+  max: Option<usize>,
+  greedy: bool,
+  /// The capturing groups in `node`, which are cleared at each iteration.
+  groups: Range<usize>,
+}
+
+/// A character class such as `[a-z]` or `\d`.
+#[derive(Clone, Debug, PartialEq)]
+struct Class {
+  items: Vec<ClassItem>,
+  negated: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ClassItem {
+  Range(char, char),
+  /// A class escape such as `\d` inside brackets.
+  Class(Class),
+}
+
+/// An instruction of the program a pattern is compiled to. Instructions which match at the
+/// current position fail or go on at the next one, unless they say otherwise.
+#[derive(Clone, Debug)]
+enum Inst {
+  Char(char),
+  Any,
+  Class(Class),
+  LineStart,
+  LineEnd,
+  WordBoundary(bool),
+  BackReference(usize),
+  /// Goes on at the next instruction, and at the given one if that fails.
+  Fork(usize),
+  Jump(usize),
+  /// Records the start of a group in the register.
+  GroupStart(usize),
+  /// Captures group `index` from the start in `register`.
+  GroupEnd {
+    index: usize,
+    register: usize,
+  },
+  /// A lookahead, whose instructions follow up to `end` and finish with `Match`.
+  Lookahead {
+    negated: bool,
+    end: usize,
+  },
+  /// Sets the iteration count of a repeat, kept in the register, to 0.
+  RepeatInit(usize),
+  /// Either starts another iteration at the next instruction or leaves the repeat at `exit`,
+  /// trying the other if that fails.
+  RepeatLoop {
+    count: usize,
+    min: usize,
+    max: Option<usize>,
+    greedy: bool,
+    exit: usize,
+  },
+  /// Records the start of an iteration and clears the captures of the previous one.
+  RepeatEnter {
+    start: usize,
+    groups: Range<usize>,
+  },
+  /// Ends an iteration, going back to the `RepeatLoop` at `next`.
+  RepeatEnd {
+    count: usize,
+    start: usize,
+    min: usize,
+    next: usize,
+  },
+  Match,
+}
+
+impl Regex {
+  /// Parses `pattern` with the flags `g`, `i`, `m` and `y`.
+  /// Returns the reason if the pattern or the flags are invalid.
+  pub fn new(pattern: &str, flags: &str) -> Result<Regex, String> {
+    let mut parser = Parser {
+      chars: pattern.chars().collect(),
+      pos: 0,
+      group_count: 0,
+    };
+    let node = parser.parse_disjunction()?;
+    if parser.pos < parser.chars.len() {
+      return Err("Unmatched ')'".to_string());
+    }
+    let mut compiler = Compiler {
+      program: vec![],
+      register_count: 0,
+    };
+    compiler.compile(&node);
+    compiler.program.push(Inst::Match);
+    let mut regex = Regex {
+      program: compiler.program,
+      register_count: compiler.register_count,
+      group_count: parser.group_count + 1,
+      global: false,
+      ignore_case: false,
+      multiline: false,
+      sticky: false,
+    };
+    for c in flags.chars() {
+      let flag = match c {
+        'g' => &mut regex.global,
+        'i' => &mut regex.ignore_case,
+        'm' => &mut regex.multiline,
+        'y' => &mut regex.sticky,
+        _ => return Err(format!("Invalid flags '{}'", flags)),
+      };
+      if *flag {
+        return Err(format!("Invalid flags '{}'", flags));
+      }
+      *flag = true;
+    }
+    Ok(regex)
+  }
+
+  /// Finds the first match starting at or after `start`, or only at `start` if `sticky`.
+  pub fn search(&self, text: &[char], start: usize) -> Option<Captures> {
+    if start > text.len() {
+      return None;
+    }
+    let last = if self.sticky { start } else { text.len() };
+    let matcher = Matcher { regex: self, text };
+    let mut registers = vec![0; self.register_count];
+    for i in start..=last {
+      let mut caps = vec![None; self.group_count];
+      if let Some(j) = matcher.run(0, i, &mut caps, &mut registers) {
+        caps[0] = Some((i, j));
+        return Some(caps);
+      }
+    }
+    None
+  }
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize,
+  group_count: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).cloned()
+  }
+
+  fn next(&mut self) -> Option<char> {
+    let c = self.peek();
+    if c.is_some() {
+      self.pos += 1;
+    }
+    c
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    let found = self.peek() == Some(c);
+    if found {
+      self.pos += 1;
+    }
+    found
+  }
+
+  fn eat_str(&mut self, s: &str) -> bool {
+    let found = s
+      .chars()
+      .enumerate()
+      .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+    if found {
+      self.pos += s.chars().count();
+    }
+    found
+  }
+
+  /// https://tc39.github.io/ecma262/#prod-Disjunction
+  fn parse_disjunction(&mut self) -> Result<Node, String> {
+    let mut alternatives = vec![self.parse_alternative()?];
+    while self.eat('|') {
+      alternatives.push(self.parse_alternative()?);
+    }
+    Ok(if alternatives.len() == 1 {
+      alternatives.pop().unwrap()
+    } else {
+      Node::Alternation(alternatives)
+    })
+  }
+
+  fn parse_alternative(&mut self) -> Result<Node, String> {
+    let mut terms = vec![];
+    while let Some(c) = self.peek() {
+      if c == '|' || c == ')' {
+        break;
+      }
+      terms.push(self.parse_term()?);
+    }
+    Ok(Node::Sequence(terms))
+  }
+
+  /// https://tc39.github.io/ecma262/#prod-Term
+  fn parse_term(&mut self) -> Result<Node, String> {
+    let first_group = self.group_count + 1;
+    let atom = match self.next().unwrap() {
+      '^' => return Ok(Node::LineStart),
+      '$' => return Ok(Node::LineEnd),
+      '\\' if self.eat('b') => return Ok(Node::WordBoundary(false)),
+      '\\' if self.eat('B') => return Ok(Node::WordBoundary(true)),
+      '(' => {
+        let node = if self.eat_str("?=") {
+          Node::Lookahead(Box::new(self.parse_disjunction()?), false)
+        } else if self.eat_str("?!") {
+          Node::Lookahead(Box::new(self.parse_disjunction()?), true)
+        } else if self.eat_str("?:") {
+          Node::Group(Box::new(self.parse_disjunction()?), None)
+        } else {
+          self.group_count += 1;
+          let index = self.group_count;
+          Node::Group(Box::new(self.parse_disjunction()?), Some(index))
+        };
+        if !self.eat(')') {
+          return Err("Unterminated group".to_string());
+        }
+        node
+      }
+      '.' => Node::Any,
+      '[' => Node::Class(self.parse_class()?),
+      '\\' => self.parse_atom_escape()?,
+      '*' | '+' | '?' => return Err("Nothing to repeat".to_string()),
+      '{' => {
+        self.pos -= 1;
+        if self.parse_braces().is_some() {
+          return Err("Nothing to repeat".to_string());
+        }
+        self.pos += 1;
+        Node::Char('{')
+      }
+      c => Node::Char(c),
+    };
+    self.parse_quantifier(atom, first_group)
+  }
+
+  /// https://tc39.github.io/ecma262/#prod-Quantifier
+  fn parse_quantifier(&mut self, atom: Node, first_group: usize) -> Result<Node, String> {
+    let (min, max) = match self.peek() {
+      Some('{') => match self.parse_braces() {
+        Some(bounds) => bounds,
+        None => return Ok(atom),
+      },
+      Some(c) if "*+?".contains(c) => {
+        self.pos += 1;
+        match c {
+          '*' => (0, None),
+          '+' => (1, None),
+          _ => (0, Some(1)),
+        }
+      }
+      _ => return Ok(atom),
+    };
+    if matches!(max, Some(max) if max < min) {
+      return Err("numbers out of order in {} quantifier".to_string());
+    }
+    let greedy = !self.eat('?');
+    Ok(Node::Repeat(Repeat {
+      node: Box::new(atom),
+      min,
+      max,
+      greedy,
+      groups: first_group..self.group_count + 1,
+    }))
+  }
+
+  /// Parses `{n}`, `{n,}` or `{n,m}` at the current `{`.
+  /// Otherwise, returns `None` and keeps the position, since the `{` is then a literal.
+  fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+    let start = self.pos;
+    self.pos += 1;
+    let bounds = self.parse_decimal().and_then(|min| {
+      let max = if self.eat(',') {
+        self.parse_decimal()
+      } else {
+        Some(min)
+      };
+      if self.eat('}') {
+        Some((min, max))
+      } else {
+        None
+      }
+    });
+    if bounds.is_none() {
+      self.pos = start;
+    }
+    bounds
+  }
+
+  fn parse_decimal(&mut self) -> Option<usize> {
+    let start = self.pos;
+    while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    let digits: String = self.chars[start..self.pos].iter().collect();
+    digits.parse().ok()
+  }
+
+  fn parse_atom_escape(&mut self) -> Result<Node, String> {
+    if matches!(self.peek(), Some('1'..='9')) {
+      return Ok(Node::BackReference(self.parse_decimal().unwrap()));
+    }
+    Ok(match self.parse_class_escape()? {
+      ClassItem::Range(c, _) => Node::Char(c),
+      ClassItem::Class(class) => Node::Class(class),
+    })
+  }
+
+  /// Parses the escape after a `\`, which denotes a character or a character class.
+  fn parse_class_escape(&mut self) -> Result<ClassItem, String> {
+    let c = match self.next() {
+      Some(c) => c,
+      None => return Err("\\ at end of pattern".to_string()),
+    };
+    let c = match c {
+      'd' | 'D' | 'w' | 'W' | 's' | 'S' => return Ok(ClassItem::Class(Class::escape(c))),
+      'n' => '\n',
+      'r' => '\r',
+      't' => '\t',
+      'v' => '\x0b',
+      'f' => '\x0c',
+      '0' => '\0',
+      'c' => match self.peek() {
+        Some(letter) if letter.is_ascii_alphabetic() => {
+          self.pos += 1;
+          (letter as u8 % 32) as char
+        }
+        _ => c,
+      },
+      'x' => self.parse_hex(2).unwrap_or(c),
+      'u' => self.parse_hex(4).unwrap_or(c),
+      c => c,
+    };
+    Ok(ClassItem::Range(c, c))
+  }
+
+  /// Parses `len` hex digits as a character, keeping the position if they are not.
+  fn parse_hex(&mut self, len: usize) -> Option<char> {
+    let digits: String = self.chars.iter().skip(self.pos).take(len).collect();
+    if digits.chars().count() != len {
+      return None;
+    }
+    let c = u32::from_str_radix(&digits, 16)
+      .ok()
+      .and_then(::std::char::from_u32)?;
+    self.pos += len;
+    Some(c)
+  }
+
+  /// https://tc39.github.io/ecma262/#prod-CharacterClass
+  fn parse_class(&mut self) -> Result<Class, String> {
+    let negated = self.eat('^');
+    let mut items = vec![];
+    loop {
+      let item = match self.next() {
+        Some(']') => break,
+        Some(c) => self.parse_class_atom(c)?,
+        None => return Err("Unterminated character class".to_string()),
+      };
+      // `-` is a literal at either end of the class, or next to a class escape.
+      let is_range =
+        self.peek() == Some('-') && matches!(self.chars.get(self.pos + 1), Some(c) if *c != ']');
+      match item {
+        ClassItem::Range(from, _) if is_range => {
+          self.pos += 1;
+          let c = self.next().unwrap();
+          match self.parse_class_atom(c)? {
+            ClassItem::Range(to, _) if to < from => {
+              return Err("Range out of order in character class".to_string());
+            }
+            ClassItem::Range(to, _) => items.push(ClassItem::Range(from, to)),
+            class => {
+              items.push(item);
+              items.push(ClassItem::Range('-', '-'));
+              items.push(class);
+            }
+          }
+        }
+        item => items.push(item),
+      }
+    }
+    Ok(Class { items, negated })
+  }
+
+  fn parse_class_atom(&mut self, c: char) -> Result<ClassItem, String> {
+    match c {
+      '\\' if self.eat('b') => Ok(ClassItem::Range('\x08', '\x08')),
+      '\\' => self.parse_class_escape(),
+      c => Ok(ClassItem::Range(c, c)),
+    }
+  }
+}
+
+impl Class {
+  /// The class `\d`, `\w` or `\s`, or its complement for the uppercase letter.
+  fn escape(c: char) -> Class {
+    let ranges: &[(char, char)] = match c.to_ascii_lowercase() {
+      'd' => &[('0', '9')],
+      'w' => &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+      _ => &[
+        ('\t', '\r'),
+        (' ', ' '),
+        ('\u{a0}', '\u{a0}'),
+        ('\u{1680}', '\u{1680}'),
+        ('\u{2000}', '\u{200a}'),
+        ('\u{2028}', '\u{2029}'),
+        ('\u{202f}', '\u{202f}'),
+        ('\u{205f}', '\u{205f}'),
+        ('\u{3000}', '\u{3000}'),
+        ('\u{feff}', '\u{feff}'),
+      ],
+    };
+    Class {
+      items: ranges
+        .iter()
+        .map(|(from, to)| ClassItem::Range(*from, *to))
+        .collect(),
+      negated: c.is_ascii_uppercase(),
+    }
+  }
+
+  fn matches(&self, c: char, ignore_case: bool) -> bool {
+    let found = self.items.iter().any(|item| match item {
+      ClassItem::Range(from, to) => {
+        let in_range = |c: char| *from <= c && c <= *to;
+        in_range(c) || ignore_case && (in_range(to_upper(c)) || in_range(to_lower(c)))
+      }
+      ClassItem::Class(class) => class.matches(c, ignore_case),
+    });
+    found != self.negated
+  }
+}
+
+/// The uppercase of `c` if it is a single character, as `Canonicalize` in the specification.
+fn to_upper(c: char) -> char {
+  let mut upper = c.to_uppercase();
+  match (upper.next(), upper.next()) {
+    (Some(upper), None) => upper,
+    _ => c,
+  }
+}
+
+fn to_lower(c: char) -> char {
+  let mut lower = c.to_lowercase();
+  match (lower.next(), lower.next()) {
+    (Some(lower), None) => lower,
+    _ => c,
+  }
+}
+
+fn is_line_terminator(c: char) -> bool {
+  c == '\n' || c == '\r' || c == '\u{2028}' || c == '\u{2029}'
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+struct Compiler {
+  program: Vec<Inst>,
+  register_count: usize,
+}
+
+impl Compiler {
+  fn register(&mut self) -> usize {
+    self.register_count += 1;
+    self.register_count - 1
+  }
+
+  fn emit(&mut self, inst: Inst) -> usize {
+    self.program.push(inst);
+    self.program.len() - 1
+  }
+
+  /// Makes the instruction at `at` go to the next one emitted.
+  fn patch(&mut self, at: usize) {
+    let next = self.program.len();
+    match &mut self.program[at] {
+      Inst::Fork(target)
+      | Inst::Jump(target)
+      | Inst::Lookahead { end: target, .. }
+      | Inst::RepeatLoop { exit: target, .. } => *target = next,
+      _ => unreachable!(),
+    }
+  }
+
+  fn compile(&mut self, node: &Node) {
+    match node {
+      Node::Char(c) => {
+        self.emit(Inst::Char(*c));
+      }
+      Node::Any => {
+        self.emit(Inst::Any);
+      }
+      Node::Class(class) => {
+        self.emit(Inst::Class(class.clone()));
+      }
+      Node::LineStart => {
+        self.emit(Inst::LineStart);
+      }
+      Node::LineEnd => {
+        self.emit(Inst::LineEnd);
+      }
+      Node::WordBoundary(negated) => {
+        self.emit(Inst::WordBoundary(*negated));
+      }
+      Node::BackReference(index) => {
+        self.emit(Inst::BackReference(*index));
+      }
+      Node::Group(node, None) => self.compile(node),
+      Node::Group(node, Some(index)) => {
+        let register = self.register();
+        self.emit(Inst::GroupStart(register));
+        self.compile(node);
+        self.emit(Inst::GroupEnd {
+          index: *index,
+          register,
+        });
+      }
+      Node::Lookahead(node, negated) => {
+        let lookahead = self.emit(Inst::Lookahead {
+          negated: *negated,
+          end: 0,
+        });
+        self.compile(node);
+        self.emit(Inst::Match);
+        self.patch(lookahead);
+      }
+      Node::Repeat(repeat) => {
+        let count = self.register();
+        let start = self.register();
+        self.emit(Inst::RepeatInit(count));
+        let next = self.emit(Inst::RepeatLoop {
+          count,
+          min: repeat.min,
+          max: repeat.max,
+          greedy: repeat.greedy,
+          exit: 0,
+        });
+        self.emit(Inst::RepeatEnter {
+          start,
+          groups: repeat.groups.clone(),
+        });
+        self.compile(&repeat.node);
+        self.emit(Inst::RepeatEnd {
+          count,
+          start,
+          min: repeat.min,
+          next,
+        });
+        self.patch(next);
+      }
+      Node::Sequence(nodes) => {
+        for node in nodes {
+          self.compile(node);
+        }
+      }
+      Node::Alternation(alternatives) => {
+        let (last, alternatives) = alternatives.split_last().unwrap();
+        let mut jumps = vec![];
+        for alternative in alternatives {
+          let fork = self.emit(Inst::Fork(0));
+          self.compile(alternative);
+          jumps.push(self.emit(Inst::Jump(0)));
+          self.patch(fork);
+        }
+        self.compile(last);
+        for jump in jumps {
+          self.patch(jump);
+        }
+      }
+    }
+  }
+}
+
+/// An entry of the stack `Matcher::run` backtracks with.
+enum Backtrack {
+  /// Another way to go on matching, at an instruction and a position.
+  Branch(usize, usize),
+  /// The previous value of a capture, restored when backtracking past it.
+  Capture(usize, Option<(usize, usize)>),
+  /// The previous value of a register.
+  Register(usize, usize),
+  /// The captures before a positive lookahead replaced them.
+  Captures(Captures),
+}
+
+struct Matcher<'a> {
+  regex: &'a Regex,
+  text: &'a [char],
+}
+
+impl<'a> Matcher<'a> {
+  /// Runs the program from `pc` at `i` until `Match`, and returns the end of the match.
+  /// Only lookaheads call this recursively, so the depth does not grow with the input.
+  fn run(
+    &self,
+    mut pc: usize,
+    mut i: usize,
+    caps: &mut Captures,
+    registers: &mut [usize],
+  ) -> Option<usize> {
+    let text = self.text;
+    let mut stack = vec![];
+    loop {
+      let matched = match &self.regex.program[pc] {
+        Inst::Char(c) => match text.get(i) {
+          Some(d) if self.char_eq(*c, *d) => {
+            i += 1;
+            true
+          }
+          _ => false,
+        },
+        Inst::Any => match text.get(i) {
+          Some(c) if !is_line_terminator(*c) => {
+            i += 1;
+            true
+          }
+          _ => false,
+        },
+        Inst::Class(class) => match text.get(i) {
+          Some(c) if class.matches(*c, self.regex.ignore_case) => {
+            i += 1;
+            true
+          }
+          _ => false,
+        },
+        Inst::LineStart => i == 0 || self.regex.multiline && is_line_terminator(text[i - 1]),
+        Inst::LineEnd => i == text.len() || self.regex.multiline && is_line_terminator(text[i]),
+        Inst::WordBoundary(negated) => {
+          let after_word = i > 0 && is_word_char(text[i - 1]);
+          let before_word = i < text.len() && is_word_char(text[i]);
+          (after_word != before_word) != *negated
+        }
+        Inst::BackReference(index) => match caps.get(*index) {
+          Some(Some((start, end))) => {
+            let (start, len) = (*start, end - start);
+            let matched =
+              i + len <= text.len() && (0..len).all(|d| self.char_eq(text[start + d], text[i + d]));
+            i += len;
+            matched
+          }
+          // A group which has not matched refers to the empty string.
+          _ => true,
+        },
+        Inst::Fork(target) => {
+          stack.push(Backtrack::Branch(*target, i));
+          true
+        }
+        Inst::Jump(target) => {
+          pc = *target;
+          continue;
+        }
+        Inst::GroupStart(register) => {
+          set_register(&mut stack, registers, *register, i);
+          true
+        }
+        Inst::GroupEnd { index, register } => {
+          stack.push(Backtrack::Capture(*index, caps[*index]));
+          caps[*index] = Some((registers[*register], i));
+          true
+        }
+        Inst::Lookahead { negated, end } => {
+          let mut inner_caps = caps.clone();
+          let matched = self.run(pc + 1, i, &mut inner_caps, registers).is_some();
+          if matched != *negated {
+            // Captures in a positive lookahead are kept.
+            if !negated {
+              let saved = ::std::mem::replace(caps, inner_caps);
+              stack.push(Backtrack::Captures(saved));
+            }
+            pc = *end;
+            continue;
+          }
+          false
+        }
+        Inst::RepeatInit(count) => {
+          set_register(&mut stack, registers, *count, 0);
+          true
+        }
+        Inst::RepeatLoop {
+          count,
+          min,
+          max,
+          greedy,
+          exit,
+        } => {
+          let count = registers[*count];
+          if count < *min {
+            true
+          } else if Some(count) == *max {
+            pc = *exit;
+            continue;
+          } else if *greedy {
+            stack.push(Backtrack::Branch(*exit, i));
+            true
+          } else {
+            stack.push(Backtrack::Branch(pc + 1, i));
+            pc = *exit;
+            continue;
+          }
+        }
+        Inst::RepeatEnter { start, groups } => {
+          set_register(&mut stack, registers, *start, i);
+          for index in groups.clone() {
+            if caps[index].is_some() {
+              stack.push(Backtrack::Capture(index, caps[index]));
+              caps[index] = None;
+            }
+          }
+          true
+        }
+        Inst::RepeatEnd {
+          count,
+          start,
+          min,
+          next,
+        } => {
+          let n = registers[*count];
+          // An optional iteration matching the empty string would repeat forever, so it fails.
+          if n >= *min && i == registers[*start] {
+            false
+          } else {
+            set_register(&mut stack, registers, *count, n + 1);
+            pc = *next;
+            continue;
+          }
+        }
+        Inst::Match => return Some(i),
+      };
+      if matched {
+        pc += 1;
+        continue;
+      }
+      loop {
+        match stack.pop()? {
+          Backtrack::Branch(target, j) => {
+            pc = target;
+            i = j;
+            break;
+          }
+          Backtrack::Capture(index, cap) => caps[index] = cap,
+          Backtrack::Register(register, value) => registers[register] = value,
+          Backtrack::Captures(saved) => *caps = saved,
+        }
+      }
+    }
+  }
+
+  fn char_eq(&self, a: char, b: char) -> bool {
+    a == b || self.regex.ignore_case && to_upper(a) == to_upper(b)
+  }
+}
+
+/// Sets a register, recording its previous value to restore when backtracking.
+fn set_register(
+  stack: &mut Vec<Backtrack>,
+  registers: &mut [usize],
+  register: usize,
+  value: usize,
+) {
+  stack.push(Backtrack::Register(register, registers[register]));
+  registers[register] = value;
+}
@@ -0,0 +1,60 @@
+//! Hidden classes of ordinary objects.
+//!
+//! A `Shape` maps property names to slots. Objects that get the same properties in the same order
+//! share a shape, since adding a property follows a transition cached in the old shape.
+//! Inline caches (see `ic`) can thus tell the layout of an object by comparing shape pointers.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Shapes are never freed, so that pointers to them stay valid as cache keys.
+pub struct Shape {
+  /// Property names in slot order.
+  names: Vec<String>,
+  slots: HashMap<String, usize>,
+  transitions: RefCell<HashMap<String, *const Shape>>,
+}
+
+thread_local! {
+  static ROOT: *const Shape = Shape::alloc(vec![], HashMap::new());
+}
+
+impl Shape {
+  fn alloc(names: Vec<String>, slots: HashMap<String, usize>) -> *const Shape {
+    Box::into_raw(Box::new(Shape {
+      names,
+      slots,
+      transitions: RefCell::new(HashMap::new()),
+    }))
+  }
+
+  /// The shape of objects without properties.
+  pub fn root() -> *const Shape {
+    ROOT.with(|root| *root)
+  }
+
+  pub fn slot(&self, name: &str) -> Option<usize> {
+    self.slots.get(name).cloned()
+  }
+
+  pub fn names(&self) -> &[String] {
+    &self.names
+  }
+
+  /// The shape after adding the property `name`, which gets the next slot.
+  pub fn with_property(&self, name: &str) -> *const Shape {
+    if let Some(shape) = self.transitions.borrow().get(name) {
+      return *shape;
+    }
+    let mut names = self.names.clone();
+    let mut slots = self.slots.clone();
+    slots.insert(name.to_string(), names.len());
+    names.push(name.to_string());
+    let shape = Shape::alloc(names, slots);
+    self
+      .transitions
+      .borrow_mut()
+      .insert(name.to_string(), shape);
+    shape
+  }
+}
@@ -0,0 +1,497 @@
+use super::object::{
+  Closure, JsArray, JsObject, JsRegExp, JsString, ObjectHeader, ObjectKind, Property,
+};
+
+/// A NaN-boxed JavaScript value as seen by compiled code.
+///
+/// Doubles are stored as their IEEE-754 bit pattern, with every NaN canonicalized to a quiet NaN.
+/// Other values live in the remaining negative quiet-NaN space:
+/// the upper 16 bits hold the tag and the lower 48 bits hold the payload.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct BoxedValue(pub u64);
+
+const CANONICAL_NAN: u64 = 0x7ff8_0000_0000_0000;
+pub const TAG_MASK: u64 = 0xffff_0000_0000_0000;
+pub const PAYLOAD_MASK: u64 = 0x0000_ffff_ffff_ffff;
+
+pub const TAG_INT: u64 = 0xfff9_0000_0000_0000;
+pub const TAG_BOOL: u64 = 0xfffa_0000_0000_0000;
+pub const TAG_NULL: u64 = 0xfffb_0000_0000_0000;
+pub const TAG_UNDEFINED: u64 = 0xfffc_0000_0000_0000;
+pub const TAG_POINTER: u64 = 0xfffd_0000_0000_0000;
+
+/// Results of the `typeof` operator, indexed by the codes below.
+pub const TYPEOF_NAMES: [&str; 6] = [
+  "undefined", "object", "boolean", "number", "string", "function",
+];
+pub const TYPEOF_UNDEFINED: usize = 0;
+pub const TYPEOF_OBJECT: usize = 1;
+pub const TYPEOF_BOOLEAN: usize = 2;
+pub const TYPEOF_NUMBER: usize = 3;
+pub const TYPEOF_STRING: usize = 4;
+pub const TYPEOF_FUNCTION: usize = 5;
+
+pub const NULL: BoxedValue = BoxedValue(TAG_NULL);
+pub const UNDEFINED: BoxedValue = BoxedValue(TAG_UNDEFINED);
+pub const FALSE: BoxedValue = BoxedValue(TAG_BOOL);
+pub const TRUE: BoxedValue = BoxedValue(TAG_BOOL | 1);
+/// Not a JavaScript value. Runtime helpers return it to tell compiled code
+/// that it has to call an accessor function itself.
+pub const EMPTY: BoxedValue = BoxedValue(TAG_UNDEFINED | 1);
+/// Not a JavaScript value either. It fills the slot of a captured `let` or `const` binding
+/// in its TDZ (temporal dead zone), so that reading or writing it throws.
+pub const UNINITIALIZED: BoxedValue = BoxedValue(TAG_UNDEFINED | 2);
+
+impl BoxedValue {
+  pub fn from_f64(f: f64) -> BoxedValue {
+    if f.is_nan() {
+      BoxedValue(CANONICAL_NAN)
+    } else {
+      BoxedValue(f.to_bits())
+    }
+  }
+
+  pub fn from_i32(i: i32) -> BoxedValue {
+    BoxedValue(TAG_INT | (i as u32 as u64))
+  }
+
+  pub fn from_bool(b: bool) -> BoxedValue {
+    if b {
+      TRUE
+    } else {
+      FALSE
+    }
+  }
+
+  pub fn from_pointer<T>(p: *mut T) -> BoxedValue {
+    BoxedValue(TAG_POINTER | (p as u64 & PAYLOAD_MASK))
+  }
+
+  pub fn from_string(s: String) -> BoxedValue {
+    BoxedValue::from_pointer(JsString::new(s))
+  }
+
+  /// Stores a number as an integer when it is one, so that it prints without a fraction.
+  pub fn from_number(f: f64) -> BoxedValue {
+    if f.fract() == 0.0
+      && f >= ::std::i32::MIN as f64
+      && f <= ::std::i32::MAX as f64
+      && !(f == 0.0 && f.is_sign_negative())
+    {
+      BoxedValue::from_i32(f as i32)
+    } else {
+      BoxedValue::from_f64(f)
+    }
+  }
+
+  fn tag(self) -> u64 {
+    self.0 & TAG_MASK
+  }
+
+  pub fn is_f64(self) -> bool {
+    self.tag() < TAG_INT
+  }
+
+  pub fn is_i32(self) -> bool {
+    self.tag() == TAG_INT
+  }
+
+  pub fn is_number(self) -> bool {
+    self.is_f64() || self.is_i32()
+  }
+
+  pub fn is_bool(self) -> bool {
+    self.tag() == TAG_BOOL
+  }
+
+  pub fn is_null(self) -> bool {
+    self.0 == TAG_NULL
+  }
+
+  pub fn is_undefined(self) -> bool {
+    self.0 == TAG_UNDEFINED
+  }
+
+  pub fn is_pointer(self) -> bool {
+    self.tag() == TAG_POINTER
+  }
+
+  pub fn as_f64(self) -> f64 {
+    f64::from_bits(self.0)
+  }
+
+  pub fn as_i32(self) -> i32 {
+    self.0 as u32 as i32
+  }
+
+  pub fn as_bool(self) -> bool {
+    self.0 & 1 == 1
+  }
+
+  pub fn as_pointer<T>(self) -> *mut T {
+    (self.0 & PAYLOAD_MASK) as *mut T
+  }
+
+  pub fn object_kind(self) -> Option<ObjectKind> {
+    if self.is_pointer() {
+      Some(unsafe { (*self.as_pointer::<ObjectHeader>()).kind })
+    } else {
+      None
+    }
+  }
+
+  pub fn is_string(self) -> bool {
+    self.object_kind() == Some(ObjectKind::String)
+  }
+
+  /// The contents of a string value. `self` must be a string.
+  pub fn as_str<'a>(self) -> &'a str {
+    unsafe { &(*self.as_pointer::<JsString>()).value }
+  }
+
+  pub fn is_array(self) -> bool {
+    self.object_kind() == Some(ObjectKind::Array)
+  }
+
+  /// The elements of an array value. `self` must be an array.
+  pub fn as_elements<'a>(self) -> &'a mut Vec<BoxedValue> {
+    unsafe { &mut (*self.as_pointer::<JsArray>()).elements }
+  }
+
+  pub fn as_array<'a>(self) -> &'a mut JsArray {
+    unsafe { &mut *self.as_pointer::<JsArray>() }
+  }
+
+  pub fn is_object(self) -> bool {
+    self.object_kind() == Some(ObjectKind::Object)
+  }
+
+  /// Whether `self` is an ordinary object, a function or a regular expression,
+  /// all of which begin with a `JsObject`.
+  pub fn has_properties(self) -> bool {
+    match self.object_kind() {
+      Some(ObjectKind::Object) | Some(ObjectKind::Function) | Some(ObjectKind::RegExp) => true,
+      _ => false,
+    }
+  }
+
+  /// The `JsObject` `self` points to. `self` must have properties.
+  pub fn as_object<'a>(self) -> &'a mut JsObject {
+    unsafe { &mut *self.as_pointer::<JsObject>() }
+  }
+
+  /// The closure `self` points to. `self` must be a function.
+  pub fn as_closure<'a>(self) -> &'a mut Closure {
+    unsafe { &mut *self.as_pointer::<Closure>() }
+  }
+
+  pub fn is_regexp(self) -> bool {
+    self.object_kind() == Some(ObjectKind::RegExp)
+  }
+
+  /// The regular expression `self` points to. `self` must be a regular expression.
+  pub fn as_regexp<'a>(self) -> &'a mut JsRegExp {
+    unsafe { &mut *self.as_pointer::<JsRegExp>() }
+  }
+
+  /// The array index `self` denotes as a property key, if any.
+  pub fn to_array_index(self) -> Option<usize> {
+    if self.is_i32() {
+      let i = self.as_i32();
+      return if i >= 0 { Some(i as usize) } else { None };
+    }
+    let n = self.to_number();
+    if self.is_number() && n >= 0.0 && n.fract() == 0.0 && n < ::std::u32::MAX as f64 {
+      Some(n as usize)
+    } else {
+      None
+    }
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-tonumber
+  pub fn to_number(self) -> f64 {
+    if self.is_f64() {
+      self.as_f64()
+    } else if self.is_i32() {
+      self.as_i32() as f64
+    } else if self.is_bool() {
+      if self.as_bool() {
+        1.0
+      } else {
+        0.0
+      }
+    } else if self.is_null() {
+      0.0
+    } else if self.is_string() {
+      string_to_number(self.as_str())
+    } else {
+      ::std::f64::NAN
+    }
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-toint32
+  pub fn to_int32(self) -> i32 {
+    if self.is_i32() {
+      return self.as_i32();
+    }
+    f64_to_int32(self.to_number())
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-toboolean
+  pub fn to_boolean(self) -> bool {
+    if self.is_f64() {
+      let f = self.as_f64();
+      !(f == 0.0 || f.is_nan())
+    } else if self.is_i32() {
+      self.as_i32() != 0
+    } else if self.is_bool() {
+      self.as_bool()
+    } else if self.is_string() {
+      !self.as_str().is_empty()
+    } else {
+      !(self.is_null() || self.is_undefined())
+    }
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-typeof-operator
+  pub fn type_of(self) -> usize {
+    if self.is_number() {
+      TYPEOF_NUMBER
+    } else if self.is_bool() {
+      TYPEOF_BOOLEAN
+    } else if self.is_undefined() {
+      TYPEOF_UNDEFINED
+    } else if self.object_kind() == Some(ObjectKind::Function) {
+      TYPEOF_FUNCTION
+    } else if self.is_string() {
+      TYPEOF_STRING
+    } else {
+      TYPEOF_OBJECT
+    }
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-strict-equality-comparison
+  pub fn strict_equals(self, other: BoxedValue) -> bool {
+    if self.is_number() && other.is_number() {
+      return self.to_number() == other.to_number();
+    }
+    if self.is_string() && other.is_string() {
+      return self.as_str() == other.as_str();
+    }
+    self.0 == other.0
+  }
+
+  /// https://tc39.github.io/ecma262/#sec-abstract-equality-comparison
+  pub fn loose_equals(self, other: BoxedValue) -> bool {
+    if (self.is_null() || self.is_undefined()) && (other.is_null() || other.is_undefined()) {
+      return true;
+    }
+    if self.is_null() || self.is_undefined() || other.is_null() || other.is_undefined() {
+      return false;
+    }
+    if self.is_string() && other.is_string() {
+      return self.as_str() == other.as_str();
+    }
+    // Objects are compared by identity, while strings are compared with other values as numbers.
+    if (self.is_pointer() && !self.is_string()) || (other.is_pointer() && !other.is_string()) {
+      return self.0 == other.0;
+    }
+    self.to_number() == other.to_number()
+  }
+
+  pub fn to_string(self) -> String {
+    if self.is_i32() {
+      format!("{}", self.as_i32())
+    } else if self.is_f64() {
+      number_to_string(self.as_f64())
+    } else if self.is_bool() {
+      format!("{}", self.as_bool())
+    } else if self.is_null() {
+      "null".to_string()
+    } else if self.is_undefined() {
+      "undefined".to_string()
+    } else if self.object_kind() == Some(ObjectKind::Function) {
+      "[Function]".to_string()
+    } else if self.is_string() {
+      self.as_str().to_string()
+    } else if self.is_array() {
+      // https://tc39.github.io/ecma262/#sec-array.prototype.join
+      let elements: Vec<String> = self
+        .as_elements()
+        .iter()
+        .map(|e| {
+          if e.is_null() || e.is_undefined() {
+            "".to_string()
+          } else {
+            e.to_string()
+          }
+        })
+        .collect();
+      elements.join(",")
+    } else if self.is_regexp() {
+      let re = self.as_regexp();
+      format!("/{}/{}", re.source, re.flags)
+    } else {
+      "[object Object]".to_string()
+    }
+  }
+
+  /// The representation `console.log` prints, e.g. `[ 1, 'a' ]` for arrays.
+  pub fn inspect(self) -> String {
+    if self.object_kind() == Some(ObjectKind::Function) {
+      match self.as_closure().name.as_str() {
+        "" => "[Function]".to_string(),
+        name => format!("[Function: {}]", name),
+      }
+    } else if self.is_array() {
+      let elements = self.as_elements();
+      if elements.is_empty() {
+        return "[]".to_string();
+      }
+      let elements: Vec<String> = elements.iter().map(|e| e.inspect_nested()).collect();
+      format!("[ {} ]", elements.join(", "))
+    } else if self.is_object() {
+      let obj = self.as_object();
+      let prefix = match obj.proto.constructor_name() {
+        Some(name) => format!("{} ", name),
+        None => "".to_string(),
+      };
+      let properties: Vec<(&str, Property)> = obj
+        .properties()
+        .into_iter()
+        .filter(|(name, _)| obj.is_enumerable(name))
+        .collect();
+      if properties.is_empty() {
+        return format!("{}{{}}", prefix);
+      }
+      let properties: Vec<String> = properties
+        .into_iter()
+        .map(|(name, property)| {
+          let value = match property {
+            Property::Data(v) => v.inspect_nested(),
+            Property::Accessor { set, .. } if set.is_undefined() => "[Getter]".to_string(),
+            Property::Accessor { get, .. } if get.is_undefined() => "[Setter]".to_string(),
+            Property::Accessor { .. } => "[Getter/Setter]".to_string(),
+          };
+          format!("{}: {}", inspect_key(name), value)
+        })
+        .collect();
+      format!("{}{{ {} }}", prefix, properties.join(", "))
+    } else {
+      self.to_string()
+    }
+  }
+
+  /// The name of `constructor` of the prototype `self`, which `console.log` prints before objects.
+  fn constructor_name(self) -> Option<String> {
+    if !self.has_properties() {
+      return None;
+    }
+    match self.as_object().lookup("constructor") {
+      Some(Property::Data(ctor)) if ctor.object_kind() == Some(ObjectKind::Function) => {
+        match ctor.as_closure().name.as_str() {
+          "" | "Object" => None,
+          name => Some(name.to_string()),
+        }
+      }
+      _ => None,
+    }
+  }
+
+  /// Like `inspect`, but quotes strings, which appear inside arrays and objects.
+  fn inspect_nested(self) -> String {
+    if self.is_string() {
+      format!("'{}'", self.as_str())
+    } else {
+      self.inspect()
+    }
+  }
+}
+
+impl ::std::fmt::Debug for BoxedValue {
+  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    write!(f, "BoxedValue({:#x}: {})", self.0, self.to_string())
+  }
+}
+
+/// Quotes property names that are not identifiers, e.g. `'3.14'`.
+fn inspect_key(name: &str) -> String {
+  let is_identifier = name
+    .chars()
+    .enumerate()
+    .all(|(i, c)| c == '_' || c == '$' || c.is_alphabetic() || (i > 0 && c.is_numeric()));
+  if is_identifier && !name.is_empty() {
+    name.to_string()
+  } else {
+    format!("'{}'", name)
+  }
+}
+
+pub fn f64_to_int32(f: f64) -> i32 {
+  if f.is_nan() || f.is_infinite() {
+    return 0;
+  }
+  let i = f.trunc() % 4294967296.0;
+  let i = if i < 0.0 { i + 4294967296.0 } else { i };
+  i as u32 as i32
+}
+
+pub fn number_to_string(f: f64) -> String {
+  if f.is_nan() {
+    "NaN".to_string()
+  } else if f.is_infinite() {
+    if f > 0.0 {
+      "Infinity".to_string()
+    } else {
+      "-Infinity".to_string()
+    }
+  } else {
+    // `Display` for f64 already omits the fraction of integral values.
+    format!("{}", f)
+  }
+}
+
+/// https://tc39.github.io/ecma262/#sec-tonumber-applied-to-the-string-type
+pub fn string_to_number(s: &str) -> f64 {
+  let s = s.trim();
+  if s.is_empty() {
+    return 0.0;
+  }
+  let (sign, unsigned) = if s.starts_with('-') {
+    (-1.0, &s[1..])
+  } else if s.starts_with('+') {
+    (1.0, &s[1..])
+  } else {
+    (1.0, s)
+  };
+  if unsigned == "Infinity" {
+    return sign * ::std::f64::INFINITY;
+  }
+  // Only decimal literals may have a sign.
+  if unsigned.len() > 2 && unsigned.as_bytes()[0] == b'0' && unsigned == s {
+    let radix = match unsigned.as_bytes()[1] {
+      b'x' | b'X' => Some(16),
+      b'o' | b'O' => Some(8),
+      b'b' | b'B' => Some(2),
+      _ => None,
+    };
+    if let Some(radix) = radix {
+      return match u64::from_str_radix(&unsigned[2..], radix) {
+        Ok(n) => n as f64,
+        Err(_) => ::std::f64::NAN,
+      };
+    }
+  }
+  // Rust accepts spellings such as "inf" and "NaN" which JavaScript does not.
+  if !unsigned
+    .bytes()
+    .all(|c| c.is_ascii_digit() || c == b'.' || c == b'e' || c == b'E' || c == b'+' || c == b'-')
+  {
+    return ::std::f64::NAN;
+  }
+  match unsigned.parse::<f64>() {
+    Ok(n) => sign * n,
+    Err(_) => ::std::f64::NAN,
+  }
+}
@@ -0,0 +1,739 @@
+use crate::error::CompileError;
+use crate::node::{FormalParameter, Node, NodeBase, PropertyDefinition, UnaryOp, VarKind};
+use std::collections::HashMap;
+
+/// Resolves every variable reference in a script to its declaration.
+///
+/// `let` and `const` declarations in nested blocks are renamed to unique names (e.g. `i#1`),
+/// so that later passes can keep a single flat variable map per function.
+/// `var` declarations are hoisted to the function scope and keep their names.
+/// Redeclarations, references in the TDZ (temporal dead zone) and assignments to constants
+/// are reported as errors.
+///
+/// The script itself is treated as the function `main_name`.
+pub fn resolve_scopes(main_name: &str, node: &mut Node) -> Result<ScopeInfo, CompileError> {
+  let mut resolver = ScopeResolver {
+    scopes: vec![],
+    functions: vec![],
+    counter: 0,
+    info: ScopeInfo::default(),
+  };
+  resolver.resolve_function(main_name.to_string(), &mut vec![], node, false, vec![])?;
+  resolver.info.place_block_bindings();
+  Ok(resolver.info)
+}
+
+/// Full name of the function expression or arrow function at `pos` inside the function `parent`.
+pub fn function_expr_name(parent: &str, name: &Option<String>, pos: usize) -> String {
+  match name {
+    Some(name) => format!("{}.{}@{}", parent, name, pos),
+    None => format!("{}.<anonymous>@{}", parent, pos),
+  }
+}
+
+/// How the variables of a function are shared with the functions nested in it.
+#[derive(Clone, Debug, Default)]
+pub struct FunctionScope {
+  /// Full name of the enclosing function.
+  pub parent: Option<String>,
+  pub has_children: bool,
+  /// Variables declared in this function and referred to from nested functions.
+  /// They live in the environment record of the function, at their index plus one.
+  pub captured: Vec<String>,
+  /// Variables declared in blocks of this function and referred to only from function
+  /// expressions created in them, keyed by the position of the block.
+  /// Such a block gets an environment record of its own every time it is entered
+  /// (and for every iteration of a loop whose head declares them), at their index plus one.
+  pub blocks: HashMap<usize, Vec<String>>,
+  /// The blocks of the enclosing function with an environment record that this function
+  /// expression is created in, from the innermost one.
+  /// The function captures the record of the innermost one, whose slot 0 points to the next.
+  pub env_blocks: Vec<usize>,
+  /// The captured `let` and `const` bindings, whose slots hold `value::UNINITIALIZED` until
+  /// their declarations are evaluated. Nested functions may be called before that,
+  /// so their references to these bindings are checked at run time.
+  pub lexical: Vec<String>,
+  /// Variables of enclosing functions referred to in this function,
+  /// with the number of functions to go up to reach the declaring one.
+  pub outer: HashMap<String, usize>,
+  /// Whether the `arguments` object of this function is referred to.
+  /// If so, the function takes an array of its arguments as an extra last parameter.
+  pub uses_arguments: bool,
+}
+
+/// Scopes of every function in a script, keyed by the full function name (e.g. `main.fibo`).
+#[derive(Clone, Debug, Default)]
+pub struct ScopeInfo {
+  pub functions: HashMap<String, FunctionScope>,
+}
+
+impl ScopeInfo {
+  /// The scope of the function `name`.
+  /// Every function of the script has an entry, so a missing one is a bug in the compiler.
+  pub fn function(&self, name: &str) -> &FunctionScope {
+    match self.functions.get(name) {
+      Some(f) => f,
+      None => panic!("no scope information for function: {}", name),
+    }
+  }
+
+  /// Full name of the function that declares the variable `name` referred to in `func`.
+  pub fn owner(&self, func: &str, name: &str) -> String {
+    match self.function(func).outer.get(name) {
+      Some(distance) => ancestor(func, *distance).to_string(),
+      None => func.to_string(),
+    }
+  }
+
+  /// Whether the variable `name` of the function `func` is referred to from nested functions.
+  pub fn is_captured(&self, func: &str, name: &str) -> bool {
+    let scope = self.function(func);
+    scope
+      .captured
+      .iter()
+      .chain(scope.blocks.values().flatten())
+      .any(|captured| captured == name)
+  }
+
+  /// The environment record holding the variable `name` of the function `func`, as the position
+  /// of its block (`None` for the record of the function), and the index of the variable in it.
+  pub fn env_index(&self, func: &str, name: &str) -> Option<(Option<usize>, usize)> {
+    let scope = self.function(func);
+    if let Some(i) = scope.captured.iter().position(|captured| captured == name) {
+      return Some((None, i + 1));
+    }
+    scope.blocks.iter().find_map(|(block, names)| {
+      let i = names.iter().position(|captured| captured == name)?;
+      Some((Some(*block), i + 1))
+    })
+  }
+
+  /// The number of records to go up from the environment record captured by the function `func`
+  /// to reach the record of `block` (or of the whole function if `None`) in its ancestor `owner`.
+  pub fn env_distance(&self, func: &str, owner: &str, block: Option<usize>) -> usize {
+    let mut distance = 0;
+    let mut func = func;
+    loop {
+      let scope = self.function(func);
+      let parent = scope.parent.as_ref().unwrap();
+      for env_block in &scope.env_blocks {
+        if parent == owner && block == Some(*env_block) {
+          return distance;
+        }
+        distance += 1;
+      }
+      if parent == owner && block.is_none() {
+        return distance;
+      }
+      distance += 1;
+      func = parent.as_str();
+    }
+  }
+
+  /// Keeps a binding captured by a declared function in the record of the function, as declared
+  /// functions are created on entry to it, and drops the blocks left without captured bindings.
+  fn place_block_bindings(&mut self) {
+    for scope in self.functions.values_mut() {
+      let captured = &scope.captured;
+      for names in scope.blocks.values_mut() {
+        names.retain(|name| !captured.contains(name));
+      }
+      scope.blocks.retain(|_, names| !names.is_empty());
+    }
+    let blocks: HashMap<String, Vec<usize>> = self
+      .functions
+      .iter()
+      .map(|(name, scope)| (name.clone(), scope.blocks.keys().cloned().collect()))
+      .collect();
+    for scope in self.functions.values_mut() {
+      if let Some(parent) = &scope.parent {
+        scope.env_blocks.retain(|block| blocks[parent].contains(block));
+      }
+    }
+  }
+}
+
+/// The name a binding is declared with in the source, i.e. `name` without the suffix
+/// added by renaming.
+pub fn source_name(name: &str) -> &str {
+  name.split('#').next().unwrap()
+}
+
+/// The function `distance` levels up from the function named `func`.
+pub fn ancestor(func: &str, distance: usize) -> &str {
+  func.rsplitn(distance + 1, '.').last().unwrap()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BindingKind {
+  Var,
+  Let,
+  Const,
+  Param,
+  Function,
+  /// The implicit `arguments` object of a function.
+  Arguments,
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+  kind: BindingKind,
+  /// Name after renaming.
+  name: String,
+  /// False while the binding is in its TDZ.
+  initialized: bool,
+}
+
+#[derive(Debug)]
+struct Scope {
+  bindings: HashMap<String, Binding>,
+  is_function_scope: bool,
+  /// Index of the function this scope belongs to in `ScopeResolver::functions`.
+  function: usize,
+  /// Position of the statement whose environment record would hold the captured bindings of
+  /// this scope, or `None` for a function scope.
+  block: Option<usize>,
+}
+
+struct ScopeResolver {
+  scopes: Vec<Scope>,
+  /// Full names of the functions being resolved, from the outermost one.
+  functions: Vec<String>,
+  counter: usize,
+  info: ScopeInfo,
+}
+
+impl ScopeResolver {
+  /// `has_this` is false for arrow functions and the script,
+  /// which have no `this` or `arguments` of their own.
+  /// `env_blocks` are the blocks a function expression is created in (see `enclosing_blocks`).
+  fn resolve_function(
+    &mut self,
+    name: String,
+    params: &mut Vec<FormalParameter>,
+    body: &mut Node,
+    has_this: bool,
+    env_blocks: Vec<usize>,
+  ) -> Result<(), CompileError> {
+    let parent = self.functions.last().cloned();
+    if let Some(parent) = &parent {
+      self.info.functions.get_mut(parent).unwrap().has_children = true;
+    }
+    self.info.functions.insert(
+      name.clone(),
+      FunctionScope {
+        parent,
+        env_blocks,
+        ..FunctionScope::default()
+      },
+    );
+    self.functions.push(name);
+    let mut scope = Scope {
+      bindings: HashMap::default(),
+      is_function_scope: true,
+      function: self.functions.len() - 1,
+      block: None,
+    };
+    if has_this {
+      scope.declare(&"this".to_string(), "this".to_string(), BindingKind::Param);
+      let arguments = "arguments".to_string();
+      scope.declare(&arguments, arguments.clone(), BindingKind::Arguments);
+    }
+    for param in params.iter() {
+      scope.declare(&param.name, param.name.clone(), BindingKind::Param);
+    }
+    let mut vars = vec![];
+    hoist_var_decls(body, &mut vars);
+    for (name, kind) in vars {
+      if !scope.bindings.contains_key(&name) || kind == BindingKind::Function {
+        scope.declare(&name, name.clone(), kind);
+      }
+    }
+    self.scopes.push(scope);
+    // Default values are evaluated in the function, and may refer to the preceding parameters.
+    let result = params
+      .iter_mut()
+      .filter_map(|param| param.init.as_mut())
+      .try_for_each(|init| self.resolve(init))
+      .and_then(|_| self.resolve_scope_body(body));
+    self.scopes.pop();
+    self.functions.pop();
+    result
+  }
+
+  /// The blocks of the current function enclosing the code being resolved, from the innermost one.
+  fn enclosing_blocks(&self) -> Vec<usize> {
+    self
+      .scopes
+      .iter()
+      .rev()
+      .take_while(|scope| !scope.is_function_scope)
+      .filter_map(|scope| scope.block)
+      .collect()
+  }
+
+  /// Resolves `node` in a new scope that has just been pushed.
+  fn resolve_scope_body(&mut self, node: &mut Node) -> Result<(), CompileError> {
+    self.declare_lexical(node)?;
+    match &mut node.base {
+      NodeBase::Block(nodes) => {
+        for node in nodes {
+          self.resolve(node)?;
+        }
+        Ok(())
+      }
+      NodeBase::Switch(_, clauses) => {
+        for clause in clauses {
+          if let Some(test) = &mut clause.test {
+            self.resolve(test)?;
+          }
+          for node in &mut clause.body {
+            self.resolve(node)?;
+          }
+        }
+        Ok(())
+      }
+      _ => self.resolve(node),
+    }
+  }
+
+  /// Declares the `let` and `const` declarations directly contained in `node`.
+  fn declare_lexical(&mut self, node: &Node) -> Result<(), CompileError> {
+    match &node.base {
+      NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => {
+        self.declare_lexical_items(nodes)?;
+      }
+      NodeBase::Switch(_, clauses) => {
+        for clause in clauses {
+          self.declare_lexical_items(&clause.body)?;
+        }
+      }
+      NodeBase::For(init, _, _, _) => self.declare_lexical(init)?,
+      NodeBase::ForIn(binding, _, _) | NodeBase::ForOf(binding, _, _) => {
+        self.declare_lexical(binding)?
+      }
+      NodeBase::VarDecl(name, _, kind) if *kind != VarKind::Var => {
+        self.declare_lexical_name(name, kind, node.pos)?
+      }
+      NodeBase::ForBinding(target, kind) if *kind != VarKind::Var => {
+        for name in target.bound_names() {
+          self.declare_lexical_name(name, kind, node.pos)?;
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  fn declare_lexical_name(
+    &mut self,
+    name: &String,
+    kind: &VarKind,
+    pos: usize,
+  ) -> Result<(), CompileError> {
+    let scope = self.scopes.last_mut().unwrap();
+    if scope.bindings.contains_key(name) {
+      return Err(CompileError::syntax_error(already_declared(name), pos));
+    }
+    let new_name = if scope.is_function_scope {
+      name.clone()
+    } else {
+      self.counter += 1;
+      format!("{}#{}", name, self.counter)
+    };
+    let kind = match kind {
+      VarKind::Const => BindingKind::Const,
+      _ => BindingKind::Let,
+    };
+    scope.declare(name, new_name, kind);
+    Ok(())
+  }
+
+  /// Declares the `let` and `const` declarations among the items of a block or case clause.
+  fn declare_lexical_items(&mut self, nodes: &[Node]) -> Result<(), CompileError> {
+    for node in nodes {
+      match node.base {
+        NodeBase::StatementList(_) | NodeBase::For(_, _, _, _) => self.declare_lexical(node)?,
+        NodeBase::ForIn(_, _, _) | NodeBase::ForOf(_, _, _) => self.declare_lexical(node)?,
+        NodeBase::VarDecl(_, _, _) => self.declare_lexical(node)?,
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn resolve(&mut self, node: &mut Node) -> Result<(), CompileError> {
+    let pos = node.pos;
+    match &mut node.base {
+      NodeBase::StatementList(nodes) => {
+        for node in nodes {
+          self.resolve(node)?;
+        }
+      }
+      NodeBase::Block(_) => {
+        self.scopes.push(Scope {
+          bindings: HashMap::default(),
+          is_function_scope: false,
+          function: self.functions.len() - 1,
+          block: Some(pos),
+        });
+        let result = self.resolve_scope_body(node);
+        self.scopes.pop();
+        result?;
+      }
+      NodeBase::Switch(discriminant, _) => {
+        // The discriminant is evaluated outside the block shared by all the clauses.
+        self.resolve(discriminant)?;
+        self.scopes.push(Scope {
+          bindings: HashMap::default(),
+          is_function_scope: false,
+          function: self.functions.len() - 1,
+          block: Some(pos),
+        });
+        let result = self.resolve_scope_body(node);
+        self.scopes.pop();
+        result?;
+      }
+      NodeBase::FunctionDecl(name, params, body) => {
+        // Declared functions are created on entry to the enclosing function.
+        let name = format!("{}.{}", self.functions.last().unwrap(), name);
+        self.resolve_function(name, params, body, true, vec![])?;
+      }
+      NodeBase::FunctionExpr(name, params, body) => {
+        let name = function_expr_name(self.functions.last().unwrap(), name, pos);
+        let env_blocks = self.enclosing_blocks();
+        self.resolve_function(name, params, body, true, env_blocks)?;
+      }
+      NodeBase::ArrowFunction(params, body) => {
+        let name = function_expr_name(self.functions.last().unwrap(), &None, pos);
+        let env_blocks = self.enclosing_blocks();
+        self.resolve_function(name, params, body, false, env_blocks)?;
+      }
+      // `this` is resolved like a variable, so that arrow functions capture it.
+      // Outside of any function it is `undefined`, as in a module.
+      NodeBase::This => {
+        node.base = match self.lookup(&"this".to_string(), pos)? {
+          Some(name) => NodeBase::Identifier(name),
+          None => NodeBase::Identifier("undefined".to_string()),
+        };
+      }
+      NodeBase::VarDecl(name, init, kind) => {
+        match init {
+          Some(init) => self.resolve(init)?,
+          None if *kind == VarKind::Const => {
+            return Err(CompileError::syntax_error(
+              "Missing initializer in const declaration",
+              pos,
+            ));
+          }
+          None => {}
+        }
+        match kind {
+          VarKind::Var => self.check_var_hoistable(name, pos)?,
+          VarKind::Let | VarKind::Const => {
+            let binding = self
+              .scopes
+              .last_mut()
+              .unwrap()
+              .bindings
+              .get_mut(name)
+              .unwrap();
+            binding.initialized = true;
+            *name = binding.name.clone();
+          }
+        }
+      }
+      NodeBase::Identifier(name) => {
+        if let Some(new_name) = self.lookup(name, pos)? {
+          *name = new_name;
+        }
+      }
+      NodeBase::Assign(lhs, rhs) => {
+        self.resolve(rhs)?;
+        self.check_assignable(lhs)?;
+        self.resolve(lhs)?;
+      }
+      NodeBase::UnaryOp(expr, op) => {
+        match op {
+          UnaryOp::PrInc | UnaryOp::PrDec | UnaryOp::PoInc | UnaryOp::PoDec => {
+            self.check_assignable(expr)?
+          }
+          _ => {}
+        }
+        self.resolve(expr)?;
+      }
+      NodeBase::Object(properties) => {
+        for property in properties.iter_mut() {
+          match property {
+            PropertyDefinition::IdentifierReference(name) => {
+              if let Some(new_name) = self.lookup(name, pos)? {
+                let value = Node::new(NodeBase::Identifier(new_name), pos);
+                *property = PropertyDefinition::Property(name.clone(), value);
+              }
+            }
+            PropertyDefinition::Property(_, value) => self.resolve(value)?,
+            PropertyDefinition::MethodDefinition(_, _, func) => self.resolve(func)?,
+          }
+        }
+      }
+      NodeBase::Member(expr, _)
+      | NodeBase::New(expr)
+      | NodeBase::Throw(expr)
+      | NodeBase::Label(_, expr)
+      | NodeBase::Return(Some(expr)) => self.resolve(expr)?,
+      NodeBase::Index(node1, node2)
+      | NodeBase::While(node1, node2)
+      | NodeBase::DoWhile(node1, node2)
+      | NodeBase::BinaryOp(node1, node2, _) => {
+        self.resolve(node1)?;
+        self.resolve(node2)?;
+      }
+      NodeBase::If(node1, node2, node3) | NodeBase::TernaryOp(node1, node2, node3) => {
+        self.resolve(node1)?;
+        self.resolve(node2)?;
+        self.resolve(node3)?;
+      }
+      NodeBase::For(init, cond, step, body) => {
+        self.resolve(init)?;
+        self.resolve(cond)?;
+        self.resolve(step)?;
+        self.resolve(body)?;
+      }
+      NodeBase::ForIn(binding, target, body) | NodeBase::ForOf(binding, target, body) => {
+        // The object is evaluated while the lexical bindings of the head are in their TDZ.
+        self.resolve(target)?;
+        match &mut binding.base {
+          NodeBase::ForBinding(pattern, VarKind::Var) => {
+            for name in pattern.bound_names() {
+              self.check_var_hoistable(name, binding.pos)?;
+            }
+            self.resolve(pattern)?;
+          }
+          NodeBase::ForBinding(pattern, _) => {
+            let scope = self.scopes.last_mut().unwrap();
+            for name in pattern.bound_names() {
+              scope.bindings.get_mut(name).unwrap().initialized = true;
+            }
+            self.resolve(pattern)?;
+          }
+          _ => {
+            self.check_assignable(binding)?;
+            self.resolve(binding)?;
+          }
+        }
+        self.resolve(body)?;
+      }
+      NodeBase::ArrayPattern(elements) => {
+        for element in elements.iter_mut().flatten() {
+          if let Some(init) = &mut element.init {
+            self.resolve(init)?;
+          }
+          self.resolve(&mut element.target)?;
+        }
+      }
+      NodeBase::ObjectPattern(properties) => {
+        for (_, element) in properties {
+          if let Some(init) = &mut element.init {
+            self.resolve(init)?;
+          }
+          self.resolve(&mut element.target)?;
+        }
+      }
+      NodeBase::Try(try_, catch, param, finally) => {
+        self.resolve(try_)?;
+        // The catch parameter is declared in the scope of the catch block,
+        // and is initialized when the block is entered, like a parameter.
+        if let NodeBase::Identifier(name) = &mut param.base {
+          self.counter += 1;
+          let new_name = format!("{}#{}", name, self.counter);
+          let mut scope = Scope {
+            bindings: HashMap::default(),
+            is_function_scope: false,
+            function: self.functions.len() - 1,
+            block: Some(pos),
+          };
+          scope.declare(name, new_name.clone(), BindingKind::Param);
+          *name = new_name;
+          self.scopes.push(scope);
+          let result = self.resolve_scope_body(catch);
+          self.scopes.pop();
+          result?;
+        }
+        self.resolve(finally)?;
+      }
+      NodeBase::Call(callee, args) => {
+        self.resolve(callee)?;
+        for arg in args {
+          self.resolve(arg)?;
+        }
+      }
+      NodeBase::Array(elems) | NodeBase::TemplateLiteral(_, elems) => {
+        for elem in elems {
+          self.resolve(elem)?;
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// Returns the new name of the binding `name` refers to, or `None` if it is not a variable.
+  /// A variable of an enclosing function is recorded as captured, in the block declaring it
+  /// if the reference is in a function expression created in the block.
+  fn lookup(&mut self, name: &String, pos: usize) -> Result<Option<String>, CompileError> {
+    let mut crossed_function = false;
+    let mut found = None;
+    for scope in self.scopes.iter().rev() {
+      if let Some(binding) = scope.bindings.get(name) {
+        // A function may be called after the declaration has been evaluated.
+        if !binding.initialized && !crossed_function {
+          return Err(CompileError::reference_error(
+            format!("Cannot access '{}' before initialization", name),
+            pos,
+          ));
+        }
+        if binding.kind != BindingKind::Function {
+          found = Some((binding.name.clone(), scope.function, binding.kind, scope.block));
+        }
+        break;
+      }
+      crossed_function |= scope.is_function_scope;
+    }
+    let (new_name, owner, kind, block) = match found {
+      Some(found) => found,
+      None => return Ok(None),
+    };
+    if kind == BindingKind::Arguments {
+      self.info.functions.get_mut(&self.functions[owner]).unwrap().uses_arguments = true;
+    }
+    let current = self.functions.len() - 1;
+    if owner < current {
+      // The function nested in the owner which contains the reference.
+      let child = self.info.function(&self.functions[owner + 1]);
+      let in_block = block.filter(|block| child.env_blocks.contains(block));
+      let owner_scope = self.info.functions.get_mut(&self.functions[owner]).unwrap();
+      let captured = match in_block {
+        Some(block) => owner_scope.blocks.entry(block).or_default(),
+        None => &mut owner_scope.captured,
+      };
+      if !captured.contains(&new_name) {
+        captured.push(new_name.clone());
+      }
+      let is_lexical = kind == BindingKind::Let || kind == BindingKind::Const;
+      if is_lexical && !owner_scope.lexical.contains(&new_name) {
+        owner_scope.lexical.push(new_name.clone());
+      }
+      let current_scope = self.info.functions.get_mut(&self.functions[current]).unwrap();
+      current_scope.outer.insert(new_name.clone(), current - owner);
+    }
+    Ok(Some(new_name))
+  }
+
+  fn check_assignable(&self, node: &Node) -> Result<(), CompileError> {
+    match &node.base {
+      NodeBase::Identifier(name) => {
+        for scope in self.scopes.iter().rev() {
+          if let Some(binding) = scope.bindings.get(name) {
+            if binding.kind == BindingKind::Const {
+              return Err(CompileError::type_error("Assignment to constant variable.", node.pos));
+            }
+            break;
+          }
+        }
+      }
+      NodeBase::ArrayPattern(elements) => {
+        for element in elements.iter().flatten() {
+          self.check_assignable(&element.target)?;
+        }
+      }
+      NodeBase::ObjectPattern(properties) => {
+        for (_, element) in properties {
+          self.check_assignable(&element.target)?;
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// A `var` must not be hoisted across a lexical declaration of the same name.
+  fn check_var_hoistable(&self, name: &String, pos: usize) -> Result<(), CompileError> {
+    for scope in self.scopes.iter().rev() {
+      match scope.bindings.get(name) {
+        Some(binding) if binding.kind == BindingKind::Let || binding.kind == BindingKind::Const => {
+          return Err(CompileError::syntax_error(already_declared(name), pos));
+        }
+        _ => {}
+      }
+      if scope.is_function_scope {
+        break;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Scope {
+  fn declare(&mut self, name: &String, new_name: String, kind: BindingKind) {
+    let initialized = match kind {
+      BindingKind::Let | BindingKind::Const => false,
+      _ => true,
+    };
+    self.bindings.insert(
+      name.clone(),
+      Binding {
+        kind,
+        name: new_name,
+        initialized,
+      },
+    );
+  }
+}
+
+fn already_declared(name: &String) -> String {
+  format!("Identifier '{}' has already been declared", name)
+}
+
+/// Collects the `var` and function declarations hoisted to the scope of the function `node`.
+fn hoist_var_decls(node: &Node, vars: &mut Vec<(String, BindingKind)>) {
+  match &node.base {
+    NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => {
+      for node in nodes {
+        hoist_var_decls(node, vars);
+      }
+    }
+    NodeBase::VarDecl(name, _, VarKind::Var) => vars.push((name.clone(), BindingKind::Var)),
+    NodeBase::FunctionDecl(name, _, _) => vars.push((name.clone(), BindingKind::Function)),
+    NodeBase::If(_, then_, else_) => {
+      hoist_var_decls(then_, vars);
+      hoist_var_decls(else_, vars);
+    }
+    NodeBase::While(_, body) | NodeBase::DoWhile(body, _) | NodeBase::Label(_, body) => {
+      hoist_var_decls(body, vars)
+    }
+    NodeBase::For(init, _, _, body)
+    | NodeBase::ForIn(init, _, body)
+    | NodeBase::ForOf(init, _, body) => {
+      hoist_var_decls(init, vars);
+      hoist_var_decls(body, vars);
+    }
+    NodeBase::ForBinding(target, VarKind::Var) => {
+      for name in target.bound_names() {
+        vars.push((name.clone(), BindingKind::Var));
+      }
+    }
+    NodeBase::Switch(_, clauses) => {
+      for clause in clauses {
+        for node in &clause.body {
+          hoist_var_decls(node, vars);
+        }
+      }
+    }
+    NodeBase::Try(try_, catch, _, finally) => {
+      hoist_var_decls(try_, vars);
+      hoist_var_decls(catch, vars);
+      hoist_var_decls(finally, vars);
+    }
+    _ => {}
+  }
+}
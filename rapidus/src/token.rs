@@ -15,11 +15,23 @@ pub enum Kind {
     Identifier(String),
     Number(f64),
     String(String),
+    Template(TemplatePart, String, String), // Part, cooked string, raw string
+    RegExp(String, String),                 // Pattern, flags
     Symbol(Symbol),
     LineTerminator,
     EOF,
 }
 
+/// A piece of a template literal, delimited by backquotes and substitutions.
+/// https://tc39.github.io/ecma262/#sec-template-literal-lexical-components
+#[derive(Clone, Debug, PartialEq, Copy)]
+pub enum TemplatePart {
+    NoSubstitution, // `...`
+    Head,           // `...${
+    Middle,         // }...${
+    Tail,           // }...`
+}
+
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum Keyword {
     Abstract,
@@ -145,6 +157,22 @@ impl Token {
         }
     }
 
+    pub fn new_template(part: TemplatePart, cooked: String, raw: String, pos: usize) -> Token {
+        Token {
+            kind: Kind::Template(part, cooked, raw),
+            pos: pos,
+            prev_pos: 0,
+        }
+    }
+
+    pub fn new_regexp(pattern: String, flags: String, pos: usize) -> Token {
+        Token {
+            kind: Kind::RegExp(pattern, flags),
+            pos: pos,
+            prev_pos: 0,
+        }
+    }
+
     pub fn new_symbol(symbol: Symbol, pos: usize) -> Token {
         Token {
             kind: Kind::Symbol(symbol),
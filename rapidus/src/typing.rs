@@ -0,0 +1,1209 @@
+use crate::node::{
+  BinOp, FormalParameter, Node, NodeBase, PatternElement, PropertyDefinition, UnaryOp,
+};
+use crate::scope::{self, ScopeInfo};
+use cilk::ir::types;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::mem;
+
+/// A static type inferred for a JavaScript value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ty {
+  /// No value has been observed yet.
+  Unknown,
+  Bool,
+  /// A number which provably stays an integer in the 32-bit range, such as an integer literal or
+  /// the result of a bitwise operator. `+`, `-`, `*` and `%` yield an `Int32` only if the ranges
+  /// of their operands keep the result in range and away from -0 (see `Range`), and `F64` otherwise.
+  Int32,
+  F64,
+  /// Any value, represented as a NaN-boxed `runtime::value::BoxedValue`.
+  Dynamic,
+}
+
+impl Default for Ty {
+  fn default() -> Ty {
+    Ty::Unknown
+  }
+}
+
+impl Ty {
+  /// The least type that can hold values of both `self` and `other`.
+  pub fn join(self, other: Ty) -> Ty {
+    match (self, other) {
+      (Ty::Unknown, ty) | (ty, Ty::Unknown) => ty,
+      (lhs, rhs) if lhs == rhs => lhs,
+      (Ty::Int32, Ty::F64) | (Ty::F64, Ty::Int32) => Ty::F64,
+      _ => Ty::Dynamic,
+    }
+  }
+
+  pub fn of_number(x: f64) -> Ty {
+    if x.fract() == 0.0
+      && x >= ::std::i32::MIN as f64
+      && x <= ::std::i32::MAX as f64
+      && !(x == 0.0 && x.is_sign_negative())
+    {
+      Ty::Int32
+    } else {
+      Ty::F64
+    }
+  }
+
+  pub fn to_cilk_type(self) -> types::Type {
+    match self {
+      Ty::Bool => types::Type::Int1,
+      Ty::Unknown | Ty::Int32 => types::Type::Int32,
+      Ty::F64 => types::Type::F64,
+      Ty::Dynamic => types::Type::Int64,
+    }
+  }
+}
+
+/// The values an `Int32` may take, which tell whether arithmetic on it stays an `Int32`.
+/// Bounds are `i64`, so that the range of a result can be computed before it is known to fit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Range {
+  pub lo: i64,
+  pub hi: i64,
+}
+
+impl Default for Range {
+  fn default() -> Range {
+    Range::EMPTY
+  }
+}
+
+impl Range {
+  /// No value, e.g. of a parameter no `Int32` has been passed to yet, or in unreachable code.
+  pub const EMPTY: Range = Range {
+    lo: ::std::i64::MAX,
+    hi: ::std::i64::MIN,
+  };
+  pub const INT32: Range = Range {
+    lo: ::std::i32::MIN as i64,
+    hi: ::std::i32::MAX as i64,
+  };
+
+  pub fn new(lo: i64, hi: i64) -> Range {
+    Range { lo, hi }
+  }
+
+  pub fn is_empty(self) -> bool {
+    self.lo > self.hi
+  }
+
+  pub fn contains(self, x: i64) -> bool {
+    self.lo <= x && x <= self.hi
+  }
+
+  pub fn join(self, other: Range) -> Range {
+    Range::new(self.lo.min(other.lo), self.hi.max(other.hi))
+  }
+
+  pub fn meet(self, other: Range) -> Range {
+    let range = Range::new(self.lo.max(other.lo), self.hi.min(other.hi));
+    if range.is_empty() {
+      Range::EMPTY
+    } else {
+      range
+    }
+  }
+
+  /// `None` if some value in the range does not fit in an `Int32`.
+  fn checked(self) -> Option<Range> {
+    if self.lo >= Range::INT32.lo && self.hi <= Range::INT32.hi {
+      Some(self)
+    } else {
+      None
+    }
+  }
+
+  pub fn add(self, other: Range) -> Option<Range> {
+    if self.is_empty() || other.is_empty() {
+      return Some(Range::EMPTY);
+    }
+    Range::new(self.lo + other.lo, self.hi + other.hi).checked()
+  }
+
+  pub fn sub(self, other: Range) -> Option<Range> {
+    if self.is_empty() || other.is_empty() {
+      return Some(Range::EMPTY);
+    }
+    Range::new(self.lo - other.hi, self.hi - other.lo).checked()
+  }
+
+  /// Multiplying 0 by a negative number yields -0, which an `Int32` cannot hold.
+  pub fn mul(self, other: Range) -> Option<Range> {
+    if self.is_empty() || other.is_empty() {
+      return Some(Range::EMPTY);
+    }
+    if (self.contains(0) && other.lo < 0) || (other.contains(0) && self.lo < 0) {
+      return None;
+    }
+    let products = [
+      self.lo * other.lo,
+      self.lo * other.hi,
+      self.hi * other.lo,
+      self.hi * other.hi,
+    ];
+    let lo = *products.iter().min().unwrap();
+    let hi = *products.iter().max().unwrap();
+    Range::new(lo, hi).checked()
+  }
+
+  /// A remainder has the sign of the dividend, so only a dividend that is not negative keeps
+  /// the result away from -0. A divisor of 0 yields NaN (and traps in integer arithmetic).
+  pub fn rem(self, divisor: Range) -> Option<Range> {
+    if self.is_empty() || divisor.is_empty() {
+      return Some(Range::EMPTY);
+    }
+    if self.lo < 0 || divisor.contains(0) {
+      return None;
+    }
+    let max = divisor.lo.abs().max(divisor.hi.abs()) - 1;
+    Some(Range::new(0, self.hi.min(max)))
+  }
+
+  /// Negating 0 yields -0, and negating the least `Int32` leaves the range.
+  pub fn neg(self) -> Option<Range> {
+    if self.is_empty() {
+      return Some(Range::EMPTY);
+    }
+    if self.contains(0) {
+      return None;
+    }
+    Range::new(-self.hi, -self.lo).checked()
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FunctionTypes {
+  pub params: Vec<Ty>,
+  /// Whether the last parameter is a rest parameter, which takes an array of the remaining
+  /// arguments.
+  pub has_rest_param: bool,
+  pub ret: Ty,
+  pub vars: HashMap<String, Ty>,
+  /// Ranges of the parameters, the return value and the variables, meaningful for `Int32`s.
+  pub param_ranges: Vec<Range>,
+  pub ret_range: Range,
+  pub var_ranges: HashMap<String, Range>,
+  /// Full names of the functions declared directly in this function.
+  pub children: Vec<String>,
+  /// Whether the function is used as a value, so that it may be called through the value.
+  /// Such a function takes and returns dynamically typed values only.
+  pub is_value: bool,
+}
+
+impl FunctionTypes {
+  pub fn param_types(&self) -> Vec<types::Type> {
+    self.params.iter().map(|ty| ty.to_cilk_type()).collect()
+  }
+}
+
+/// Types of every function in a script, keyed by the full function name (e.g. `main.fibo`).
+#[derive(Clone, Debug, Default)]
+pub struct TypeInfo {
+  pub functions: HashMap<String, FunctionTypes>,
+  /// Positions of the binary `+`, `-`, `*` and `%` whose result provably is an `Int32`.
+  /// The others yield an `F64`, though `+` and `-` on `Int32`s are still done on integers
+  /// unless they overflow.
+  pub int32_binary_ops: HashSet<usize>,
+  /// Likewise for unary `-`, `++` and `--`, which may share a position with a binary operator,
+  /// as in `a + b++`.
+  pub int32_unary_ops: HashSet<usize>,
+}
+
+impl TypeInfo {
+  /// The types of the function `name`.
+  /// Every function of the script has an entry, so a missing one is a bug in the compiler.
+  pub fn function(&self, name: &str) -> &FunctionTypes {
+    match self.functions.get(name) {
+      Some(f) => f,
+      None => panic!("no type information for function: {}", name),
+    }
+  }
+
+  /// Functions used as values, in the order of their ids in function values.
+  pub fn function_values(&self) -> Vec<&String> {
+    let mut names: Vec<&String> = self
+      .functions
+      .iter()
+      .filter(|(_, f)| f.is_value)
+      .map(|(name, _)| name)
+      .collect();
+    names.sort();
+    names
+  }
+
+  /// Resolves a function `name` called inside the function `caller`.
+  /// Functions declared in `caller` or in its ancestors are visible, as are the ancestors themselves.
+  pub fn resolve_function(&self, caller: &str, name: &str) -> Option<String> {
+    let mut path: Vec<&str> = caller.split('.').collect();
+    while !path.is_empty() {
+      let scope = path.join(".");
+      let candidate = format!("{}.{}", scope, name);
+      if self.functions.contains_key(&candidate) {
+        return Some(candidate);
+      }
+      if *path.last().unwrap() == name && self.functions.contains_key(&scope) {
+        return Some(scope);
+      }
+      path.pop();
+    }
+    None
+  }
+}
+
+/// Infers the type of each local, parameter and return value by iterating to a fixed point.
+/// A variable captured by nested functions has a single type, recorded in the declaring function.
+///
+/// The ranges of `Int32`s are inferred alongside, refined by the conditions guarding the code
+/// (e.g. `x` is at least 3 in the `else` branch of `if (x <= 2)`), so that arithmetic which
+/// provably stays in range is done on `Int32`s.
+pub struct TypeInference<'a> {
+  info: TypeInfo,
+  scope_info: &'a ScopeInfo,
+  changed: bool,
+  /// Bounds on local `Int32` variables implied by the conditions enclosing the node being inferred.
+  /// A bound is cleared once the variable may have been assigned since the condition held.
+  facts: Vec<Option<(String, Range)>>,
+  /// Integers around the literals of the script, to which growing ranges are widened so that
+  /// inference terminates. They are collected in the first round, which does not widen.
+  thresholds: BTreeSet<i64>,
+  widening: bool,
+}
+
+impl<'a> TypeInference<'a> {
+  pub fn new(scope_info: &'a ScopeInfo) -> Self {
+    let mut thresholds = BTreeSet::new();
+    thresholds.insert(Range::INT32.lo);
+    thresholds.insert(0);
+    thresholds.insert(Range::INT32.hi);
+    TypeInference {
+      info: TypeInfo::default(),
+      scope_info,
+      changed: false,
+      facts: vec![],
+      thresholds,
+      widening: false,
+    }
+  }
+
+  pub fn run(mut self, main_name: &str, node: &Node) -> TypeInfo {
+    self.declare_function(main_name, &vec![], node);
+    loop {
+      self.changed = false;
+      self.info.int32_binary_ops.clear();
+      self.info.int32_unary_ops.clear();
+      self.infer_function(main_name, node);
+      if !self.changed {
+        break;
+      }
+      self.widening = true;
+    }
+    // The host reads the result of `main` as a double, so whatever it returns is converted.
+    self.info.functions.get_mut(main_name).unwrap().ret = Ty::F64;
+    self.info
+  }
+
+  fn declare_function(&mut self, name: &str, params: &Vec<FormalParameter>, body: &Node) {
+    let mut types = FunctionTypes::default();
+    types.params = vec![Ty::Unknown; params.len()];
+    types.param_ranges = vec![Range::EMPTY; params.len()];
+    if params.last().map_or(false, |param| param.is_rest_param) {
+      types.has_rest_param = true;
+      types.params[params.len() - 1] = Ty::Dynamic;
+    }
+    let mut decls = vec![];
+    collect_function_decls(body, &mut decls);
+    for (child_name, child_params, child_body) in decls {
+      let full_name = format!("{}.{}", name, child_name);
+      self.declare_function(&full_name, child_params, child_body);
+      types.children.push(full_name);
+    }
+    self.info.functions.insert(name.to_string(), types);
+  }
+
+  /// Declares a function expression or an arrow function when it is first visited.
+  fn declare_function_value(&mut self, name: &str, params: &Vec<FormalParameter>, body: &Node) {
+    if !self.info.functions.contains_key(name) {
+      self.declare_function(name, params, body);
+      self.changed = true;
+    }
+    self.mark_function_value(name);
+  }
+
+  fn mark_function_value(&mut self, name: &str) {
+    let types = self.info.functions.get_mut(name).unwrap();
+    if !types.is_value {
+      types.is_value = true;
+      self.changed = true;
+    }
+    for i in 0..types.params.len() {
+      self.update_param(name, i, Ty::Dynamic);
+    }
+    self.update_ret(name, Ty::Dynamic);
+  }
+
+  fn infer_function_body(
+    &mut self,
+    name: &str,
+    params: &Vec<FormalParameter>,
+    body: &Node,
+    has_this: bool,
+  ) {
+    // Conditions in the enclosing function say nothing about when this one is called.
+    let facts = mem::replace(&mut self.facts, vec![]);
+    if has_this {
+      self.update_var(name, &"this".to_string(), Ty::Dynamic);
+    }
+    if self.scope_info.function(name).uses_arguments {
+      self.update_var(name, &"arguments".to_string(), Ty::Dynamic);
+    }
+    for (i, param) in params.iter().enumerate() {
+      let ty = self.info.function(name).params[i];
+      let range = self.info.function(name).param_ranges[i];
+      self.update_var(name, &param.name, ty);
+      self.update_var_range(name, &param.name, range);
+      // The default value replaces an `undefined` argument.
+      if let Some(init) = &param.init {
+        let ty = self.infer(name, init);
+        let range = self.value_range(name, init);
+        self.update_var(name, &param.name, ty);
+        self.update_var_range(name, &param.name, range);
+      }
+    }
+    self.infer_function(name, body);
+    self.facts = facts;
+  }
+
+  fn infer_function(&mut self, name: &str, body: &Node) {
+    self.infer(name, body);
+    // Falling off the end of a function returns `undefined`.
+    if can_complete(body) {
+      self.update_ret(name, Ty::Dynamic);
+    }
+  }
+
+  fn infer(&mut self, func: &str, node: &Node) -> Ty {
+    match &node.base {
+      NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => {
+        for node in nodes {
+          self.infer(func, node);
+        }
+        Ty::Unknown
+      }
+      NodeBase::FunctionDecl(name, params, body) => {
+        let full_name = format!("{}.{}", func, name);
+        self.infer_function_body(&full_name, params, body, true);
+        Ty::Unknown
+      }
+      NodeBase::FunctionExpr(name, params, body) => {
+        let full_name = scope::function_expr_name(func, name, node.pos);
+        self.declare_function_value(&full_name, params, body);
+        self.infer_function_body(&full_name, params, body, true);
+        Ty::Dynamic
+      }
+      NodeBase::ArrowFunction(params, body) => {
+        let full_name = scope::function_expr_name(func, &None, node.pos);
+        self.declare_function_value(&full_name, params, body);
+        self.infer_function_body(&full_name, params, body, false);
+        Ty::Dynamic
+      }
+      NodeBase::VarDecl(name, init, _kind) => {
+        let (ty, range) = match init {
+          Some(init) => (self.infer(func, init), self.value_range(func, init)),
+          None => (Ty::Dynamic, Range::INT32),
+        };
+        self.update_var(func, name, ty);
+        self.update_var_range(func, name, range);
+        Ty::Unknown
+      }
+      NodeBase::Assign(lhs, rhs) => match &lhs.base {
+        NodeBase::Identifier(name) => {
+          let ty = self.infer(func, rhs);
+          let range = self.value_range(func, rhs);
+          self.update_var(func, name, ty);
+          self.update_var_range(func, name, range);
+          self.var_type(func, lhs)
+        }
+        _ => {
+          // The target is evaluated before the value, and a pattern assigns to variables.
+          self.kill_assigned_facts(node);
+          let ty = self.infer(func, rhs);
+          self.infer(func, lhs);
+          ty
+        }
+      },
+      NodeBase::If(cond, then_, else_) => {
+        self.infer(func, cond);
+        let depth = self.facts.len();
+        let then_facts = self.condition_facts(func, cond, true);
+        let else_facts = self.condition_facts(func, cond, false);
+        self.facts.extend(then_facts);
+        self.infer(func, then_);
+        self.facts.truncate(depth);
+        self.facts.extend(else_facts);
+        self.infer(func, else_);
+        self.facts.truncate(depth);
+        Ty::Unknown
+      }
+      NodeBase::TernaryOp(cond, then_, else_) => {
+        self.infer(func, cond);
+        let then_ty = self.infer(func, then_);
+        let else_ty = self.infer(func, else_);
+        then_ty.join(else_ty)
+      }
+      NodeBase::Label(_, body) => self.infer(func, body),
+      // Loops are entered again after their bodies, so the facts about variables they assign to
+      // are cleared first.
+      NodeBase::While(cond, body) => {
+        self.kill_assigned_facts(node);
+        self.infer(func, cond);
+        let depth = self.facts.len();
+        let facts = self.condition_facts(func, cond, true);
+        self.facts.extend(facts);
+        self.infer(func, body);
+        self.facts.truncate(depth);
+        Ty::Unknown
+      }
+      NodeBase::DoWhile(body, cond) => {
+        self.kill_assigned_facts(node);
+        self.infer(func, body);
+        self.infer(func, cond);
+        Ty::Unknown
+      }
+      NodeBase::For(init, cond, step, body) => {
+        self.infer(func, init);
+        self.kill_assigned_facts(node);
+        self.infer(func, cond);
+        let depth = self.facts.len();
+        let facts = self.condition_facts(func, cond, true);
+        self.facts.extend(facts);
+        // The step runs after the body, so it keeps the facts the body leaves.
+        self.infer(func, body);
+        self.infer(func, step);
+        self.facts.truncate(depth);
+        Ty::Unknown
+      }
+      NodeBase::ForIn(binding, target, body) | NodeBase::ForOf(binding, target, body) => {
+        self.kill_assigned_facts(node);
+        self.infer(func, target);
+        self.infer_binding(func, binding);
+        self.infer(func, body);
+        Ty::Unknown
+      }
+      NodeBase::Switch(discriminant, clauses) => {
+        self.infer(func, discriminant);
+        // The `default` clause runs after all the tests are evaluated, wherever it is.
+        for test in clauses.iter().filter_map(|clause| clause.test.as_ref()) {
+          self.kill_assigned_facts(test);
+        }
+        for clause in clauses {
+          if let Some(test) = &clause.test {
+            self.infer(func, test);
+          }
+          for node in &clause.body {
+            self.infer(func, node);
+          }
+        }
+        Ty::Unknown
+      }
+      NodeBase::Try(try_, catch, param, finally) => {
+        self.infer(func, try_);
+        // Any value can be thrown.
+        if let NodeBase::Identifier(name) = &param.base {
+          self.update_var(func, name, Ty::Dynamic);
+        }
+        self.infer(func, catch);
+        self.infer(func, finally);
+        Ty::Unknown
+      }
+      NodeBase::Throw(expr) => {
+        self.infer(func, expr);
+        Ty::Unknown
+      }
+      NodeBase::Return(ret) => {
+        let (ty, range) = match ret {
+          Some(ret) => (self.infer(func, ret), self.value_range(func, ret)),
+          None => (Ty::Dynamic, Range::INT32),
+        };
+        self.update_ret(func, ty);
+        self.update_ret_range(func, range);
+        Ty::Unknown
+      }
+      NodeBase::BinaryOp(lhs, rhs, op) => {
+        let lhs_ty = self.infer(func, lhs);
+        let rhs_ty = self.infer(func, rhs);
+        match op {
+          BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Rem
+            if self.int_range(func, node).is_some() =>
+          {
+            self.info.int32_binary_ops.insert(node.pos);
+            Ty::Int32
+          }
+          BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Rem
+            if may_become_int32(lhs_ty, rhs_ty) =>
+          {
+            Ty::Unknown
+          }
+          op => binop_type(op, lhs_ty, rhs_ty),
+        }
+      }
+      NodeBase::UnaryOp(expr, op) => {
+        let ty = self.infer(func, expr);
+        match op {
+          UnaryOp::PrInc | UnaryOp::PrDec | UnaryOp::PoInc | UnaryOp::PoDec => match &expr.base {
+            NodeBase::Identifier(_) if ty == Ty::Unknown => Ty::Unknown,
+            NodeBase::Identifier(name) => {
+              let delta = match op {
+                UnaryOp::PrInc | UnaryOp::PoInc => Range::new(1, 1),
+                _ => Range::new(-1, -1),
+              };
+              match self.int_range(func, expr).and_then(|range| range.add(delta)) {
+                Some(range) => {
+                  self.info.int32_unary_ops.insert(node.pos);
+                  self.update_var(func, name, Ty::Int32);
+                  self.update_var_range(func, name, range);
+                  Ty::Int32
+                }
+                None => {
+                  self.update_var(func, name, Ty::F64);
+                  Ty::F64
+                }
+              }
+            }
+            _ => Ty::F64,
+          },
+          UnaryOp::Plus => number_type(ty),
+          UnaryOp::Minus if self.int_range(func, node).is_some() => {
+            self.info.int32_unary_ops.insert(node.pos);
+            Ty::Int32
+          }
+          UnaryOp::Minus if may_become_int32(ty, Ty::Int32) => Ty::Unknown,
+          UnaryOp::Minus => Ty::F64,
+          UnaryOp::Not | UnaryOp::Delete => Ty::Bool,
+          UnaryOp::BitwiseNot => Ty::Int32,
+          UnaryOp::Void => Ty::Dynamic,
+          UnaryOp::Typeof => Ty::Dynamic,
+        }
+      }
+      NodeBase::Call(callee, args) => {
+        // The callee is evaluated before the arguments.
+        self.kill_assigned_facts(callee);
+        let arg_tys: Vec<Ty> = args.iter().map(|arg| self.infer(func, arg)).collect();
+        let name = match &callee.base {
+          NodeBase::Identifier(name) if !self.is_declared(func, name) => name,
+          NodeBase::Member(obj, member) => {
+            if obj.base == NodeBase::Identifier("console".to_string()) && member == "log" {
+              return Ty::Unknown;
+            }
+            self.infer(func, obj);
+            return Ty::Dynamic;
+          }
+          // Called through a function value.
+          _ => {
+            self.infer(func, callee);
+            return Ty::Dynamic;
+          }
+        };
+        let callee_name = match self.info.resolve_function(func, name) {
+          Some(callee_name) => callee_name,
+          None => return Ty::Unknown,
+        };
+        let types = self.info.function(&callee_name);
+        let fixed_params = types.params.len() - types.has_rest_param as usize;
+        for i in 0..fixed_params {
+          // Missing arguments are `undefined`.
+          let ty = arg_tys.get(i).cloned().unwrap_or(Ty::Dynamic);
+          let range = match args.get(i) {
+            Some(arg) => self.value_range(func, arg),
+            None => Range::INT32,
+          };
+          self.update_param(&callee_name, i, ty);
+          self.update_param_range(&callee_name, i, range);
+        }
+        self.info.function(&callee_name).ret
+      }
+      // The constructor is called through its value.
+      NodeBase::New(expr) => {
+        match &expr.base {
+          NodeBase::Call(callee, args) => {
+            self.kill_assigned_facts(callee);
+            for arg in args {
+              self.infer(func, arg);
+            }
+            self.infer(func, callee);
+          }
+          _ => {
+            self.infer(func, expr);
+          }
+        }
+        Ty::Dynamic
+      }
+      NodeBase::Identifier(name) if name == "undefined" && !self.is_declared(func, name) => {
+        Ty::Dynamic
+      }
+      NodeBase::Identifier(name) if !self.is_declared(func, name) => {
+        match self.info.resolve_function(func, name) {
+          Some(name) => {
+            self.mark_function_value(&name);
+            Ty::Dynamic
+          }
+          None => Ty::Unknown,
+        }
+      }
+      NodeBase::Identifier(_) => self.var_type(func, node),
+      NodeBase::Member(obj, _) => {
+        self.infer(func, obj);
+        Ty::Dynamic
+      }
+      NodeBase::Index(obj, key) => {
+        self.infer(func, obj);
+        self.infer(func, key);
+        Ty::Dynamic
+      }
+      NodeBase::Array(elems) | NodeBase::TemplateLiteral(_, elems) => {
+        for elem in elems {
+          self.infer(func, elem);
+        }
+        Ty::Dynamic
+      }
+      NodeBase::Object(properties) => {
+        for property in properties {
+          match property {
+            PropertyDefinition::IdentifierReference(name) => {
+              self.infer(func, &Node::new(NodeBase::Identifier(name.clone()), node.pos));
+            }
+            PropertyDefinition::Property(_, value)
+            | PropertyDefinition::MethodDefinition(_, _, value) => {
+              self.infer(func, value);
+            }
+          }
+        }
+        Ty::Dynamic
+      }
+      NodeBase::Number(x) => {
+        if Ty::of_number(*x) == Ty::Int32 {
+          let x = *x as i64;
+          let thresholds = [x - 1, x, x + 1, -x - 1, -x, -x + 1];
+          let thresholds = thresholds.iter().filter(|x| Range::INT32.contains(**x));
+          self.thresholds.extend(thresholds);
+        }
+        Ty::of_number(*x)
+      }
+      NodeBase::Boolean(_) => Ty::Bool,
+      NodeBase::Null
+      | NodeBase::String(_)
+      | NodeBase::TemplateStrings(_, _)
+      | NodeBase::RegExp(_, _) => Ty::Dynamic,
+      _ => Ty::Unknown,
+    }
+  }
+
+  /// Infers the variables bound by a for-in/of head, which may be assigned any value.
+  fn infer_binding(&mut self, func: &str, node: &Node) {
+    match &node.base {
+      NodeBase::Identifier(name) => self.update_var(func, name, Ty::Dynamic),
+      NodeBase::ForBinding(target, _) => self.infer_binding(func, target),
+      NodeBase::ArrayPattern(elements) => {
+        for element in elements.iter().flatten() {
+          if let Some(init) = &element.init {
+            self.infer(func, init);
+          }
+          self.infer_binding(func, &element.target);
+        }
+      }
+      NodeBase::ObjectPattern(properties) => {
+        for (_, element) in properties {
+          if let Some(init) = &element.init {
+            self.infer(func, init);
+          }
+          self.infer_binding(func, &element.target);
+        }
+      }
+      _ => {
+        self.infer(func, node);
+      }
+    }
+  }
+
+  fn is_declared(&self, func: &str, name: &String) -> bool {
+    let owner = self.scope_info.owner(func, name);
+    self.info.function(&owner).vars.contains_key(name)
+  }
+
+  fn var_type(&self, func: &str, node: &Node) -> Ty {
+    match &node.base {
+      NodeBase::Identifier(name) => self
+        .info
+        .function(&self.scope_info.owner(func, name))
+        .vars
+        .get(name)
+        .cloned()
+        .unwrap_or(Ty::Unknown),
+      _ => Ty::Unknown,
+    }
+  }
+
+  /// The range of `node` if it provably is an `Int32`. Arithmetic is an `Int32` only if its
+  /// result stays in range, which `infer` records for the compiler.
+  fn int_range(&mut self, func: &str, node: &Node) -> Option<Range> {
+    match &node.base {
+      NodeBase::Number(x) if Ty::of_number(*x) == Ty::Int32 => {
+        Some(Range::new(*x as i64, *x as i64))
+      }
+      NodeBase::Identifier(name) if self.var_type(func, node) == Ty::Int32 => {
+        let mut range = self.var_range(func, name);
+        for (var, bound) in self.facts.iter().flatten() {
+          if var == name {
+            range = range.meet(*bound);
+          }
+        }
+        Some(range)
+      }
+      NodeBase::Assign(lhs, _) => match &lhs.base {
+        NodeBase::Identifier(name) if self.var_type(func, lhs) == Ty::Int32 => {
+          Some(self.var_range(func, name))
+        }
+        _ => None,
+      },
+      NodeBase::BinaryOp(lhs, rhs, op) => match op {
+        BinOp::Add => self.int_range(func, lhs)?.add(self.int_range(func, rhs)?),
+        BinOp::Sub => self.int_range(func, lhs)?.sub(self.int_range(func, rhs)?),
+        BinOp::Mul => self.int_range(func, lhs)?.mul(self.int_range(func, rhs)?),
+        BinOp::Rem => self.int_range(func, lhs)?.rem(self.int_range(func, rhs)?),
+        BinOp::And | BinOp::Or | BinOp::Xor | BinOp::Shl | BinOp::Shr => Some(Range::INT32),
+        BinOp::Comma => self.int_range(func, rhs),
+        _ => None,
+      },
+      NodeBase::UnaryOp(expr, op) => match op {
+        UnaryOp::Minus => match expr.base {
+          NodeBase::Number(x) if Ty::of_number(-x) == Ty::Int32 => {
+            Some(Range::new(-x as i64, -x as i64))
+          }
+          _ => self.int_range(func, expr)?.neg(),
+        },
+        UnaryOp::Plus => self.int_range(func, expr),
+        UnaryOp::BitwiseNot => Some(Range::INT32),
+        UnaryOp::PrInc | UnaryOp::PrDec | UnaryOp::PoInc | UnaryOp::PoDec => {
+          let old = match expr.base {
+            NodeBase::Identifier(_) => self.int_range(func, expr)?,
+            _ => return None,
+          };
+          let new = match op {
+            UnaryOp::PrInc | UnaryOp::PoInc => old.add(Range::new(1, 1))?,
+            _ => old.sub(Range::new(1, 1))?,
+          };
+          match op {
+            UnaryOp::PrInc | UnaryOp::PrDec => Some(new),
+            _ => Some(old),
+          }
+        }
+        _ => None,
+      },
+      NodeBase::TernaryOp(_, then_, else_) => {
+        let then_range = self.int_range(func, then_)?;
+        Some(then_range.join(self.int_range(func, else_)?))
+      }
+      NodeBase::Call(callee, _) => {
+        let name = match &callee.base {
+          NodeBase::Identifier(name) if !self.is_declared(func, name) => name,
+          _ => return None,
+        };
+        let callee_name = self.info.resolve_function(func, name)?;
+        let types = self.info.function(&callee_name);
+        if types.ret != Ty::Int32 {
+          return None;
+        }
+        Some(types.ret_range)
+      }
+      _ => None,
+    }
+  }
+
+  /// The range recorded for the value `node` when it is assigned: any `Int32`, if it is not
+  /// provably an `Int32`.
+  fn value_range(&mut self, func: &str, node: &Node) -> Range {
+    self.int_range(func, node).unwrap_or(Range::INT32)
+  }
+
+  fn var_range(&self, func: &str, name: &String) -> Range {
+    let owner = self.scope_info.owner(func, name);
+    let ranges = &self.info.function(&owner).var_ranges;
+    ranges.get(name).cloned().unwrap_or(Range::INT32)
+  }
+
+  /// Bounds on variables that hold where `cond` has evaluated to `truth`.
+  fn condition_facts(
+    &mut self,
+    func: &str,
+    cond: &Node,
+    truth: bool,
+  ) -> Vec<Option<(String, Range)>> {
+    let (lhs, rhs, op) = match &cond.base {
+      NodeBase::UnaryOp(expr, UnaryOp::Not) => return self.condition_facts(func, expr, !truth),
+      NodeBase::BinaryOp(lhs, rhs, BinOp::LAnd) if truth => {
+        let mut facts = self.condition_facts(func, lhs, truth);
+        facts.extend(self.condition_facts(func, rhs, truth));
+        return facts;
+      }
+      NodeBase::BinaryOp(lhs, rhs, BinOp::LOr) if !truth => {
+        let mut facts = self.condition_facts(func, lhs, truth);
+        facts.extend(self.condition_facts(func, rhs, truth));
+        return facts;
+      }
+      NodeBase::BinaryOp(lhs, rhs, op) => (lhs, rhs, op),
+      _ => return vec![],
+    };
+    // Facts are only drawn from comparisons of `Int32`s, which are never NaN,
+    // so a comparison that does not hold is the opposite one.
+    let (op, mirrored_op) = match (op, truth) {
+      (BinOp::Lt, true) | (BinOp::Ge, false) => (BinOp::Lt, BinOp::Gt),
+      (BinOp::Le, true) | (BinOp::Gt, false) => (BinOp::Le, BinOp::Ge),
+      (BinOp::Gt, true) | (BinOp::Le, false) => (BinOp::Gt, BinOp::Lt),
+      (BinOp::Ge, true) | (BinOp::Lt, false) => (BinOp::Ge, BinOp::Le),
+      _ => return vec![],
+    };
+    let mut facts = vec![];
+    facts.extend(self.comparison_fact(func, lhs, &op, rhs));
+    facts.extend(self.comparison_fact(func, rhs, &mirrored_op, lhs));
+    facts
+      .into_iter()
+      .filter(|(name, _)| !assigns(cond, name))
+      .map(Some)
+      .collect()
+  }
+
+  /// A bound on the variable in `target` implied by `target op other`, where `target` is a local
+  /// variable or the square of one, as in `k * k <= n`.
+  fn comparison_fact(
+    &mut self,
+    func: &str,
+    target: &Node,
+    op: &BinOp,
+    other: &Node,
+  ) -> Option<(String, Range)> {
+    let (var, is_square) = match &target.base {
+      NodeBase::Identifier(_) => (target, false),
+      NodeBase::BinaryOp(lhs, rhs, BinOp::Mul) if lhs.base == rhs.base => (&**lhs, true),
+      _ => return None,
+    };
+    let name = match &var.base {
+      NodeBase::Identifier(name) => name,
+      _ => return None,
+    };
+    // Nested functions may assign to captured variables at any call.
+    let scope = self.scope_info.function(func);
+    if scope.outer.contains_key(name) || self.scope_info.is_captured(func, name) {
+      return None;
+    }
+    if self.var_type(func, var) != Ty::Int32 {
+      return None;
+    }
+    let other = self.int_range(func, other)?;
+    if other.is_empty() {
+      return None;
+    }
+    let bound = match (op, is_square) {
+      (BinOp::Lt, false) => Range::new(Range::INT32.lo, other.hi - 1),
+      (BinOp::Le, false) => Range::new(Range::INT32.lo, other.hi),
+      (BinOp::Gt, false) => Range::new(other.lo + 1, Range::INT32.hi),
+      (BinOp::Ge, false) => Range::new(other.lo, Range::INT32.hi),
+      (BinOp::Lt, true) | (BinOp::Le, true) => {
+        let max = if *op == BinOp::Lt { other.hi - 1 } else { other.hi };
+        if max < 0 {
+          Range::EMPTY
+        } else {
+          let root = isqrt(max);
+          Range::new(-root, root)
+        }
+      }
+      _ => return None,
+    };
+    Some((name.clone(), bound))
+  }
+
+  /// Clears the facts about the variables `node` may assign to. This is needed where code
+  /// is inferred before an assignment that may run first, e.g. at the start of a loop.
+  fn kill_assigned_facts(&mut self, node: &Node) {
+    for fact in self.facts.iter_mut() {
+      if fact.as_ref().map_or(false, |(name, _)| assigns(node, name)) {
+        *fact = None;
+      }
+    }
+  }
+
+  /// Joins `range` into the range `old`. From the second round on, a bound that grows is widened
+  /// to the next threshold, so that a loop like `for (i = 0; i < 1000; i++)` takes a few rounds
+  /// rather than one per iteration.
+  fn widen(&self, old: Range, range: Range) -> Range {
+    let new = old.join(range);
+    if !self.widening || old.is_empty() || new == old {
+      return new;
+    }
+    let lo = if new.lo < old.lo {
+      *self.thresholds.range(..=new.lo).next_back().unwrap_or(&new.lo)
+    } else {
+      new.lo
+    };
+    let hi = if new.hi > old.hi {
+      *self.thresholds.range(new.hi..).next().unwrap_or(&new.hi)
+    } else {
+      new.hi
+    };
+    Range::new(lo, hi)
+  }
+
+  fn update_var_range(&mut self, func: &str, name: &String, range: Range) {
+    let owner = self.scope_info.owner(func, name);
+    let old = self.info.function(&owner).var_ranges.get(name).cloned();
+    let new = self.widen(old.unwrap_or(Range::EMPTY), range);
+    if old != Some(new) {
+      let types = self.info.functions.get_mut(&owner).unwrap();
+      types.var_ranges.insert(name.clone(), new);
+      self.changed = true;
+    }
+  }
+
+  fn update_param_range(&mut self, func: &str, index: usize, range: Range) {
+    let old = match self.info.function(func).param_ranges.get(index) {
+      Some(old) => *old,
+      None => return,
+    };
+    let new = self.widen(old, range);
+    if old != new {
+      self.info.functions.get_mut(func).unwrap().param_ranges[index] = new;
+      self.changed = true;
+    }
+  }
+
+  fn update_ret_range(&mut self, func: &str, range: Range) {
+    let old = self.info.function(func).ret_range;
+    let new = self.widen(old, range);
+    if old != new {
+      self.info.functions.get_mut(func).unwrap().ret_range = new;
+      self.changed = true;
+    }
+  }
+
+  /// Also clears the facts about the variable, which no longer hold after the assignment.
+  fn update_var(&mut self, func: &str, name: &String, ty: Ty) {
+    for fact in self.facts.iter_mut() {
+      if fact.as_ref().map_or(false, |(var, _)| var == name) {
+        *fact = None;
+      }
+    }
+    let owner = self.scope_info.owner(func, name);
+    let types = self.info.functions.get_mut(&owner).unwrap();
+    let old = types.vars.get(name).cloned().unwrap_or(Ty::Unknown);
+    let new = old.join(ty);
+    if old != new || !types.vars.contains_key(name) {
+      types.vars.insert(name.clone(), new);
+      self.changed = true;
+    }
+  }
+
+  fn update_param(&mut self, func: &str, index: usize, ty: Ty) {
+    let types = self.info.functions.get_mut(func).unwrap();
+    if index >= types.params.len() {
+      return;
+    }
+    let new = types.params[index].join(ty);
+    if types.params[index] != new {
+      types.params[index] = new;
+      self.changed = true;
+    }
+  }
+
+  fn update_ret(&mut self, func: &str, ty: Ty) {
+    let types = self.info.functions.get_mut(func).unwrap();
+    let new = types.ret.join(ty);
+    if types.ret != new {
+      types.ret = new;
+      self.changed = true;
+    }
+  }
+}
+
+/// Result type of a binary operation on operands of the given types.
+pub fn binop_type(op: &BinOp, lhs: Ty, rhs: Ty) -> Ty {
+  let is_dynamic = lhs == Ty::Dynamic || rhs == Ty::Dynamic;
+  match op {
+    // Only `+` may yield something other than a number.
+    BinOp::Add if is_dynamic => Ty::Dynamic,
+    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Rem => Ty::F64,
+    BinOp::Div | BinOp::Exp => Ty::F64,
+    BinOp::Eq
+    | BinOp::Ne
+    | BinOp::SEq
+    | BinOp::SNe
+    | BinOp::Lt
+    | BinOp::Gt
+    | BinOp::Le
+    | BinOp::Ge
+    | BinOp::InstanceOf => Ty::Bool,
+    BinOp::And | BinOp::Or | BinOp::Xor | BinOp::Shl | BinOp::Shr => Ty::Int32,
+    BinOp::ZFShr => Ty::F64,
+    BinOp::LAnd | BinOp::LOr => lhs.join(rhs),
+    BinOp::Comma => rhs,
+    _ => Ty::Unknown,
+  }
+}
+
+/// Whether control may reach the end of the statement `node`, which decides if a function body
+/// implicitly returns `undefined`. Statements other than `if` and blocks are assumed to complete.
+fn can_complete(node: &Node) -> bool {
+  match &node.base {
+    NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => nodes.iter().all(can_complete),
+    NodeBase::If(_, then_, else_) => can_complete(then_) || can_complete(else_),
+    NodeBase::Return(_) | NodeBase::Throw(_) => false,
+    _ => true,
+  }
+}
+
+/// Whether evaluating `node` may assign to the variable `name`. Nested functions are skipped,
+/// since facts are only kept for variables they do not capture.
+fn assigns(node: &Node, name: &str) -> bool {
+  let any = |nodes: &Vec<Node>| nodes.iter().any(|node| assigns(node, name));
+  let binds = |target: &Node| target.bound_names().iter().any(|bound| *bound == name);
+  let element_assigns = |element: &PatternElement| {
+    assigns(&element.target, name) || element.init.as_ref().map_or(false, |init| assigns(init, name))
+  };
+  match &node.base {
+    NodeBase::StatementList(nodes)
+    | NodeBase::Block(nodes)
+    | NodeBase::Array(nodes)
+    | NodeBase::TemplateLiteral(_, nodes) => any(nodes),
+    NodeBase::VarDecl(var, init, _) => {
+      var == name || init.as_ref().map_or(false, |init| assigns(init, name))
+    }
+    NodeBase::Assign(target, value) => {
+      binds(target) || assigns(target, name) || assigns(value, name)
+    }
+    NodeBase::UnaryOp(expr, op) => {
+      let is_update = match op {
+        UnaryOp::PrInc | UnaryOp::PrDec | UnaryOp::PoInc | UnaryOp::PoDec => true,
+        _ => false,
+      };
+      (is_update && binds(expr)) || assigns(expr, name)
+    }
+    NodeBase::ForIn(binding, target, body) | NodeBase::ForOf(binding, target, body) => {
+      binds(binding) || assigns(binding, name) || assigns(target, name) || assigns(body, name)
+    }
+    NodeBase::ForBinding(target, _) => assigns(target, name),
+    NodeBase::ArrayPattern(elements) => elements.iter().flatten().any(element_assigns),
+    NodeBase::ObjectPattern(properties) => {
+      properties.iter().any(|(_, element)| element_assigns(element))
+    }
+    NodeBase::Member(expr, _)
+    | NodeBase::New(expr)
+    | NodeBase::Throw(expr)
+    | NodeBase::Label(_, expr)
+    | NodeBase::Return(Some(expr)) => assigns(expr, name),
+    NodeBase::Index(node1, node2)
+    | NodeBase::While(node1, node2)
+    | NodeBase::DoWhile(node1, node2)
+    | NodeBase::BinaryOp(node1, node2, _) => assigns(node1, name) || assigns(node2, name),
+    NodeBase::If(node1, node2, node3) | NodeBase::TernaryOp(node1, node2, node3) => {
+      assigns(node1, name) || assigns(node2, name) || assigns(node3, name)
+    }
+    NodeBase::For(init, cond, step, body) => {
+      assigns(init, name) || assigns(cond, name) || assigns(step, name) || assigns(body, name)
+    }
+    NodeBase::Try(try_, catch, _, finally) => {
+      assigns(try_, name) || assigns(catch, name) || assigns(finally, name)
+    }
+    NodeBase::Switch(discriminant, clauses) => {
+      assigns(discriminant, name)
+        || clauses.iter().any(|clause| {
+          clause.test.as_ref().map_or(false, |test| assigns(test, name)) || any(&clause.body)
+        })
+    }
+    NodeBase::Call(callee, args) => assigns(callee, name) || any(args),
+    NodeBase::Object(properties) => properties.iter().any(|property| match property {
+      PropertyDefinition::Property(_, value)
+      | PropertyDefinition::MethodDefinition(_, _, value) => assigns(value, name),
+      PropertyDefinition::IdentifierReference(_) => false,
+    }),
+    _ => false,
+  }
+}
+
+/// The greatest integer whose square is at most `x`.
+fn isqrt(x: i64) -> i64 {
+  let mut root = (x as f64).sqrt() as i64;
+  while root * root > x {
+    root -= 1;
+  }
+  while (root + 1) * (root + 1) <= x {
+    root += 1;
+  }
+  root
+}
+
+/// Whether arithmetic on operands of these types may still prove to be on `Int32`s once the
+/// types of the operands not known yet are. Until then, its type is not known either.
+fn may_become_int32(lhs: Ty, rhs: Ty) -> bool {
+  (lhs == Ty::Unknown || rhs == Ty::Unknown) && num_type(lhs).join(num_type(rhs)) == Ty::Int32
+}
+
+/// Booleans take part in arithmetic as the integers 0 and 1.
+fn num_type(ty: Ty) -> Ty {
+  match ty {
+    Ty::Unknown | Ty::Bool => Ty::Int32,
+    ty => ty,
+  }
+}
+
+/// Type of the result of ToNumber on a value of type `ty`.
+fn number_type(ty: Ty) -> Ty {
+  match ty {
+    Ty::Dynamic => Ty::F64,
+    ty => num_type(ty),
+  }
+}
+
+fn collect_function_decls<'a>(
+  node: &'a Node,
+  decls: &mut Vec<(&'a String, &'a Vec<FormalParameter>, &'a Node)>,
+) {
+  match &node.base {
+    NodeBase::StatementList(nodes) | NodeBase::Block(nodes) => {
+      for node in nodes {
+        collect_function_decls(node, decls);
+      }
+    }
+    NodeBase::If(_, then_, else_) => {
+      collect_function_decls(then_, decls);
+      collect_function_decls(else_, decls);
+    }
+    NodeBase::While(_, body) | NodeBase::DoWhile(body, _) | NodeBase::Label(_, body) => {
+      collect_function_decls(body, decls)
+    }
+    NodeBase::For(init, _, step, body) => {
+      collect_function_decls(init, decls);
+      collect_function_decls(step, decls);
+      collect_function_decls(body, decls);
+    }
+    NodeBase::ForIn(_, _, body) | NodeBase::ForOf(_, _, body) => {
+      collect_function_decls(body, decls)
+    }
+    NodeBase::Switch(_, clauses) => {
+      for clause in clauses {
+        for node in &clause.body {
+          collect_function_decls(node, decls);
+        }
+      }
+    }
+    NodeBase::Try(try_, catch, _, finally) => {
+      collect_function_decls(try_, decls);
+      collect_function_decls(catch, decls);
+      collect_function_decls(finally, decls);
+    }
+    NodeBase::FunctionDecl(name, params, body) => decls.push((name, params, &**body)),
+    _ => {}
+  }
+}
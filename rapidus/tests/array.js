@@ -0,0 +1,31 @@
+let a = [1, 2, 'three']
+console.log(a)
+
+let n = 0
+if (a.length == 3) n += 1
+if (a[1] == 2 && a[2] === 'three') n += 2
+if (a[3] === undefined && a[-1] === undefined) n += 4
+
+a[5] = 6
+if (a.length == 6 && a[4] === undefined) n += 8
+
+if (a.push(7, 8) == 8 && a[7] == 8) n += 16
+if (a.pop() == 8 && a.length == 7) n += 32
+if ([].pop() === undefined) n += 64
+
+let sum = 0
+for (let i = 0; i < 10; i++) a[i] = i * i
+a[3]++
+for (let i = 0; i < a.length; i++) sum += a[i]
+if (sum == 286) n += 128
+
+if ('abc'[1] == 'b' && a + '' == '0,1,4,10,16,25,36,49,64,81') n += 256
+
+// Arrays have no holes, so a store far past the end throws rather than filling the gap.
+try {
+  a[1e9] = 1
+} catch (e) {
+  if (e.name == 'RangeError' && a.length == 10) n += 512
+}
+
+return n
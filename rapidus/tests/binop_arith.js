@@ -0,0 +1,5 @@
+let a = 7 / 2
+let b = 2 ** 10
+let c = (1, 2, 3)
+let d = 7.5 % 2
+return a + b + c + d
@@ -0,0 +1,6 @@
+let a = 12 & 10
+let b = 12 | 3
+let c = 12 ^ 10
+let d = 1.9 | 0
+let e = 4294967297 | 0
+return a + b + c + d + e
@@ -0,0 +1,12 @@
+let n = 0
+if (1 < 2) n += 1
+if (2 > 1) n += 2
+if (2 >= 2) n += 4
+if (1 != 2) n += 8
+if (3 === 3) n += 16
+if (true !== 1) n += 32
+if (0.5 < 1) n += 64
+if (1 == true) n += 128
+if (2 < 1) n += 256
+if (1 === 1.5) n += 512
+return n
@@ -0,0 +1,6 @@
+let a = 1 << 4
+let b = (0 - 16) >> 2
+let c = (0 - 1) >>> 28
+let d = (0 - 1) >>> 0
+let e = 1 << 33
+return a + b + c + d + e
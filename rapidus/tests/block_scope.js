@@ -0,0 +1,25 @@
+let x = 1
+let total = 0
+{
+  let x = 10
+  total += x
+  {
+    const x = 100
+    total += x
+  }
+  total += x
+}
+total += x
+
+for (let i = 0; i < 3; i++) total += i
+for (let i = 10; i < 12; i++) total += i
+
+var v = 1
+if (total > 0) {
+  var v = 2
+  let w = v * 1000
+  total += w
+}
+var v
+
+return total + v
@@ -0,0 +1,31 @@
+let sum = 0
+for (let i = 0; i < 100; i++) {
+  if (i % 2 == 0) continue
+  if (i > 10) break
+  sum += i
+}
+
+let n = 0
+while (true) {
+  n += 1
+  if (n < 5) continue
+  break
+}
+
+let pairs = 0
+outer: for (let x = 0; x < 10; x++) {
+  for (let y = 0; y < 10; y++) {
+    if (y > x) continue outer
+    if (x == 5) break outer
+    pairs += 1
+  }
+}
+
+let skipped = 0
+block: {
+  skipped = 1
+  if (pairs > 0) break block
+  skipped = 2
+}
+
+return sum + n + pairs + skipped
@@ -0,0 +1,28 @@
+let total = 0
+function add(n) {
+  total += n
+}
+add(3)
+add(4)
+
+function make(start) {
+  let count = start
+  function inc(step) {
+    count += step
+    function deeper() {
+      return count * 2 + start
+    }
+    return deeper()
+  }
+  inc(1)
+  inc(2)
+  return count + inc(10)
+}
+total += make(5)
+
+function fact(n) {
+  if (n <= 1) return 1
+  return n * fact(n - 1)
+}
+
+return total + fact(5)
@@ -0,0 +1,43 @@
+let fs = []
+for (let i = 0; i < 3; i++) {
+  fs.push(() => i)
+  gc()
+}
+let r = fs[0]() + fs[1]() * 10 + fs[2]() * 100
+
+// Each iteration starts with the value the previous one ended with.
+let gs = []
+for (let i = 0; i < 6; i++) {
+  gs.push(() => i)
+  i++
+}
+r += gs[0]() + gs[1]() + gs[2]()
+
+let hs = []
+for (const x of [4, 5, 6]) {
+  hs.push(() => x)
+}
+r += hs[0]() * hs[2]()
+
+let ks = []
+let n = 0
+while (n < 3) {
+  let m = n * 1000
+  ks.push(() => m)
+  n++
+}
+r += ks[2]()
+
+// Closures created in the same iteration share its bindings.
+let get = null
+let set = null
+for (let i = 0; i < 1; i++) {
+  get = () => i
+  set = v => {
+    i = v
+  }
+}
+set(7)
+r += get()
+
+return r
@@ -0,0 +1,41 @@
+let n = 0
+
+// The body runs once even if the condition is false.
+let runs = 0
+do {
+  runs += 1
+} while (false)
+if (runs == 1) n += 1
+
+let x = 0
+do x += 2; while (x < 9)
+if (x == 10) n += 2
+
+// continue jumps to the condition, break leaves the loop.
+let sum = 0
+let i = 0
+do {
+  i += 1
+  if (i % 2 == 0) continue
+  if (i > 9) break
+  sum += i
+} while (i < 100)
+if (sum == 25 && i == 11) n += 4
+
+// The semicolon after the condition may be omitted on the same line.
+let y = 0
+do y += 1; while (y < 3) y += 10
+if (y == 13) n += 8
+
+let pairs = 0
+outer: do {
+  let j = 0
+  do {
+    j += 1
+    if (j == 3) continue outer
+    pairs += 1
+  } while (true)
+} while (false)
+if (pairs == 2) n += 16
+
+return n
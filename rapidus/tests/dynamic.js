@@ -0,0 +1,15 @@
+function pick(flag) {
+  if (flag) return 1
+  return null
+}
+
+let x = 1
+console.log(x)
+x = true
+console.log(x)
+x = pick(x)
+let sum = x + 2
+if (pick(false) == undefined) {
+  sum = sum + 10
+}
+return sum
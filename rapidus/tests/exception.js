@@ -0,0 +1,84 @@
+function fail(msg) {
+  throw msg
+}
+
+let n = 0
+try {
+  fail('boom')
+  n += 1000
+} catch (e) {
+  if (e == 'boom') n += 1
+}
+
+// Exceptions propagate through function values and methods.
+let thrower = { run: function (v) { fail({ code: v }) } }
+try {
+  thrower.run(42)
+} catch (e) {
+  if (e.code == 42) n += 2
+}
+
+let order = ''
+function cleanup() {
+  try {
+    return 'try'
+  } finally {
+    order += 'f'
+  }
+}
+if (cleanup() == 'try' && order == 'f') n += 4
+
+// A return in finally overrides the completion of the block.
+function overrides() {
+  try {
+    throw 1
+  } catch (e) {
+    return 'catch'
+  } finally {
+    return 'finally'
+  }
+}
+if (overrides() == 'finally') n += 8
+
+let count = 0
+for (let i = 0; i < 5; i++) {
+  try {
+    if (i == 1) continue
+    if (i == 3) break
+  } finally {
+    count++
+  }
+}
+if (count == 4) n += 16
+
+// An exception leaving finally without catch still runs it, and is caught outside.
+let ran = false
+try {
+  try {
+    fail('inner')
+  } finally {
+    ran = true
+  }
+} catch (e) {
+  if (ran && e == 'inner') n += 32
+}
+
+// Rethrowing from catch.
+try {
+  try {
+    fail(1)
+  } catch (e) {
+    throw e + 1
+  }
+} catch (e) {
+  if (e == 2) n += 64
+}
+
+let getter = { get value() { throw 'getter' } }
+try {
+  getter.value
+} catch (e) {
+  if (e == 'getter') n += 128
+}
+
+return n
@@ -0,0 +1,10 @@
+function half(x) {
+  return x * 0.5
+}
+
+let sum = 0
+for (let i = 1; i <= 11; i += 1) {
+  sum += half(1)
+}
+console.log(sum)
+return sum
@@ -0,0 +1,97 @@
+let n = 0
+
+// for-in visits own keys, then inherited ones which are not shadowed.
+let proto = { a: 1, b: 2 }
+let obj = { c: 3, a: 4 }
+obj.__proto__ = proto
+let keys = ''
+for (let k in obj) keys += k
+if (keys == 'cab') n += 1
+
+// Properties deleted before they are reached are skipped,
+// and integer keys come first in ascending order.
+let deleting = { x: 1, y: 2, 2: 'two', 1: 'one' }
+let visited = ''
+for (var key in deleting) {
+  visited += key
+  delete deleting.y
+}
+if (visited == '12x' && key == 'x') n += 2
+
+// Arrays and strings have their indices as keys, and other values have none.
+let indices = ''
+for (const i in [5, 6, 7]) indices += i
+for (const i in 'ab') indices += i
+for (const i in 42) indices += i
+for (const i in null) indices += i
+if (indices == '01201') n += 4
+
+// for-of iterates the elements of arrays and the code points of strings.
+let sum = 0
+for (const x of [1, 2, 3, 4]) sum += x
+let chars = []
+for (const c of 'a😀b') chars.push(c)
+if (sum == 10 && chars.length == 3 && chars[1] == '😀') n += 8
+
+// Other objects are iterated through their '@@iterator' method.
+function range(from, to) {
+  let r = {}
+  r['@@iterator'] = function() {
+    let i = from
+    return {
+      next: function() {
+        i += 1
+        return { done: i > to, value: i - 1 }
+      }
+    }
+  }
+  return r
+}
+let total = 0
+for (let v of range(3, 6)) total += v
+if (total == 12) n += 16
+
+// Destructuring patterns with defaults and holes.
+let pairs = ''
+for (const [a, , b = 'z'] of [['p', 0, 'q'], ['r']]) pairs += a + b
+let people = ''
+for (let { name, age: years = 20 } of [{ name: 'al', age: 30 }, { name: 'bo' }]) {
+  people += name + years
+}
+if (pairs == 'pqrz' && people == 'al30bo20') n += 32
+
+// Existing variables, properties and patterns can be assigned to.
+let last
+let holder = {}
+let p, q
+for (last of [1, 2]) {}
+for (holder.value in { only: 0 }) {}
+for ([p, q] of [[1, 2], [3, 4]]) {}
+if (last == 2 && holder.value == 'only' && p == 3 && q == 4) n += 64
+
+// break and continue, also with labels.
+let found = 0
+outer: for (const row of [[1, 2], [3, 4], [5, 6]]) {
+  for (const x of row) {
+    if (x == 2) continue outer
+    if (x == 5) break outer
+    found += x
+  }
+}
+let skipped = 0
+for (const x of [1, 2, 3, 4]) {
+  if (x % 2 == 0) continue
+  skipped += x
+}
+if (found == 8 && skipped == 4) n += 128
+
+// Elements pushed during the iteration are visited too.
+let growing = [1]
+let count = 0
+for (const x of growing) {
+  count += 1
+  if (x < 5) growing.push(x + 1)
+}
+if (count == 5) n += 256
+
+return n
@@ -0,0 +1,43 @@
+function apply(f, x) {
+  return f(x)
+}
+
+function twice(f) {
+  return x => f(f(x))
+}
+
+function adder(n) {
+  return function (x) {
+    return x + n
+  }
+}
+
+function compose(f, g) {
+  return x => f(g(x))
+}
+
+let add3 = adder(3)
+let r = apply(add3, 4)
+r += twice(add3)(10)
+
+let square = x => x * x
+r += apply(square, 5)
+r += compose(square, add3)(2)
+
+let counter = (function () {
+  let c = 0
+  return () => {
+    c += 1
+    return c
+  }
+})()
+counter()
+counter()
+r += counter()
+
+function lexicalThis() {
+  return (() => this)() === this
+}
+if (lexicalThis() && typeof square == 'function') r += 1000
+
+return r
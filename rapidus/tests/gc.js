@@ -0,0 +1,38 @@
+function make(n) {
+  return { value: n, next: null }
+}
+
+// Most nodes and strings become garbage right away, while every 100th node is kept in a list.
+let list = null
+let length = 0
+for (let i = 0; i < 20000; i++) {
+  let node = make(i)
+  if (i % 100 == 0) {
+    node.next = list
+    list = node
+  }
+  let s = 'x' + i
+  length += s.length
+}
+gc()
+
+// Captured variables live in environment records, which must survive as well.
+function counter() {
+  let count = 0
+  return function () {
+    count++
+    return count
+  }
+}
+let next = counter()
+gc()
+next()
+
+let count = 0
+let total = 0
+while (list !== null) {
+  count++
+  total += list.value
+  list = list.next
+}
+return total + length + count + next()
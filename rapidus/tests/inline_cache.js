@@ -0,0 +1,19 @@
+function sum(p) {
+  return p.x + p.y
+}
+
+// `a` and `b` have different shapes, so the loads in `sum` become polymorphic.
+let a = { x: 1, y: 2 }
+let b = { y: 3, x: 4 }
+let total = 0
+for (let i = 0; i < 100; i++) {
+  total += sum(a) + sum(b)
+  a.x = a.x + 1
+}
+
+// Deleting a property changes the shape, and cached slots must not be reused.
+delete a.x
+a.z = 5
+if (a.x === undefined && a.y == 2 && a.z == 5) total += 1
+
+return total
@@ -0,0 +1,30 @@
+let big = 2147483647
+let n = 0
+if (big + 1 == 2147483648) n += 1
+let m = big
+m++
+if (m == 2147483648) n += 2
+if (big * big == 4611686014132420609) n += 4
+
+// -0 is kept.
+let zero = 0
+if (1 / (zero * -1) < 0 && 1 / -zero < 0) n += 8
+
+let min = -2147483648
+if (-min == 2147483648 && min - 1 == -2147483649) n += 16
+
+// `%` neither traps nor wraps.
+if (min % -1 == 0 && 5 % zero != 5 % zero) n += 32
+if (7 % 3 == 1 && -7 % 3 == -1 && 7.5 % 2 == 1.5) n += 64
+
+// Sums of Int32 arguments which may overflow are done on doubles.
+function add(a, b) {
+  return a + b
+}
+function sub(a, b) {
+  return a - b
+}
+if (add(1, 2) == 3 && add(big, big) == 4294967294 && sub(min, 1) == -2147483649) n += 128
+if (sub(1, 2) == -1 && add(min, -1) == -2147483649 && sub(big, -1) == 2147483648) n += 256
+
+return n
@@ -0,0 +1,15 @@
+function loud(x) {
+  console.log(x)
+  return x
+}
+
+let n = 0
+let a = 0 && loud(5)
+let b = 3 && 5
+let c = 0 || 7
+let d = 2 || loud(7)
+let e = 1.5 && 2
+if (a == 0 && b == 5) n += 1
+if (c == 9 || d == 2) n += 2
+if (c == 9 || d == 9) n += 4
+return a + b + c + d + e + n
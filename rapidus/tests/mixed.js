@@ -0,0 +1,10 @@
+function scale(x, k) {
+  return x * k
+}
+
+let n = 3
+let x = 1
+x = x + 0.5
+let y = scale(x, n) + scale(n, 1)
+console.log(y)
+return y
@@ -0,0 +1,5 @@
+outer: for (let i = 0; i < 10; i++) {
+  let f = () => {
+    break outer
+  }
+}
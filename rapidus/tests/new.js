@@ -0,0 +1,44 @@
+function Point(x, y) {
+  this.x = x
+  this.y = y
+}
+Point.prototype.sum = function () { return this.x + this.y }
+
+let n = 0
+let p = new Point(3, 4)
+if (p.x == 3 && p.sum() == 7) n += 1
+if (p instanceof Point && !({} instanceof Point)) n += 2
+if (p.__proto__ === Point.prototype && Point.prototype.constructor === Point) n += 4
+
+// Returning an object from a constructor replaces `this`; other values are ignored.
+function Wrapper() {
+  this.ignored = true
+  return { wrapped: 1 }
+}
+function Counter() {
+  this.count = 10
+  return 123
+}
+if (new Wrapper().wrapped == 1 && new Counter().count == 10) n += 8
+
+function Animal() { this.legs = 4 }
+Animal.prototype.describe = function () { return this.name + ' has ' + this.legs + ' legs' }
+function Dog(name) { this.name = name }
+Dog.prototype = new Animal()
+let d = new Dog('Rex')
+console.log(d)
+if (d.describe() == 'Rex has 4 legs' && d instanceof Dog && d instanceof Animal) n += 16
+
+// Own properties shadow inherited ones.
+d.legs = 3
+if (d.legs == 3 && Dog.prototype.legs == 4) n += 32
+
+let base = { greet: function () { return 'hi ' + this.who } }
+let derived = { who: 'there' }
+derived.__proto__ = base
+if (derived.greet() == 'hi there') n += 64
+
+let make = function () { this.made = true }
+if (new make().made) n += 128
+
+return n
@@ -0,0 +1,31 @@
+let x = 3
+let o = { a: 1, 'b c': 'two', x, 1.5: true }
+console.log(o)
+
+let n = 0
+if (o.a == 1 && o['b c'] == 'two' && o.x == 3 && o[1.5]) n += 1
+if (o.missing === undefined) n += 2
+
+o.a += 10
+o.a++
+o['d'] = 4
+if (o.a == 12 && o.d == 4) n += 4
+
+if (delete o.a && o.a === undefined && delete o['missing']) n += 8
+
+let counter = {
+  count: 0,
+  get double() { return this.count * 2 },
+  set double(v) { this.count = v / 2 },
+  inc: function () { this.count++; return this },
+}
+counter.inc().inc()
+if (counter.count == 2 && counter.double == 4) n += 16
+counter.double = 10
+if (counter.count == 5) n += 32
+
+let nested = { inner: { value: 7 } }
+nested.inner.value *= 2
+if (nested.inner.value == 14 && typeof nested == 'object') n += 64
+
+return n
@@ -0,0 +1,60 @@
+let n = 0
+
+// Missing arguments are `undefined`, and extra ones are ignored.
+function pair(x, y) {
+  return [x, y]
+}
+let p = pair(1)
+if (p[0] == 1 && p[1] === undefined && pair(1, 2, 3).length == 2) n += 1
+
+// Default values are evaluated at each call, and may refer to earlier parameters.
+let calls = 0
+function next() {
+  calls += 1
+  return calls
+}
+function withDefaults(a, b = a * 2, c = next()) {
+  return a + b + c
+}
+if (withDefaults(1) == 4 && withDefaults(1) == 5 && withDefaults(1, 10, 100) == 111) n += 2
+if (withDefaults(1, undefined, 0) == 3 && calls == 2) n += 4
+
+// Rest parameters collect the remaining arguments.
+function rest(first, ...others) {
+  let sum = first
+  let i = 0
+  while (i < others.length) {
+    sum += others[i]
+    i += 1
+  }
+  return sum * 10 + others.length
+}
+if (rest(1) == 10 && rest(1, 2, 3) == 62) n += 8
+
+// `arguments` holds every argument, including extra ones.
+function count() {
+  return arguments.length
+}
+function second(x) {
+  return arguments[1]
+}
+if (count() == 0 && count(1, 2, 3) == 3 && second(1, 'two') == 'two') n += 16
+
+// Arrow functions see the `arguments` of the enclosing function.
+function outer(x) {
+  let inner = () => arguments[0] + x
+  return inner(100)
+}
+if (outer(5) == 10) n += 32
+
+// Parameters can be assigned.
+function clamp(x) {
+  if (x > 10) x = 10
+  return x
+}
+let f = function (x = 'default', ...xs) {
+  return x + xs.length
+}
+if (clamp(20) == 10 && clamp(3) == 3 && f() == 'default0' && f('v', 1, 2) == 'v2') n += 64
+
+return n
@@ -0,0 +1,40 @@
+let n = 0
+
+let re = /ab+c/i
+if (re.test('xxABBCyy') && !re.test('ac') && re.source == 'ab+c' && re.flags == 'i') n += 1
+
+let m = /(\d{4})-(\d\d)(?:-(\d\d))?/.exec('on 2019-08, maybe')
+if (m[0] == '2019-08' && m[1] == '2019' && m[2] == '08' && m[3] == undefined && m.length == 4) n += 2
+if (/^[a-z_$][\w$]*$/.test('foo_1') && !/^[a-z_$][\w$]*$/.test('1foo') && /a.c/.exec('a\nc') == null) n += 4
+
+// A global regular expression continues from lastIndex.
+let words = /\w+/g
+let found = ''
+for (let w = words.exec('one two  three'); w != null; w = words.exec('one two  three')) {
+  found += w[0] + words.lastIndex + ','
+}
+if (found == 'one3,two7,three14,' && words.lastIndex == 0 && words.global) n += 8
+
+let sticky = /a/y
+if (sticky.test('aab') && sticky.test('aab') && !sticky.test('aab') && sticky.lastIndex == 0) n += 16
+
+if ('a-b-c'.replace('-', '+') == 'a+b-c' && 'a-b-c'.replace(/-/g, '') == 'abc') n += 32
+if ('John Smith'.replace(/(\w+)\s(\w+)/, '$2, $1') == 'Smith, John' && 'abc'.replace(/b/, '[$`|$&|$\'|$$]') == 'a[a|b|c|$]c') n += 64
+
+let all = 'A1b22C333'.match(/[a-c]\d+/gi)
+let first = 'x=1, y=2'.match(/(\w)=(\d)/)
+if (all.length == 3 && all[2] == 'C333' && first[2] == '1' && 'abc'.match(/z/g) == null) n += 128
+
+let lines = 'first\nsecond\nthird'.match(/^\w+$/gm)
+if (lines.length == 3 && lines[1] == 'second' && /^second$/.test('first\nsecond') == false) n += 256
+
+// A slash after an operand is a division.
+let x = 10, y = 2
+if (x / y / 5 == 1 && (x) / y == 5 && '/'.replace(/\//, '\\') == '\\') n += 512
+
+// Quantifiers repeat over long inputs.
+let long = 'a'
+for (let i = 0; i < 17; i++) long += long
+if (/^a*$/.test(long) && /.+/.exec(long + '\nb')[0] == long && /^(?:aa)+?$/.test(long)) n += 1024
+
+return n
@@ -0,0 +1,20 @@
+let name = 'rapidus'
+let s = 'Hello, ' + name + '!'
+console.log(s)
+console.log('x =', 1.5, true, null)
+
+let n = 0
+if (s === 'Hello, rapidus!') n += 1
+if (s.length == 15) n += 2
+if ('abc' < 'abd') n += 4
+if ('10' == 10) n += 8
+if (1 + '2' === '12') n += 16
+if ('3' * '4' == 12) n += 32
+if (typeof s == 'string') n += 64
+
+let t = ''
+for (let i = 0; i < 3; i++) t = t + i
+if (t == '012') n += 128
+if (!'') n += 256
+
+return n
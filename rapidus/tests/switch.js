@@ -0,0 +1,123 @@
+let n = 0
+
+// Dense integer cases are dispatched through a jump table.
+function dense(x) {
+  switch (x) {
+    case 0: return 10
+    case 1: return 11
+    case 2:
+    case 3: return 13
+    case 5: return 15
+    case -1: return 9
+    default: return 0
+  }
+}
+if (dense(0) == 10 && dense(1) == 11 && dense(2) == 13 && dense(3) == 13) n += 1
+if (dense(4) == 0 && dense(5) == 15 && dense(-1) == 9 && dense(6) == 0 && dense(-100) == 0) n += 2
+
+// Sparse and mixed cases are compared one by one.
+function sparse(x) {
+  switch (x) {
+    case 1: return 'one'
+    case 1000: return 'thousand'
+    case 'a': return 'letter'
+    case 1.5: return 'half'
+  }
+  return 'none'
+}
+if (sparse(1) == 'one' && sparse(1000) == 'thousand' && sparse('a') == 'letter') n += 4
+if (sparse(1.5) == 'half' && sparse('1') == 'none' && sparse(2) == 'none') n += 8
+
+// Control falls through until a break, and default may come first.
+function fall(x) {
+  let s = ''
+  switch (x) {
+    default:
+      s += 'd'
+    case 1:
+      s += '1'
+    case 2:
+      s += '2'
+      break
+    case 3:
+      s += '3'
+  }
+  return s
+}
+if (fall(1) == '12' && fall(2) == '2' && fall(3) == '3' && fall(9) == 'd12') n += 16
+
+// Case tests are evaluated in order until one matches.
+let evaluated = 0
+function test(v) {
+  evaluated += 1
+  return v
+}
+switch (2) {
+  case test(1):
+  case test(2):
+    break
+  case test(3):
+}
+if (evaluated == 2) n += 32
+
+// break leaves the switch, while continue applies to the enclosing loop.
+let sum = 0
+for (let i = 0; i < 6; i++) {
+  switch (i % 3) {
+    case 0:
+      continue
+    case 1:
+      sum += 1
+      break
+    default:
+      sum += 10
+  }
+  sum += 100
+}
+if (sum == 422) n += 64
+
+// A labeled break may leave a loop from inside a switch.
+let steps = 0
+outer: while (true) {
+  steps += 1
+  switch (steps) {
+    case 3:
+      break outer
+  }
+}
+if (steps == 3) n += 128
+
+// The clauses share one block scope.
+switch (1) {
+  case 1:
+    let shared = 7
+  case 2:
+    if (shared == 7) n += 256
+}
+
+let duplicate = 0
+switch (2) {
+  case 2: duplicate = 1; break
+  case 2: duplicate = 2; break
+  case 3: duplicate = 3; break
+  case 4: duplicate = 4; break
+  case 5: duplicate = 5; break
+}
+if (duplicate == 1) n += 512
+
+// A loop counter is an Int32, so a switch on it goes through a jump table as well.
+let counted = 0
+for (let i = -2; i < 9; i++) {
+  switch (i) {
+    case 0: counted += 1; break
+    case 1: counted += 10; break
+    case 2:
+    case 3: counted += 100; break
+    case 5: counted += 1000; break
+    case 6: continue
+    default: counted += 10000
+  }
+}
+if (counted == 51211) n += 1024
+
+return n
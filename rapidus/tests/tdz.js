@@ -0,0 +1,5 @@
+let x = 1
+{
+  x = 2
+  let x = 3
+}
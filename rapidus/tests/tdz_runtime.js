@@ -0,0 +1,35 @@
+// A function declared before a `let` may be called while the binding is in its TDZ.
+let n = 0
+function read() { return x }
+try {
+  read()
+  n += 1000
+} catch (e) {
+  if (e.name == 'ReferenceError') n += 1
+}
+let x = 2
+n += read()
+
+function write(v) { y = v }
+try {
+  write(1000)
+} catch (e) {
+  if (e.name == 'ReferenceError') n += 4
+}
+let y = 0
+write(8)
+n += y
+
+// The bindings of a block are in their TDZ every time it is entered.
+for (let i = 0; i < 2; i++) {
+  let f = () => z
+  try {
+    n += f()
+  } catch (e) {
+    if (e.name == 'ReferenceError') n += 16
+  }
+  const z = 32
+  n += f()
+}
+
+return n
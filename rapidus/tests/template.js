@@ -0,0 +1,42 @@
+let n = 0
+let x = 3
+let name = 'world'
+
+if (`x = ${x}` == 'x = 3' && `plain` == 'plain' && `` == '') n += 1
+if (`${x}${x + 1}` == '34' && `a${'b'}c${[1, 2]}d` == 'abc1,2d') n += 2
+if (`nested ${`inner ${name}`}!` == 'nested inner world!') n += 4
+if (`object ${{ a: x }.a}` == 'object 3' && `${true} ${null} ${undefined}` == 'true null undefined') n += 8
+
+let lines = `one
+two\tthree`
+if (lines == 'one\ntwo\tthree') n += 16
+
+function tag(strings, a, b) {
+  return strings.length + ':' + strings[0] + a + strings[1] + b + strings[2]
+}
+if (tag`<${1}|${2}>` == '3:<1|2>') n += 32
+
+let upper = {
+  prefix: '!',
+  tag: function (strings, value) { return this.prefix + strings[0] + value }
+}
+if (upper.tag`hi ${name}` == '!hi world') n += 64
+
+// The strings array carries the raw strings, and is the same array every time
+// the template is evaluated.
+function raw(strings) {
+  return strings.raw[0] + strings[0].length
+}
+if (raw`a\tb` == 'a\\tb3') n += 128
+
+let seen = []
+function remember(strings) {
+  seen.push(strings)
+}
+for (let i = 0; i < 2; i++) {
+  remember`same`
+}
+remember`same`
+if (seen[0] === seen[1] && seen[0] !== seen[2]) n += 256
+
+return n
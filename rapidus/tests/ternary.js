@@ -0,0 +1,12 @@
+function fib(n) {
+  return n < 2 ? n : fib(n - 1) + fib(n - 2)
+}
+
+function half_or_zero(x) {
+  return x > 0 ? x / 2 : 0
+}
+
+let a = fib(10)
+let b = half_or_zero(5) + half_or_zero(-3)
+let c = a > 50 ? (b > 2 ? 100 : 200) : 300
+return a + b + c
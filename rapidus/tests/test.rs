@@ -1,8 +1,13 @@
 extern crate rapidus;
+use rapidus::error::CompileErrorKind;
 use rapidus::executer;
 use rapidus::executer::{ConcreteValue, GenericValue};
+use rapidus::parser;
+use rapidus::runtime;
+use rapidus::scope;
+use rapidus::typing::{Ty, TypeInference, TypeInfo};
 
-fn test_file(file_name: &str, expected: i32) {
+fn test_file(file_name: &str, expected: f64) {
   let mut m = match executer::compile_file(format!("tests/{}", file_name)) {
     Ok(m) => m,
     Err(e) => panic!("Failed to construct module. {}", e),
@@ -11,29 +16,335 @@ fn test_file(file_name: &str, expected: i32) {
     Ok(v) => v,
     Err(e) => panic!("Failed in interpreter. {}", e),
   };
-  if res != ConcreteValue::Int32(expected) {
-    panic!("expected Int32({}), but {:?}", expected, res);
+  if res != ConcreteValue::F64(expected) {
+    panic!("expected F64({}), but {:?}", expected, res);
   }
   let res = match executer::execute_jit(&mut m) {
     Ok(v) => v,
     Err(e) => panic!("Failed to jit compile. {}", e),
   };
-  if res != GenericValue::Int32(expected) {
-    panic!("expected Int32({}), but {:?}", expected, res);
+  if res != GenericValue::F64(expected) {
+    panic!("expected F64({}), but {:?}", expected, res);
   }
 }
 
+fn assert_compile_error(file_name: &str, kind: CompileErrorKind) {
+  match executer::compile_file(format!("tests/{}", file_name)) {
+    Ok(_) => panic!("{} should not compile", file_name),
+    Err(err) => assert_eq!(err.kind, kind, "{}", err),
+  }
+}
+
+fn infer_file(file_name: &str) -> TypeInfo {
+  let mut parser = parser::Parser::load_module(format!("tests/{}", file_name)).unwrap();
+  let mut node = parser.parse_all().unwrap();
+  let scope_info = scope::resolve_scopes("main", &mut node).unwrap();
+  TypeInference::new(&scope_info).run("main", &node)
+}
+
 #[test]
 fn rapidus_fibo() {
-  test_file("fibo.js", 55);
+  test_file("fibo.js", 55.0);
 }
 
 #[test]
 fn rapidus_while() {
-  test_file("while.js", 9);
+  test_file("while.js", 9.0);
 }
 
 #[test]
 fn rapidus_prime() {
-  test_file("prime.js", 19);
+  test_file("prime.js", 19.0);
+}
+
+#[test]
+fn rapidus_float() {
+  test_file("float.js", 5.5);
+}
+
+#[test]
+fn rapidus_mixed_types() {
+  test_file("mixed.js", 7.5);
+}
+
+#[test]
+fn typing_fibo() {
+  let info = infer_file("fibo.js");
+  let fibo = info.function("main.fibo");
+  assert_eq!(fibo.params, vec![Ty::Int32]);
+  // The range of a recursive call is not bounded, so the sum may overflow.
+  assert_eq!(fibo.ret, Ty::F64);
+  assert_eq!(info.function("main").vars["x"], Ty::F64);
+}
+
+#[test]
+fn typing_prime() {
+  let info = infer_file("prime.js");
+  let prime = info.function("main.prime");
+  assert_eq!(prime.params, vec![Ty::Int32]);
+  assert_eq!(prime.vars["k"], Ty::Int32);
+  assert_eq!(info.function("main").vars["i"], Ty::Int32);
+}
+
+#[test]
+fn rapidus_int32_overflow() {
+  test_file("int32_overflow.js", 511.0);
+}
+
+#[test]
+fn typing_int32_overflow() {
+  let info = infer_file("int32_overflow.js");
+  let main = info.function("main");
+  assert_eq!(main.vars["big"], Ty::Int32);
+  assert_eq!(main.vars["min"], Ty::Int32);
+  assert_eq!(main.vars["m"], Ty::F64);
+  let add = info.function("main.add");
+  assert_eq!(add.params, vec![Ty::Int32, Ty::Int32]);
+  assert_eq!(add.ret, Ty::F64);
+}
+
+#[test]
+fn rapidus_undefined_value() {
+  test_file("undefined_value.js", 15.0);
+}
+
+#[test]
+fn typing_undefined_value() {
+  let info = infer_file("undefined_value.js");
+  assert_eq!(info.function("main").vars["u"], Ty::Dynamic);
+  assert_eq!(info.function("main.nothing").ret, Ty::Dynamic);
+  assert_eq!(info.function("main.bare").ret, Ty::Dynamic);
+}
+
+#[test]
+fn typing_mixed_types() {
+  let info = infer_file("mixed.js");
+  let main = info.function("main");
+  assert_eq!(main.vars["n"], Ty::Int32);
+  assert_eq!(main.vars["x"], Ty::F64);
+  assert_eq!(info.function("main.scale").params, vec![Ty::F64, Ty::Int32]);
+  assert_eq!(info.function("main.scale").ret, Ty::F64);
+}
+
+#[test]
+fn rapidus_dynamic() {
+  test_file("dynamic.js", 13.0);
+}
+
+#[test]
+fn typing_dynamic() {
+  let info = infer_file("dynamic.js");
+  assert_eq!(info.function("main").vars["x"], Ty::Dynamic);
+  assert_eq!(info.function("main.pick").ret, Ty::Dynamic);
+  assert_eq!(info.function("main.pick").params, vec![Ty::Dynamic]);
+}
+
+#[test]
+fn rapidus_binop_arith() {
+  test_file("binop_arith.js", 1032.0);
+}
+
+#[test]
+fn rapidus_binop_compare() {
+  test_file("binop_compare.js", 255.0);
+}
+
+#[test]
+fn rapidus_binop_bitwise() {
+  test_file("binop_bitwise.js", 31.0);
+}
+
+#[test]
+fn rapidus_binop_shift() {
+  test_file("binop_shift.js", 4294967324.0);
+}
+
+#[test]
+fn rapidus_logical() {
+  test_file("logical.js", 19.0);
+}
+
+#[test]
+fn rapidus_unary() {
+  test_file("unary.js", 89.5);
+}
+
+#[test]
+fn rapidus_typeof() {
+  test_file("typeof.js", 7.0);
+}
+
+#[test]
+fn rapidus_ternary() {
+  test_file("ternary.js", 157.5);
+}
+
+#[test]
+fn rapidus_break_continue() {
+  test_file("break_continue.js", 46.0);
+}
+
+#[test]
+fn rapidus_invalid_label() {
+  assert_compile_error("undefined_label.js", CompileErrorKind::SyntaxError);
+  assert_compile_error("nested_label.js", CompileErrorKind::SyntaxError);
+}
+
+#[test]
+fn rapidus_block_scope() {
+  test_file("block_scope.js", 2147.0);
+}
+
+#[test]
+fn rapidus_invalid_scope() {
+  assert_compile_error("tdz.js", CompileErrorKind::ReferenceError);
+  assert_compile_error("const_assign.js", CompileErrorKind::TypeError);
+  assert_compile_error("duplicate_let.js", CompileErrorKind::SyntaxError);
+}
+
+#[test]
+fn rapidus_tdz_runtime() {
+  test_file("tdz_runtime.js", 111.0);
+}
+
+#[test]
+fn rapidus_closure() {
+  test_file("closure.js", 176.0);
+}
+
+#[test]
+fn scope_closure() {
+  let mut parser = parser::Parser::load_module("tests/closure.js".to_string()).unwrap();
+  let mut node = parser.parse_all().unwrap();
+  let info = scope::resolve_scopes("main", &mut node).unwrap();
+  assert_eq!(info.function("main").captured, vec!["total".to_string()]);
+  assert_eq!(
+    info.function("main.make").captured,
+    vec!["count".to_string(), "start".to_string()]
+  );
+  assert_eq!(info.function("main.make.inc.deeper").outer["start"], 2);
+}
+
+#[test]
+fn rapidus_closure_loop() {
+  test_file("closure_loop.js", 2250.0);
+}
+
+#[test]
+fn scope_closure_loop() {
+  let mut parser = parser::Parser::load_module("tests/closure_loop.js".to_string()).unwrap();
+  let mut node = parser.parse_all().unwrap();
+  let info = scope::resolve_scopes("main", &mut node).unwrap();
+  assert!(info.function("main").captured.is_empty());
+  assert_eq!(info.function("main").blocks.len(), 5);
+}
+
+#[test]
+fn rapidus_function_value() {
+  test_file("function_value.js", 1076.0);
+}
+
+#[test]
+fn typing_function_value() {
+  let info = infer_file("function_value.js");
+  assert!(!info.function("main.adder").is_value);
+  assert!(!info.function("main.twice").is_value);
+  assert_eq!(info.function("main.apply").params[0], Ty::Dynamic);
+  assert_eq!(info.function_values().len(), 7);
+}
+
+#[test]
+fn rapidus_compile_error() {
+  assert_compile_error("undefined_var.js", CompileErrorKind::ReferenceError);
+  assert_compile_error("unsupported.js", CompileErrorKind::Unsupported);
+  assert_compile_error("void_value.js", CompileErrorKind::Unsupported);
+}
+
+#[test]
+fn rapidus_gc() {
+  test_file("gc.js", 2099092.0);
+  // Each test runs on its own thread, which has its own heap.
+  let stats = runtime::gc::stats();
+  assert!(stats.collections >= 4);
+  assert!(stats.freed >= 70000);
+}
+
+#[test]
+fn rapidus_string() {
+  test_file("string.js", 511.0);
+}
+
+#[test]
+fn rapidus_array() {
+  test_file("array.js", 1023.0);
+}
+
+#[test]
+fn rapidus_object() {
+  test_file("object.js", 127.0);
+}
+
+#[test]
+fn rapidus_params() {
+  test_file("params.js", 127.0);
+}
+
+#[test]
+fn rapidus_template() {
+  test_file("template.js", 511.0);
+}
+
+#[test]
+fn rapidus_regexp() {
+  test_file("regexp.js", 2047.0);
+}
+
+#[test]
+fn rapidus_switch() {
+  test_file("switch.js", 2047.0);
+}
+
+#[test]
+fn rapidus_do_while() {
+  test_file("do_while.js", 31.0);
+}
+
+#[test]
+fn rapidus_for_in_of() {
+  test_file("for_in_of.js", 511.0);
+}
+
+#[test]
+fn rapidus_new() {
+  test_file("new.js", 255.0);
+}
+
+#[test]
+fn rapidus_exception() {
+  test_file("exception.js", 255.0);
+}
+
+#[test]
+fn rapidus_type_error() {
+  test_file("type_error.js", 255.0);
+}
+
+#[test]
+fn rapidus_uncaught_exception() {
+  let mut m = executer::compile_file("tests/uncaught.js").unwrap();
+  assert_eq!(
+    executer::execute_interpreter(&mut m),
+    Err("Uncaught exception thrown at line 2: oops".to_string())
+  );
+}
+
+#[test]
+fn rapidus_inline_cache() {
+  test_file("inline_cache.js", 5951.0);
+  let mut m = executer::compile_file("tests/inline_cache.js").unwrap();
+  executer::execute_interpreter(&mut m).unwrap();
+  executer::execute_jit(&mut m).unwrap();
+  // Each cache counts its own hits, so tests running at the same time do not add to them.
+  let (hits, _) = runtime::ic::stats(m.inline_caches());
+  assert!(hits >= 700);
 }
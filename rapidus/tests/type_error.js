@@ -0,0 +1,60 @@
+let n = 0
+let o = { f: 1 }
+try {
+  o.f()
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 1
+}
+
+try {
+  o.missing.x
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 2
+}
+
+try {
+  o.missing.x = 1
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 4
+}
+
+try {
+  delete o.missing.x
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 8
+}
+
+let arrow = () => 1
+try {
+  new arrow()
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 16
+}
+
+try {
+  o instanceof o
+  n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 32
+}
+
+// Errors thrown by builtin methods are caught too.
+try {
+  'a'.match('(')
+  n += 1000
+} catch (e) {
+  if (e.name == 'SyntaxError') n += 64
+}
+
+try {
+  for (let x of o) n += 1000
+} catch (e) {
+  if (e.name == 'TypeError') n += 128
+}
+
+return n
@@ -0,0 +1,16 @@
+let n = 0
+let v = 1
+let names = typeof v + typeof 'a' + typeof {} + typeof undefined + typeof nope
+names += typeof (() => 1) + typeof true
+if (names == 'numberstringobjectundefinedundefinedfunctionboolean') n += 1
+
+let t = typeof v
+if (t == 'number' && typeof t == 'string') n += 2
+
+// The type of a dynamically typed value is looked up at run time.
+let o = { kind: typeof null, f: function () {} }
+if (o.kind == 'object' && typeof o.kind + typeof o.f + typeof o.g == 'stringfunctionundefined') {
+  n += 4
+}
+
+return n
@@ -0,0 +1,22 @@
+let n = 0
+for (let i = 0; i < 10; i++) n += 1
+let j = 5
+let a = j++
+let b = ++j
+let c = j--
+let d = --j
+let e = -d
+let f = +true
+let g = !0
+let h = ~5
+let k = 0
+if (typeof j === 'number') k += 1
+if (typeof true == 'boolean') k += 2
+if (typeof null === 'object') k += 4
+if (typeof undefined === 'undefined') k += 8
+if (typeof j !== 'string') k += 16
+let v = void 0
+if (v === undefined) k += 32
+let x = 0.5
+x++
+return n + a + b + c + d + e + f + g + h + k + x
@@ -0,0 +1,5 @@
+function f() {
+  throw "oops"
+}
+f()
+return 1
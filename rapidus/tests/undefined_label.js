@@ -0,0 +1,3 @@
+for (let i = 0; i < 10; i++) {
+  break label2
+}
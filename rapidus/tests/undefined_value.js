@@ -0,0 +1,14 @@
+function nothing() {}
+function bare(x) {
+  if (x) return
+  return 1
+}
+
+let u
+let n = 0
+if (u === undefined) n += 1
+if (nothing() === undefined) n += 2
+if (bare(true) === undefined && bare(false) == 1) n += 4
+if (typeof u == 'undefined') n += 8
+
+return n
@@ -0,0 +1 @@
+let r = console.log(1)